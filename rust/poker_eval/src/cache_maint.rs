@@ -0,0 +1,139 @@
+use log::info;
+use poker_eval::{
+    board_eval_cache_redb::{get_data_path, EvalCacheEnum, ProduceFlopTexture},
+    board_hc_eval_cache_redb::{
+        ProduceHandStrength, ProduceMonteCarloEval, ProducePartialRankCards,
+    },
+    default_num_threads,
+    eval_cache_maintenance::{
+        cache_stats, drop_stale, precompute, verify_cache, MaintainedProducer,
+    },
+    init_logger, BatchedReDb, PokerError, DEFAULT_SEED, DEFAULT_WRITE_BATCH_SIZE,
+};
+
+/*
+Warms, inspects and invalidates the redb caches in DATA_DIR
+
+cargo run --release --bin cache_maint -- precompute flop_texture all
+cargo run --release --bin cache_maint -- precompute monte_carlo flop 100000 3
+cargo run --release --bin cache_maint -- stats partial_rank 10000
+cargo run --release --bin cache_maint -- verify hand_strength 1000
+cargo run --release --bin cache_maint -- drop-stale partial_rank
+
+Caches are partial_rank, flop_texture, monte_carlo and hand_strength.
+precompute can be stopped and rerun, keys already in the cache are skipped.
+*/
+
+const USAGE: &str =
+    "Usage: cache_maint precompute <cache> <flop|turn|river|all> [max to compute] [num players] \
+| stats <cache> [num samples] | verify <cache> [max entries] | drop-stale <cache>";
+
+fn main() {
+    main_impl().unwrap();
+}
+
+fn main_impl() -> Result<(), PokerError> {
+    init_logger();
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        return Err(PokerError::from_str(USAGE));
+    }
+
+    let cache_name: EvalCacheEnum = args[2].parse()?;
+
+    match cache_name {
+        EvalCacheEnum::PartialRank => run::<ProducePartialRankCards>(&args),
+        EvalCacheEnum::FlopTexture => run::<ProduceFlopTexture>(&args),
+        EvalCacheEnum::MonteCarloEval => run::<ProduceMonteCarloEval>(&args),
+        EvalCacheEnum::HandStrength => run::<ProduceHandStrength>(&args),
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(args: &[String], i: usize) -> Result<Option<T>, PokerError> {
+    match args.get(i) {
+        Some(s) => s
+            .parse()
+            .map(Some)
+            .map_err(|_| PokerError::from_string(format!("Invalid number {}", s))),
+        None => Ok(None),
+    }
+}
+
+fn run<P: MaintainedProducer>(args: &[String]) -> Result<(), PokerError> {
+    let cache_name = P::cache_name();
+    let default_num_players = P::fixed_num_players().unwrap_or(2);
+
+    //BatchedReDb::new refuses a stale stamp, stats and drop-stale open by path to still work then
+    let store: BatchedReDb<P::Key> = match args[1].as_str() {
+        "stats" | "drop-stale" => {
+            BatchedReDb::open(&get_data_path(cache_name)?, DEFAULT_WRITE_BATCH_SIZE)?
+        }
+        _ => BatchedReDb::new(cache_name, DEFAULT_WRITE_BATCH_SIZE)?,
+    };
+
+    match args[1].as_str() {
+        "precompute" => {
+            let board_card_counts = match args.get(3).map(|s| s.as_str()) {
+                Some("flop") => vec![3],
+                Some("turn") => vec![4],
+                Some("river") => vec![5],
+                Some("all") => vec![3, 4, 5],
+                _ => return Err(PokerError::from_str(USAGE)),
+            };
+            let max_to_compute: Option<usize> = parse_arg(args, 4)?;
+            let num_players = parse_arg(args, 5)?.unwrap_or(default_num_players);
+
+            for num_board_cards in board_card_counts {
+                let report = precompute::<P>(
+                    &store,
+                    num_board_cards,
+                    num_players,
+                    max_to_compute,
+                    default_num_threads(),
+                )?;
+                info!("{}", report);
+            }
+        }
+        "stats" => {
+            let num_samples = parse_arg(args, 3)?.unwrap_or(10_000);
+            let stats = cache_stats::<P>(&store, default_num_players, num_samples, DEFAULT_SEED)?;
+            let file_size = std::fs::metadata(get_data_path(cache_name)?)
+                .map(|m| m.len())
+                .unwrap_or(0);
+            info!(
+                "{}  file size {:.1} MB",
+                stats,
+                file_size as f64 / 1_000_000.0
+            );
+        }
+        "verify" => {
+            let report = verify_cache::<P>(&store, parse_arg(args, 3)?)?;
+            info!("{}", report);
+            if report.num_mismatches > 0 {
+                return Err(PokerError::from_string(format!(
+                    "{} entries of the {} cache are wrong, run drop-stale after bumping its schema version",
+                    report.num_mismatches,
+                    cache_name.name()
+                )));
+            }
+        }
+        "drop-stale" => {
+            let num_dropped = drop_stale::<P>(&store)?;
+            info!(
+                "Dropped {} entries from the {} cache, now at schema version {}",
+                num_dropped,
+                cache_name.name(),
+                cache_name.get_schema_version()
+            );
+        }
+        s => {
+            return Err(PokerError::from_string(format!(
+                "Unknown command {}. {}",
+                s, USAGE
+            )))
+        }
+    }
+
+    Ok(())
+}
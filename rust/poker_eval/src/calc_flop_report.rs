@@ -0,0 +1,66 @@
+use std::fs::File;
+
+use log::info;
+use poker_eval::{build_flop_report, init_logger, PokerError, CANONICAL_FLOPS, DEFAULT_SEED};
+
+/*
+How range A does vs range B on all 1,755 distinct flops, grouped by flop texture
+
+cargo run --release --bin calc_flop_report -- "22+,A2s+,KTs+,ATo+" "22+,A2+,K9+,QT+,JT" 10000 /tmp/flop_report
+
+Writes /tmp/flop_report_flops.csv, /tmp/flop_report_textures.csv and /tmp/flop_report.json
+*/
+
+fn main() {
+    main_impl().unwrap();
+}
+
+fn main_impl() -> Result<(), PokerError> {
+    init_logger();
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        return Err(PokerError::from_str(
+            "Usage: calc_flop_report <range a> <range b> [iterations per flop] [output prefix]",
+        ));
+    }
+    let range_a = &args[1];
+    let range_b = &args[2];
+    let num_iterations_per_flop = match args.get(3) {
+        Some(s) => s
+            .parse()
+            .map_err(|_| PokerError::from_string(format!("Invalid iterations {}", s)))?,
+        None => 10_000,
+    };
+    let output_prefix = args.get(4).map_or("flop_report", |s| s.as_str());
+
+    let report = build_flop_report(
+        range_a,
+        range_b,
+        &CANONICAL_FLOPS,
+        num_iterations_per_flop,
+        DEFAULT_SEED,
+    )?;
+
+    let create = |suffix: &str| {
+        let path = format!("{}{}", output_prefix, suffix);
+        info!("Writing {}", path);
+        File::create(&path)
+            .map_err(|e| PokerError::from_string(format!("Unable to create {}: {}", path, e)))
+    };
+
+    report.write_flops_csv(create("_flops.csv")?)?;
+    report.write_texture_csv(create("_textures.csv")?)?;
+    report.write_json(create(".json")?)?;
+
+    for row in report.by_texture() {
+        info!(
+            "{:45} {:5} of 22,100 flops  equity {:.2}%",
+            row.texture.to_string(),
+            row.weight,
+            row.stats.equity * 100.0
+        );
+    }
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::{fmt::Display, iter::Cloned, slice::Iter, str::FromStr};
 
 use num_integer::binomial;
 
-use crate::{Card, Deck, HoleCards, PokerError, Round};
+use crate::{Card, Deck, HoleCards, PokerError, Round, ALL_CARDS};
 
 pub struct Board {
     cards: Vec<Card>,
@@ -126,6 +126,39 @@ impl Board {
         index
     }
 
+    //The inverse of get_index, the cards are highest first
+    pub fn from_index(index: u32) -> Result<Board, PokerError> {
+        let num_cards = index >> 27;
+        if num_cards > 7 {
+            return Err(PokerError::from_string(format!(
+                "Invalid board index {}, too many cards",
+                index
+            )));
+        }
+
+        let mut remaining = index & ((1 << 27) - 1);
+        let mut cards = Vec::with_capacity(num_cards as usize);
+        for dim in (1..=num_cards).rev() {
+            //The largest card with binomial(card, dim) <= remaining
+            let mut card = dim - 1;
+            while card + 1 < ALL_CARDS.len() as u32 && binomial(card + 1, dim) <= remaining {
+                card += 1;
+            }
+            remaining -= binomial(card, dim);
+            cards.push(ALL_CARDS[card as usize]);
+        }
+
+        let mut board = Board::new_from_cards(&cards);
+        if remaining != 0 || board.get_index() != index {
+            return Err(PokerError::from_string(format!(
+                "Invalid board index {}",
+                index
+            )));
+        }
+
+        Ok(board)
+    }
+
     pub fn as_slice_card(&self) -> &[Card] {
         &self.cards
     }
@@ -185,3 +218,29 @@ impl Display for Board {
         write!(f, "{}", s.trim())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    #[test]
+    fn test_board_from_index() {
+        for s in [
+            "Ah 7d 2c",
+            "2c 3c 4c 5c",
+            "As Ks Qs Js Ts",
+            "2c Ah Kd 3s 9h 8c 7d",
+        ] {
+            let mut board = Board::try_from(s).unwrap();
+            let index = board.get_index();
+            let decoded = Board::from_index(index).unwrap();
+            assert_eq!(
+                decoded.as_slice_card().iter().sorted().collect_vec(),
+                board.as_slice_card().iter().sorted().collect_vec()
+            );
+        }
+        assert!(Board::from_index(u32::MAX).is_err());
+    }
+}
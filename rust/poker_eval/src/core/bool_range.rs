@@ -2,7 +2,8 @@ use std::str::FromStr;
 
 use crate::{
     pre_calc::{NUMBER_OF_CARDS, NUMBER_OF_HOLE_CARDS, NUMBER_OF_SUITS},
-    Card, CardValue, CardValueRange, HoleCards, PokerError, Suit, ALL_HOLE_CARDS,
+    Card, CardUsedType, CardValue, CardValueRange, HoleCards, PokerError, Suit, ALL_HOLE_CARDS,
+    SIMPLE_RANGE_INDEX_LEN,
 };
 use bitvec::prelude::*;
 use itertools::Itertools;
@@ -174,81 +175,6 @@ impl BoolRange {
         result / indices.len() as f32
     }
 
-    #[inline]
-    fn update_with_singleton(&mut self, combo: &str, enabled: bool) -> Result<(), PokerError> {
-        let (rank1, rank2, suitedness) = parse_singleton(combo)?;
-        trace!(
-            "update_with_singleton rank1: {}, rank2: {}, suitedness: {:?}",
-            rank1,
-            rank2,
-            suitedness
-        );
-        self.set_enabled(&indices_with_suitedness(rank1, rank2, suitedness), enabled);
-        Ok(())
-    }
-
-    #[inline]
-    fn update_with_plus_range(&mut self, range: &str, enabled: bool) -> Result<(), PokerError> {
-        let lowest_combo = &range[..range.len() - 1];
-        let (rank1, rank2, suitedness) = parse_singleton(lowest_combo)?;
-        assert!(rank1 >= rank2);
-
-        let gap = (rank1 as u8) - (rank2 as u8);
-        if gap <= 1 {
-            let rank1_u8 = rank1 as u8;
-            // pair and connector (e.g.,  88+, T9s+)
-            for i in rank1_u8..13 {
-                let r1: CardValue = i.try_into().unwrap();
-                let r2: CardValue = (i - gap).try_into().unwrap();
-                self.set_enabled(&indices_with_suitedness(r1, r2, suitedness), enabled);
-            }
-        } else {
-            // otherwise (e.g., ATo+)
-            for i in (rank2 as u8)..(rank1 as u8) {
-                let r2: CardValue = i.try_into().unwrap();
-                self.set_enabled(&indices_with_suitedness(rank1, r2, suitedness), enabled);
-            }
-        }
-        Ok(())
-    }
-
-    #[inline]
-    fn update_with_dash_range(&mut self, range: &str, enabled: bool) -> Result<(), PokerError> {
-        let combo_pair = range.split('-').collect::<Vec<_>>();
-        let (rank11, rank12, suitedness) = parse_singleton(combo_pair[0])?;
-        let (rank21, rank22, suitedness2) = parse_singleton(combo_pair[1])?;
-        let gap = (rank11 as u8) - (rank12 as u8);
-        let gap2 = (rank21 as u8) - (rank22 as u8);
-        if suitedness != suitedness2 {
-            Err(format!("Suitedness does not match: {range}").into())
-        } else if gap == gap2 {
-            // same gap (e.g., 88-55, KQo-JTo)
-            if rank11 > rank21 {
-                for i in (rank21 as u8)..=(rank11 as u8) {
-                    let r1: CardValue = i.try_into().unwrap();
-                    let r2: CardValue = (i - gap).try_into().unwrap();
-                    self.set_enabled(&indices_with_suitedness(r1, r2, suitedness), enabled);
-                }
-                Ok(())
-            } else {
-                Err(format!("Range must be in descending order: {range}").into())
-            }
-        } else if rank11 == rank21 {
-            // same first rank (e.g., A5s-A2s)
-            if rank12 > rank22 {
-                for i in (rank22 as u8)..=(rank12 as u8) {
-                    let r2 = i.try_into().unwrap();
-                    self.set_enabled(&indices_with_suitedness(rank11, r2, suitedness), enabled);
-                }
-                Ok(())
-            } else {
-                Err(format!("Range must be in descending order: {range}").into())
-            }
-        } else {
-            Err(format!("Invalid range: {range}").into())
-        }
-    }
-
     fn pairs_strings(&self, result: &mut Vec<String>) {
         let is_enabled_vec = CardValueRange::new(CardValue::Two, CardValue::Ace)
             .rev()
@@ -454,9 +380,103 @@ impl BoolRange {
     pub fn get_perc_enabled(&self) -> f64 {
         self.data.count_ones() as f64 / NUMBER_OF_HOLE_CARDS as f64
     }
+
+    pub fn get_num_combos(&self) -> usize {
+        self.data.count_ones()
+    }
+
+    pub fn union(&self, other: &BoolRange) -> BoolRange {
+        BoolRange::new_with_data(self.data | other.data)
+    }
+
+    pub fn intersection(&self, other: &BoolRange) -> BoolRange {
+        BoolRange::new_with_data(self.data & other.data)
+    }
+
+    //Hole cards in this range that are not in the other
+    pub fn difference(&self, other: &BoolRange) -> BoolRange {
+        BoolRange::new_with_data(self.data & !other.data)
+    }
+
+    //All hole cards not in this range
+    pub fn complement(&self) -> BoolRange {
+        let mut result = BoolRange::new_with_data(!self.data);
+        //The bit array is a bit bigger than the # of hole cards, those need to stay unset
+        result.data[NUMBER_OF_HOLE_CARDS..].fill(false);
+        result
+    }
+
+    /*
+    Removes any hole cards that contain one of the cards, typically
+    the board or hole cards we know about
+    */
+    pub fn remove_cards(&mut self, dead_cards: &[Card]) {
+        for card in dead_cards {
+            self.data &= ALL_CARD_RANGES[card.index as usize].inverse.data;
+        }
+    }
+
+    pub fn without_cards(&self, dead_cards: &[Card]) -> BoolRange {
+        let mut result = self.clone();
+        result.remove_cards(dead_cards);
+        result
+    }
+
+    pub fn remove_used_cards(&mut self, used_cards: &CardUsedType) {
+        for card_index in used_cards.iter_ones() {
+            if card_index >= NUMBER_OF_CARDS {
+                break;
+            }
+            self.data &= ALL_CARD_RANGES[card_index].inverse.data;
+        }
+    }
+
+    /*
+    # of hole cards in the range for each of the 169 hand classes (AA, AKs, AKo...)
+    Indexed by HoleCards::to_simple_range_index
+    */
+    pub fn get_combo_count_by_simple_range_index(&self) -> Vec<usize> {
+        SIMPLE_RANGE_INDEX_COMBOS
+            .iter()
+            .map(|indices| indices.iter().filter(|i| self.data[**i]).count())
+            .collect_vec()
+    }
+
+    /*
+    Goes through the 169 hand classes in simple range index order, AA first,
+    returning the class index and the hole cards in the range for it.
+
+    Classes with no hole cards in the range are skipped
+    */
+    pub fn iter_by_simple_range_index(&self) -> impl Iterator<Item = (usize, Vec<HoleCards>)> + '_ {
+        SIMPLE_RANGE_INDEX_COMBOS
+            .iter()
+            .enumerate()
+            .map(|(simple_index, indices)| {
+                (
+                    simple_index,
+                    indices
+                        .iter()
+                        .filter(|i| self.data[**i])
+                        .map(|i| ALL_HOLE_CARDS[*i])
+                        .collect_vec(),
+                )
+            })
+            .filter(|(_, hole_cards)| !hole_cards.is_empty())
+    }
 }
-const COMBO_PAT: &str = r"(?:(?:[AaKkQqJjTt2-9]{2}[os]?)|(?:(?:[AaKkQqJjTt2-9][cdhs]){2}))";
-static TRIM_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*([-:,])\s*").unwrap());
+
+//For each simple range index (0..169), the range indices of the hole cards in it
+pub static SIMPLE_RANGE_INDEX_COMBOS: Lazy<Vec<Vec<usize>>> = Lazy::new(|| {
+    let mut result: Vec<Vec<usize>> = vec![Vec::new(); SIMPLE_RANGE_INDEX_LEN];
+    for hc in ALL_HOLE_CARDS.iter() {
+        result[hc.to_simple_range_index()].push(hc.to_range_index());
+    }
+    result
+});
+pub(crate) const COMBO_PAT: &str =
+    r"(?:(?:[AaKkQqJjTt2-9]{2}[os]?)|(?:(?:[AaKkQqJjTt2-9][cdhs]){2}))";
+pub(crate) static TRIM_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*([-:,])\s*").unwrap());
 static RANGE_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(&format!(
         r"^(?P<range>{COMBO_PAT}(?:\+|(?:-{COMBO_PAT}))?)$"
@@ -478,7 +498,7 @@ static RANGE_REGEX: Lazy<Regex> = Lazy::new(|| {
 
 //Use holecard -- to_range_index
 #[inline]
-fn pair_indices(rank: CardValue) -> Vec<usize> {
+pub(crate) fn pair_indices(rank: CardValue) -> Vec<usize> {
     //let rank: usize = rank_obj.try_into().unwrap();
     let mut result = Vec::with_capacity(6);
     for suit1_index in 0..NUMBER_OF_SUITS {
@@ -510,7 +530,7 @@ fn nonpair_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
 }
 
 #[inline]
-fn suited_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
+pub(crate) fn suited_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
     let mut result = Vec::with_capacity(4);
     for suit1 in Suit::suits() {
         let card1 = Card::new(rank1, suit1);
@@ -526,7 +546,7 @@ fn suited_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
 }
 
 #[inline]
-fn offsuit_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
+pub(crate) fn offsuit_indices(rank1: CardValue, rank2: CardValue) -> Vec<usize> {
     let mut result = Vec::with_capacity(12);
     for suit1 in Suit::suits() {
         let card1 = Card::new(rank1, suit1);
@@ -584,6 +604,94 @@ fn indices_with_suitedness(
     }
 }
 
+#[inline]
+fn singleton_indices(combo: &str) -> Result<Vec<usize>, PokerError> {
+    let (rank1, rank2, suitedness) = parse_singleton(combo)?;
+    trace!(
+        "singleton_indices rank1: {}, rank2: {}, suitedness: {:?}",
+        rank1,
+        rank2,
+        suitedness
+    );
+    Ok(indices_with_suitedness(rank1, rank2, suitedness))
+}
+
+#[inline]
+fn plus_range_indices(range: &str) -> Result<Vec<usize>, PokerError> {
+    let lowest_combo = &range[..range.len() - 1];
+    let (rank1, rank2, suitedness) = parse_singleton(lowest_combo)?;
+    assert!(rank1 >= rank2);
+
+    let mut result = Vec::new();
+    let gap = (rank1 as u8) - (rank2 as u8);
+    if gap <= 1 {
+        let rank1_u8 = rank1 as u8;
+        // pair and connector (e.g.,  88+, T9s+)
+        for i in rank1_u8..13 {
+            let r1: CardValue = i.try_into().unwrap();
+            let r2: CardValue = (i - gap).try_into().unwrap();
+            result.extend(indices_with_suitedness(r1, r2, suitedness));
+        }
+    } else {
+        // otherwise (e.g., ATo+)
+        for i in (rank2 as u8)..(rank1 as u8) {
+            let r2: CardValue = i.try_into().unwrap();
+            result.extend(indices_with_suitedness(rank1, r2, suitedness));
+        }
+    }
+    Ok(result)
+}
+
+#[inline]
+fn dash_range_indices(range: &str) -> Result<Vec<usize>, PokerError> {
+    let combo_pair = range.split('-').collect::<Vec<_>>();
+    let (rank11, rank12, suitedness) = parse_singleton(combo_pair[0])?;
+    let (rank21, rank22, suitedness2) = parse_singleton(combo_pair[1])?;
+    let gap = (rank11 as u8) - (rank12 as u8);
+    let gap2 = (rank21 as u8) - (rank22 as u8);
+    let mut result = Vec::new();
+    if suitedness != suitedness2 {
+        Err(format!("Suitedness does not match: {range}").into())
+    } else if gap == gap2 {
+        // same gap (e.g., 88-55, KQo-JTo)
+        if rank11 > rank21 {
+            for i in (rank21 as u8)..=(rank11 as u8) {
+                let r1: CardValue = i.try_into().unwrap();
+                let r2: CardValue = (i - gap).try_into().unwrap();
+                result.extend(indices_with_suitedness(r1, r2, suitedness));
+            }
+            Ok(result)
+        } else {
+            Err(format!("Range must be in descending order: {range}").into())
+        }
+    } else if rank11 == rank21 {
+        // same first rank (e.g., A5s-A2s)
+        if rank12 > rank22 {
+            for i in (rank22 as u8)..=(rank12 as u8) {
+                let r2 = i.try_into().unwrap();
+                result.extend(indices_with_suitedness(rank11, r2, suitedness));
+            }
+            Ok(result)
+        } else {
+            Err(format!("Range must be in descending order: {range}").into())
+        }
+    } else {
+        Err(format!("Invalid range: {range}").into())
+    }
+}
+
+//Returns the hole card range indices for one comma seperated element of a range string, like AKs, 88+ or K5-K2
+#[inline]
+pub(crate) fn range_element_indices(range: &str) -> Result<Vec<usize>, PokerError> {
+    if range.contains('-') {
+        dash_range_indices(range)
+    } else if range.contains('+') {
+        plus_range_indices(range)
+    } else {
+        singleton_indices(range)
+    }
+}
+
 #[inline]
 fn parse_singleton(combo: &str) -> Result<(CardValue, CardValue, Suitedness), PokerError> {
     if combo.len() == 4 {
@@ -675,13 +783,7 @@ impl FromStr for BoolRange {
 
             trace!("range: {}", range);
 
-            if range.contains('-') {
-                result.update_with_dash_range(range, true)?;
-            } else if range.contains('+') {
-                result.update_with_plus_range(range, true)?;
-            } else {
-                result.update_with_singleton(range, true)?;
-            }
+            result.set_enabled(&range_element_indices(range)?, true);
         }
 
         Ok(result)
@@ -816,6 +918,75 @@ mod tests {
         assert_eq!(range_string, "AA");
     }
 
+    #[test]
+    fn test_range_algebra() {
+        let pairs: BoolRange = "TT+".parse().unwrap();
+        let broadway: BoolRange = "AK,AQ,KQ,QQ+".parse().unwrap();
+
+        let union = pairs.union(&broadway);
+        assert_eq!(union, "TT+,AK,AQ,KQ".parse().unwrap());
+        assert_eq!(union.get_num_combos(), 5 * 6 + 3 * 16);
+
+        let intersection = pairs.intersection(&broadway);
+        assert_eq!(intersection, "QQ+".parse().unwrap());
+
+        let difference = pairs.difference(&broadway);
+        assert_eq!(difference, "JJ,TT".parse().unwrap());
+
+        let complement = pairs.complement();
+        assert_eq!(complement.get_num_combos(), NUMBER_OF_HOLE_CARDS - 5 * 6);
+        assert_eq!(complement.complement(), pairs);
+        assert_eq!(
+            BoolRange::default().complement().get_num_combos(),
+            NUMBER_OF_HOLE_CARDS
+        );
+        assert!(complement.intersection(&pairs).data.not_any());
+    }
+
+    #[test]
+    fn test_remove_cards() {
+        let range: BoolRange = "AA,AKs,KQo".parse().unwrap();
+        let board: Vec<Card> = vec!["As".parse().unwrap(), "Kd".parse().unwrap()];
+
+        let removed = range.without_cards(&board);
+        //AA loses 3 combos, AKs 2 (As Ks and Ad Kd), KQo 3
+        assert_eq!(removed.get_num_combos(), 3 + 2 + 9);
+
+        let aces: HoleCards = "Ac Ah".parse().unwrap();
+        let blocked: HoleCards = "As Ah".parse().unwrap();
+        assert!(removed.data[aces.to_range_index()]);
+        assert!(!removed.data[blocked.to_range_index()]);
+
+        let mut used_cards = CardUsedType::default();
+        for c in board.iter() {
+            used_cards.set(c.index as usize, true);
+        }
+        let mut removed2 = range.clone();
+        removed2.remove_used_cards(&used_cards);
+        assert_eq!(removed, removed2);
+    }
+
+    #[test]
+    fn test_iter_by_simple_range_index() {
+        let range: BoolRange = "AA,AKs,AhKd,72o".parse().unwrap();
+
+        let counts = range.get_combo_count_by_simple_range_index();
+        assert_eq!(counts.len(), SIMPLE_RANGE_INDEX_LEN);
+        assert_eq!(counts.iter().sum::<usize>(), range.get_num_combos());
+        assert_eq!(counts[0], 6);
+        assert_eq!(counts[1], 4);
+        //AKo
+        assert_eq!(counts[13], 1);
+
+        let classes = range.iter_by_simple_range_index().collect_vec();
+        assert_eq!(classes.len(), 4);
+        assert_eq!(classes[0].0, 0);
+        assert_eq!(classes[0].1.len(), 6);
+        assert_eq!(classes[2].1[0].to_simple_range_string(), "AKo");
+        assert_eq!(classes[3].1.len(), 12);
+        assert_eq!(classes[3].1[0].to_simple_range_string(), "72o");
+    }
+
     #[test]
     fn range_from_str() {
         let pair_plus = "88+".parse::<BoolRange>();
@@ -1,4 +1,4 @@
-use crate::{Card, CardUsedType, PokerError};
+use crate::{BoolRange, Card, CardUsedType, HoleCards, PokerError, WeightedRange};
 
 pub fn set_used_card(c_index: usize, cards_used: &mut CardUsedType) -> Result<(), PokerError> {
     let count_before = cards_used.count_ones();
@@ -30,6 +30,47 @@ pub fn unset_used_card(c_index: usize, cards_used: &mut CardUsedType) -> Result<
     Ok(())
 }
 
+/*
+The board and hole cards as a bit set, an error if a card is in both or twice on the board
+*/
+pub fn deal_cards_used(board: &[Card], hole_cards: &HoleCards) -> Result<CardUsedType, PokerError> {
+    let mut cards_used = CardUsedType::default();
+    for c in board.iter().chain(hole_cards.as_slice().iter()) {
+        set_used_card(c.index as usize, &mut cards_used)?;
+    }
+    Ok(cards_used)
+}
+
+//The hole cards in the range that don't use any card already used, an error if there are none
+pub fn range_hole_cards_left(
+    range: &BoolRange,
+    cards_used: &CardUsedType,
+) -> Result<Vec<HoleCards>, PokerError> {
+    Ok(weighted_range_hole_cards_left(&range.into(), cards_used)?
+        .into_iter()
+        .map(|(hc, _)| hc)
+        .collect())
+}
+
+//Same as range_hole_cards_left keeping the weights
+pub fn weighted_range_hole_cards_left(
+    range: &WeightedRange,
+    cards_used: &CardUsedType,
+) -> Result<Vec<(HoleCards, f32)>, PokerError> {
+    let hole_cards: Vec<(HoleCards, f32)> = range
+        .get_all_enabled_holecards_with_weights()
+        .into_iter()
+        .filter(|(hc, _)| !cards_used[hc.get_hi_card().index as usize])
+        .filter(|(hc, _)| !cards_used[hc.get_lo_card().index as usize])
+        .collect();
+    if hole_cards.is_empty() {
+        return Err(PokerError::from_str(
+            "Range has no hole cards left given the board and hole cards",
+        ));
+    }
+    Ok(hole_cards)
+}
+
 // pub fn get_unused_card(rng: &mut StdRng, cards_used: &CardUsedType) -> Option<usize> {
 //     let mut attempts = 0;
 //     loop {
@@ -0,0 +1,113 @@
+/*
+Deciding when a Monte Carlo simulation has run long enough
+*/
+
+//A 95% confidence interval is the estimate +- this many standard errors
+pub const Z_SCORE_95: f64 = 1.96;
+
+//Below this the variance estimate isn't reliable enough to stop on
+pub const DEFAULT_BATCH_SIZE: usize = 1_000;
+
+#[derive(Debug, Clone)]
+pub struct StoppingRule {
+    //Stop once the standard error of every player's equity is at most this (0.004 is +- 0.8% at 95%)
+    pub target_std_error: Option<f64>,
+    //Stop once this many milliseconds have passed
+    pub time_budget_ms: Option<f64>,
+    //Always stop after this many iterations
+    pub max_iterations: usize,
+    //How many iterations to run between checks
+    pub batch_size: usize,
+}
+
+impl StoppingRule {
+    pub fn with_target_std_error(target_std_error: f64, max_iterations: usize) -> Self {
+        Self {
+            target_std_error: Some(target_std_error),
+            time_budget_ms: None,
+            max_iterations,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    pub fn with_time_budget_ms(time_budget_ms: f64, max_iterations: usize) -> Self {
+        Self {
+            target_std_error: None,
+            time_budget_ms: Some(time_budget_ms),
+            max_iterations,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    //How many iterations to run next, never going over max_iterations
+    pub fn next_batch_size(&self, num_iterations: usize) -> usize {
+        self.batch_size
+            .max(1)
+            .min(self.max_iterations.saturating_sub(num_iterations))
+    }
+
+    pub fn is_done(&self, num_iterations: usize, std_error: f64, start_ms: f64) -> bool {
+        if num_iterations >= self.max_iterations {
+            return true;
+        }
+        if let Some(target_std_error) = self.target_std_error {
+            if num_iterations >= self.batch_size && std_error <= target_std_error {
+                return true;
+            }
+        }
+        if let Some(time_budget_ms) = self.time_budget_ms {
+            if now_ms() - start_ms >= time_budget_ms {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+//Standard error of the mean, given the sum and sum of squares of the samples
+pub fn std_error_of_mean(sum: f64, sum_sq: f64, num_samples: usize) -> f64 {
+    if num_samples == 0 {
+        return 0.0;
+    }
+    let n = num_samples as f64;
+    let mean = sum / n;
+    let variance = (sum_sq / n - mean * mean).max(0.0);
+    (variance / n).sqrt()
+}
+
+//std::time::Instant isn't available in the browser
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopping_rule() {
+        //p = 0.5 coin flips, std error is 0.5 / sqrt(n)
+        assert!((std_error_of_mean(500.0, 500.0, 1_000) - 0.5 / 1_000f64.sqrt()).abs() < 1e-12);
+
+        let rule = StoppingRule::with_target_std_error(0.01, 100_000);
+        let start_ms = now_ms();
+        assert!(!rule.is_done(1_000, 0.02, start_ms));
+        assert!(rule.is_done(3_000, 0.009, start_ms));
+        //not enough iterations to trust the std error
+        assert!(!rule.is_done(10, 0.0, start_ms));
+        assert!(rule.is_done(100_000, 0.02, start_ms));
+        assert_eq!(rule.next_batch_size(99_500), 500);
+
+        let rule = StoppingRule::with_time_budget_ms(0.0, 100_000);
+        assert!(rule.is_done(1, 1.0, start_ms));
+    }
+}
@@ -1,24 +1,73 @@
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{distributions::Distribution, rngs::StdRng, Rng, SeedableRng};
 
-use crate::{Card, CardUsedType, HoleCards, PokerError, ALL_CARDS};
+use crate::{Card, CardUsedType, HoleCards, PokerError, WeightedHoleCards, ALL_CARDS};
 
 pub struct Deck {
     pub rng: StdRng,
     pub(crate) used_cards: CardUsedType,
     //available_range: BoolRange,
+    pub(crate) variant: DeckVariant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeckVariant {
+    #[default]
+    Standard,
+    //Short deck (6+) hold'em, no 2s to 5s
+    ShortDeck,
+}
+
+impl DeckVariant {
+    //Cards are ordered by value, so the short deck is just the last 36
+    pub fn first_card_index(&self) -> usize {
+        match self {
+            DeckVariant::Standard => 0,
+            DeckVariant::ShortDeck => 16,
+        }
+    }
+
+    pub fn num_cards(&self) -> usize {
+        52 - self.first_card_index()
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        card.index as usize >= self.first_card_index()
+    }
+
+    pub fn cards(&self) -> &'static [Card] {
+        &ALL_CARDS[self.first_card_index()..]
+    }
+
+    //Cards that are not in this deck, so they can be treated as dead cards
+    pub fn removed_cards(&self) -> &'static [Card] {
+        &ALL_CARDS[..self.first_card_index()]
+    }
 }
 
 const MAX_RAND_NUMBER_ATTEMPS: usize = 1_000;
 
+//What Deck::new uses, so results are the same run to run unless a seed is given
+pub const DEFAULT_SEED: u64 = 42;
+
 impl Deck {
     pub fn new() -> Self {
-        let rng = StdRng::seed_from_u64(42);
-        //fastrand::seed(42);
+        Self::new_with_seed(DEFAULT_SEED)
+    }
+
+    pub fn new_with_seed(seed: u64) -> Self {
+        Self::new_with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    pub fn new_with_rng(rng: StdRng) -> Self {
+        Self::new_with_variant(rng, DeckVariant::Standard)
+    }
 
+    pub fn new_with_variant(rng: StdRng, variant: DeckVariant) -> Self {
         let mut d = Deck {
             rng,
             used_cards: CardUsedType::default(),
             // available_range: BoolRange::default(),
+            variant,
         };
 
         d.reset();
@@ -66,12 +115,38 @@ impl Deck {
         &mut self,
         possible_hole_cards: &Vec<HoleCards>,
     ) -> Result<HoleCards, PokerError> {
+        let num_possible = possible_hole_cards.len();
+        self.choose_available(possible_hole_cards, |rng| rng.gen_range(0..num_possible))
+    }
+
+    /*
+    Same as above, but the hole cards are chosen in proportion to their weights
+    */
+    pub fn choose_available_in_weighted_range(
+        &mut self,
+        weighted_hole_cards: &WeightedHoleCards,
+    ) -> Result<HoleCards, PokerError> {
+        if let Some(dist) = weighted_hole_cards.dist.as_ref() {
+            self.choose_available(&weighted_hole_cards.hole_cards, |rng| dist.sample(rng))
+        } else {
+            self.choose_available_in_range(&weighted_hole_cards.hole_cards)
+        }
+    }
+
+    fn choose_available<F>(
+        &mut self,
+        possible_hole_cards: &[HoleCards],
+        mut choose_index: F,
+    ) -> Result<HoleCards, PokerError>
+    where
+        F: FnMut(&mut StdRng) -> usize,
+    {
         let mut attempts = 0;
         loop {
             attempts += 1;
             //trace!("Attempt {}", attempts);
             //let rand_int: usize = fastrand::usize(0..possible_hole_cards.len());
-            let rand_int: usize = self.rng.gen_range(0..possible_hole_cards.len());
+            let rand_int: usize = choose_index(&mut self.rng);
             let hole_cards = possible_hole_cards[rand_int];
 
             if attempts > MAX_RAND_NUMBER_ATTEMPS {
@@ -100,7 +175,7 @@ impl Deck {
         //Usually most of the deck is available
         let mut attempts = 0;
         loop {
-            let rand_int: usize = self.rng.gen_range(0..52);
+            let rand_int: usize = self.rng.gen_range(self.variant.first_card_index()..52);
             //let rand_int: usize = fastrand::usize(0..52);
             assert!(rand_int < 52);
 
@@ -127,6 +202,38 @@ impl Deck {
 
 #[cfg(test)]
 mod tests {
+    use crate::CardValue;
+
+    use super::*;
+
+    #[test]
+    fn test_seeded_decks_repeat() {
+        let mut deck1 = Deck::new_with_seed(7);
+        let mut deck2 = Deck::new_with_rng(StdRng::seed_from_u64(7));
+        assert_eq!(deck1.choose_new_board(), deck2.choose_new_board());
+
+        let mut deck3 = Deck::new_with_seed(8);
+        deck1.reset();
+        let board1 = deck1.choose_new_board();
+        let board3 = deck3.choose_new_board();
+        assert_ne!(board1, board3);
+    }
+
+    #[test]
+    fn test_short_deck() {
+        let mut deck = Deck::new_with_variant(StdRng::seed_from_u64(7), DeckVariant::ShortDeck);
+        for _ in 0..36 {
+            let card = deck.get_unused_card().unwrap();
+            assert!(card.value >= CardValue::Six);
+        }
+        assert_eq!(36, deck.get_number_of_used_cards());
+        assert!(deck.get_unused_card().is_err());
+
+        assert_eq!(36, DeckVariant::ShortDeck.cards().len());
+        assert_eq!(16, DeckVariant::ShortDeck.removed_cards().len());
+        assert!(!DeckVariant::ShortDeck.contains("5s".parse().unwrap()));
+        assert!(DeckVariant::ShortDeck.contains("6c".parse().unwrap()));
+    }
 
     // #[test]
     // fn test_choose_available_in_range_aces() {
@@ -0,0 +1,359 @@
+use itertools::Itertools;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    Rng,
+};
+
+use crate::{Card, CardUsedType, Deck, HoleCards, PokerError, WeightedHoleCards, WeightedRange};
+
+//Below this many (hole cards of each range player) combinations, we list all the valid ones and choose from them
+const MAX_ENUMERATED_TUPLES: usize = 50_000;
+
+//Only used when the ranges are wide, so almost all tuples are accepted
+const MAX_TUPLE_ATTEMPTS: usize = 100_000;
+
+/*
+Deals hole cards to all players at once, following the true joint distribution given the dead cards,
+so the probability of a (player 1 hole cards, player 2 hole cards, ...) tuple is proportional to
+the product of their weights in the ranges, and 0 if any cards are shared.
+
+Choosing each player in turn and retrying on conflicts is biased when ranges overlap,
+since whoever is chosen first takes the shared cards more often.
+*/
+pub struct HoleCardsSampler {
+    num_players: usize,
+    //Players with no range get any 2 unused cards, these are dealt after the range players
+    random_players: Vec<usize>,
+    method: SamplerMethod,
+}
+
+enum SamplerMethod {
+    //Every valid tuple of hole cards, flattened, with its weight in dist
+    Enumerated {
+        range_players: Vec<usize>,
+        tuples: Vec<HoleCards>,
+        dist: WeightedIndex<f64>,
+    },
+    //Choose each player independently, rejecting the whole tuple if any cards are shared
+    Rejection {
+        range_players: Vec<(usize, WeightedHoleCards)>,
+    },
+}
+
+impl HoleCardsSampler {
+    /*
+    ranges is indexed by player, None means the player has a random hand
+    dead_cards are cards no one can have (the board, known hole cards)
+    */
+    pub fn new(ranges: &[Option<&WeightedRange>], dead_cards: &[Card]) -> Result<Self, PokerError> {
+        Self::build(ranges, dead_cards, MAX_ENUMERATED_TUPLES)
+    }
+
+    fn build(
+        ranges: &[Option<&WeightedRange>],
+        dead_cards: &[Card],
+        max_enumerated_tuples: usize,
+    ) -> Result<Self, PokerError> {
+        let random_players = ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.is_none())
+            .map(|(player_index, _)| player_index)
+            .collect_vec();
+
+        let mut range_players: Vec<(usize, WeightedHoleCards)> = Vec::new();
+        for (player_index, r) in ranges.iter().enumerate() {
+            if let Some(r) = r {
+                let mut live_range = (*r).clone();
+                live_range.remove_cards(dead_cards);
+                let whc = live_range.get_weighted_hole_cards().map_err(|_| {
+                    PokerError::from_string(format!(
+                        "Player {} has no possible hole cards given the dead cards",
+                        player_index
+                    ))
+                })?;
+                range_players.push((player_index, whc));
+            }
+        }
+
+        //Most restrictive first, to prune the enumeration early
+        range_players.sort_by_key(|(_, whc)| whc.hole_cards.len());
+
+        let work_size = range_players.iter().fold(1usize, |acc, (_, whc)| {
+            acc.saturating_mul(whc.hole_cards.len())
+        });
+
+        let method = if work_size <= max_enumerated_tuples {
+            Self::enumerate_tuples(ranges, &range_players)?
+        } else {
+            SamplerMethod::Rejection { range_players }
+        };
+
+        Ok(HoleCardsSampler {
+            num_players: ranges.len(),
+            random_players,
+            method,
+        })
+    }
+
+    fn enumerate_tuples(
+        ranges: &[Option<&WeightedRange>],
+        range_players: &[(usize, WeightedHoleCards)],
+    ) -> Result<SamplerMethod, PokerError> {
+        let mut tuples = Vec::new();
+        let mut weights = Vec::new();
+        let mut current = Vec::with_capacity(range_players.len());
+
+        fn recurse(
+            ranges: &[Option<&WeightedRange>],
+            range_players: &[(usize, WeightedHoleCards)],
+            used_cards: &mut CardUsedType,
+            current: &mut Vec<HoleCards>,
+            weight: f64,
+            tuples: &mut Vec<HoleCards>,
+            weights: &mut Vec<f64>,
+        ) {
+            if current.len() == range_players.len() {
+                tuples.extend_from_slice(current);
+                weights.push(weight);
+                return;
+            }
+            let (player_index, whc) = &range_players[current.len()];
+            let range = ranges[*player_index].unwrap();
+
+            for hc in whc.hole_cards.iter() {
+                let hi: usize = hc.get_hi_card().into();
+                let lo: usize = hc.get_lo_card().into();
+                if used_cards[hi] || used_cards[lo] {
+                    continue;
+                }
+                used_cards.set(hi, true);
+                used_cards.set(lo, true);
+                current.push(*hc);
+
+                let hc_weight = range.get_weight_for_holecards(hc) as f64;
+                recurse(
+                    ranges,
+                    range_players,
+                    used_cards,
+                    current,
+                    weight * hc_weight,
+                    tuples,
+                    weights,
+                );
+
+                current.pop();
+                used_cards.set(hi, false);
+                used_cards.set(lo, false);
+            }
+        }
+
+        recurse(
+            ranges,
+            range_players,
+            &mut CardUsedType::default(),
+            &mut current,
+            1.0,
+            &mut tuples,
+            &mut weights,
+        );
+
+        if weights.is_empty() {
+            return Err(PokerError::from_str(
+                "No possible hole cards for the players without sharing cards",
+            ));
+        }
+
+        let dist = WeightedIndex::new(&weights)
+            .map_err(|e| PokerError::from_string(format!("Unable to build weights: {}", e)))?;
+
+        Ok(SamplerMethod::Enumerated {
+            range_players: range_players.iter().map(|(p, _)| *p).collect_vec(),
+            tuples,
+            dist,
+        })
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.num_players
+    }
+
+    /*
+    The deck should already have the dead cards set as used
+    Sets the chosen hole cards as used in the deck
+    */
+    pub fn sample(
+        &self,
+        deck: &mut Deck,
+        player_hole_cards: &mut [HoleCards],
+    ) -> Result<(), PokerError> {
+        assert_eq!(self.num_players, player_hole_cards.len());
+
+        match &self.method {
+            SamplerMethod::Enumerated {
+                range_players,
+                tuples,
+                dist,
+            } => {
+                //Nothing to choose, and we don't want to use up random numbers
+                if range_players.is_empty() {
+                    return self.deal_random_players(deck, player_hole_cards);
+                }
+                let tuple_index = dist.sample(&mut deck.rng);
+                let tuple = &tuples
+                    [tuple_index * range_players.len()..(tuple_index + 1) * range_players.len()];
+                for (player_index, hc) in range_players.iter().zip(tuple.iter()) {
+                    player_hole_cards[*player_index] = *hc;
+                }
+            }
+            SamplerMethod::Rejection { range_players } => {
+                let mut attempts = 0;
+                'attempt: loop {
+                    attempts += 1;
+                    if attempts > MAX_TUPLE_ATTEMPTS {
+                        return Err(PokerError::from_string(format!(
+                            "Unable to find hole cards without sharing cards after {} attempts",
+                            MAX_TUPLE_ATTEMPTS
+                        )));
+                    }
+
+                    let mut chosen_cards = CardUsedType::default();
+                    for (player_index, whc) in range_players.iter() {
+                        let hc = choose_hole_cards(whc, &mut deck.rng);
+                        let hi: usize = hc.get_hi_card().into();
+                        let lo: usize = hc.get_lo_card().into();
+                        if chosen_cards[hi] || chosen_cards[lo] {
+                            continue 'attempt;
+                        }
+                        chosen_cards.set(hi, true);
+                        chosen_cards.set(lo, true);
+                        player_hole_cards[*player_index] = hc;
+                    }
+                    break;
+                }
+            }
+        }
+
+        let range_player_indexes: Box<dyn Iterator<Item = &usize>> = match &self.method {
+            SamplerMethod::Enumerated { range_players, .. } => Box::new(range_players.iter()),
+            SamplerMethod::Rejection { range_players } => {
+                Box::new(range_players.iter().map(|(p, _)| p))
+            }
+        };
+        for player_index in range_player_indexes {
+            deck.set_used_card(player_hole_cards[*player_index].get_hi_card());
+            deck.set_used_card(player_hole_cards[*player_index].get_lo_card());
+        }
+
+        self.deal_random_players(deck, player_hole_cards)
+    }
+
+    //Once the range players have their cards, any 2 of the remaining cards are equally likely
+    fn deal_random_players(
+        &self,
+        deck: &mut Deck,
+        player_hole_cards: &mut [HoleCards],
+    ) -> Result<(), PokerError> {
+        for player_index in self.random_players.iter() {
+            let card1 = deck.get_unused_card()?;
+            let card2 = deck.get_unused_card()?;
+            player_hole_cards[*player_index] = HoleCards::new(card1, card2)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn choose_hole_cards(whc: &WeightedHoleCards, rng: &mut StdRng) -> HoleCards {
+    let index = match whc.dist.as_ref() {
+        Some(dist) => dist.sample(rng),
+        None => rng.gen_range(0..whc.hole_cards.len()),
+    };
+    whc.hole_cards[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, ALL_HOLE_CARDS};
+
+    use super::*;
+
+    fn sample_frequencies(sampler: &HoleCardsSampler, board: &Board) -> Vec<(String, f64)> {
+        let mut deck = Deck::new();
+        let num_samples = 30_000;
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let mut player_hole_cards = vec![ALL_HOLE_CARDS[0]; sampler.num_players()];
+
+        for _ in 0..num_samples {
+            deck.reset();
+            for c in board.as_slice_card().iter() {
+                deck.set_used_card(*c);
+            }
+            sampler.sample(&mut deck, &mut player_hole_cards).unwrap();
+            let key = player_hole_cards.iter().map(|hc| hc.to_string()).join(" ");
+            match counts.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((key, 1)),
+            }
+        }
+
+        counts
+            .into_iter()
+            .map(|(k, c)| (k, c as f64 / num_samples as f64))
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_overlapping_ranges_unbiased() {
+        //AhAs conflicts with AhQd, so the 3 valid tuples should each be 1/3
+        //Choosing player 1 first would give AhAs JcJd half the time
+        let board: Board = "2c 3d 4h".parse().unwrap();
+        let p1: WeightedRange = "AhAs,KhKs".parse().unwrap();
+        let p2: WeightedRange = "AhQd,JcJd".parse().unwrap();
+        let ranges = [Some(&p1), Some(&p2)];
+
+        for max_enumerated_tuples in [MAX_ENUMERATED_TUPLES, 0] {
+            let sampler =
+                HoleCardsSampler::build(&ranges, board.as_slice_card(), max_enumerated_tuples)
+                    .unwrap();
+            let freqs = sample_frequencies(&sampler, &board);
+            assert_eq!(3, freqs.len());
+            for (key, freq) in freqs.iter() {
+                assert!((freq - 1.0 / 3.0).abs() < 0.02, "{} {}", key, freq);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weights_and_random_players() {
+        let board: Board = "Ac 3d 4h".parse().unwrap();
+        //AcKc is dead because of the board
+        let p1: WeightedRange = "AcKc,QQ:0.5,JsJh".parse().unwrap();
+        let ranges = [None, Some(&p1), None];
+
+        let sampler = HoleCardsSampler::new(&ranges, board.as_slice_card()).unwrap();
+        let mut deck = Deck::new();
+        let mut player_hole_cards = vec![ALL_HOLE_CARDS[0]; 3];
+        let mut num_jacks = 0;
+        let num_samples = 10_000;
+        for _ in 0..num_samples {
+            deck.reset();
+            for c in board.as_slice_card().iter() {
+                deck.set_used_card(*c);
+            }
+            sampler.sample(&mut deck, &mut player_hole_cards).unwrap();
+            assert_eq!(9, deck.get_number_of_used_cards());
+            if player_hole_cards[1].to_string() == "JsJh" {
+                num_jacks += 1;
+            }
+        }
+        //6 QQ combos at 0.5 vs 1 JJ combo
+        let freq = num_jacks as f64 / num_samples as f64;
+        assert!((freq - 1.0 / 4.0).abs() < 0.02);
+
+        let dead_range: WeightedRange = "AcKc".parse().unwrap();
+        assert!(HoleCardsSampler::new(&[Some(&dead_range), None], board.as_slice_card()).is_err());
+    }
+}
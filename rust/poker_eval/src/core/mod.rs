@@ -3,6 +3,12 @@ pub use card::*;
 mod hole_cards;
 pub use hole_cards::*;
 
+mod omaha_hole_cards;
+pub use omaha_hole_cards::*;
+
+mod omaha_range;
+pub use omaha_range::*;
+
 mod error;
 pub use error::*;
 
@@ -15,8 +21,23 @@ pub use test_util::*;
 mod deck;
 pub use deck::*;
 
+mod hole_cards_sampler;
+pub use hole_cards_sampler::*;
+
+mod parallel;
+pub use parallel::*;
+
+mod convergence;
+pub use convergence::*;
+
 mod bool_range;
 pub use bool_range::*;
 
+mod weighted_range;
+pub use weighted_range::*;
+
 mod board;
 pub use board::*;
+
+mod suit_isomorphism;
+pub use suit_isomorphism::*;
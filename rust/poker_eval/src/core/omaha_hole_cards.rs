@@ -0,0 +1,138 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{set_used_card, Card, CardUsedType, PokerError};
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub enum OmahaVariant {
+    //Pot limit Omaha, 4 hole cards
+    Plo4,
+    //5 card Omaha
+    Plo5,
+}
+
+impl OmahaVariant {
+    pub fn num_hole_cards(&self) -> usize {
+        match self {
+            OmahaVariant::Plo4 => 4,
+            OmahaVariant::Plo5 => 5,
+        }
+    }
+
+    pub fn from_num_hole_cards(num_hole_cards: usize) -> Result<Self, PokerError> {
+        match num_hole_cards {
+            4 => Ok(OmahaVariant::Plo4),
+            5 => Ok(OmahaVariant::Plo5),
+            n => Err(PokerError::from_string(format!(
+                "Omaha needs 4 or 5 hole cards, not {}",
+                n
+            ))),
+        }
+    }
+}
+
+/*
+4 or 5 hole cards, sorted from highest to lowest card index
+
+Unlike hold'em, exactly 2 of them must be used with exactly 3 board cards, see fast_omaha_eval
+*/
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub struct OmahaHoleCards {
+    cards: [Card; 5],
+    num_cards: u8,
+}
+
+impl OmahaHoleCards {
+    pub fn new(cards: &[Card]) -> Result<Self, PokerError> {
+        OmahaVariant::from_num_hole_cards(cards.len())?;
+
+        let mut sorted_cards = [cards[0]; 5];
+        sorted_cards[..cards.len()].copy_from_slice(cards);
+        sorted_cards[..cards.len()].sort_by_key(|c| std::cmp::Reverse(c.index));
+
+        for i in 1..cards.len() {
+            if sorted_cards[i - 1] == sorted_cards[i] {
+                return Err(PokerError::from_str("Hole cards must be different"));
+            }
+        }
+
+        Ok(OmahaHoleCards {
+            cards: sorted_cards,
+            num_cards: cards.len() as u8,
+        })
+    }
+
+    pub fn variant(&self) -> OmahaVariant {
+        OmahaVariant::from_num_hole_cards(self.num_cards as usize).unwrap()
+    }
+
+    pub fn as_slice(&self) -> &[Card] {
+        &self.cards[..self.num_cards as usize]
+    }
+
+    pub fn set_used(&self, cards_used: &mut CardUsedType) -> Result<(), PokerError> {
+        for card in self.as_slice().iter() {
+            set_used_card((*card).into(), cards_used)?;
+        }
+        Ok(())
+    }
+
+    pub fn intersects(&self, cards_used: &CardUsedType) -> bool {
+        self.as_slice().iter().any(|c| cards_used[c.index as usize])
+    }
+}
+
+impl FromStr for OmahaHoleCards {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+
+        if chars.len() % 2 != 0 {
+            return Err(PokerError::from_string(format!(
+                "Need a value and suit for each card: {}",
+                s
+            )));
+        }
+
+        let cards = chars
+            .chunks(2)
+            .map(|vs| Ok(Card::new(vs[0].try_into()?, vs[1].try_into()?)))
+            .collect::<Result<Vec<Card>, PokerError>>()?;
+
+        OmahaHoleCards::new(&cards)
+    }
+}
+
+impl Display for OmahaHoleCards {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for card in self.as_slice().iter() {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_omaha_hole_cards() {
+        let hc: OmahaHoleCards = "2c Ah Kd Ks".parse().unwrap();
+        assert_eq!(hc.to_string(), "AhKsKd2c");
+        assert_eq!(hc.variant(), OmahaVariant::Plo4);
+
+        let hc: OmahaHoleCards = "7c8c9dTdJh".parse().unwrap();
+        assert_eq!(hc.variant(), OmahaVariant::Plo5);
+        assert_eq!(hc.as_slice().len(), 5);
+
+        assert!("AhKdKs".parse::<OmahaHoleCards>().is_err());
+        assert!("AhKdKsAh".parse::<OmahaHoleCards>().is_err());
+        assert!("AhKdKsQ".parse::<OmahaHoleCards>().is_err());
+    }
+}
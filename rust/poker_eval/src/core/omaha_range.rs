@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::{Card, CardValue, OmahaHoleCards, OmahaVariant, PokerError, Suit, ALL_CARDS};
+
+/*
+An Omaha range, a comma separated list of hands where each card is one of
+
+Ah   -- exactly that card
+A    -- any ace
+x    -- any card (* also works)
+
+optionally followed by a suit requirement
+
+ds   -- double suited, 2 suits with 2+ cards each
+ss   -- single suited, only 1 suit with 2+ cards
+r    -- rainbow, no 2 cards of the same suit
+
+Ex: AAxxds,KKQJ,AhAsxx for PLO4, or AKxxx,xxxxx for PLO5
+
+There are too many Omaha hands (270,725 for PLO4) to store a weight for each like WeightedRange,
+so we keep the patterns and check hands against them
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OmahaRange {
+    pub variant: OmahaVariant,
+    patterns: Vec<OmahaHandPattern>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CardPattern {
+    Exact(Card),
+    Value(CardValue),
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SuitPattern {
+    Any,
+    DoubleSuited,
+    SingleSuited,
+    Rainbow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OmahaHandPattern {
+    cards: Vec<CardPattern>,
+    suits: SuitPattern,
+}
+
+impl OmahaHandPattern {
+    fn matches(&self, hole_cards: &OmahaHoleCards) -> bool {
+        let cards = hole_cards.as_slice();
+        if cards.len() != self.cards.len() {
+            return false;
+        }
+
+        //Exact cards can only match themselves
+        let mut remaining_values = [0u8; 13];
+        for c in cards.iter() {
+            remaining_values[c.value as usize] += 1;
+        }
+        for card_pattern in self.cards.iter() {
+            if let CardPattern::Exact(card) = card_pattern {
+                if !cards.contains(card) {
+                    return false;
+                }
+                remaining_values[card.value as usize] -= 1;
+            }
+        }
+
+        //Then any of the remaining cards with the value, the rest are matched by x
+        for card_pattern in self.cards.iter() {
+            if let CardPattern::Value(value) = card_pattern {
+                if remaining_values[*value as usize] == 0 {
+                    return false;
+                }
+                remaining_values[*value as usize] -= 1;
+            }
+        }
+
+        let mut suit_counts = [0u8; 4];
+        for c in cards.iter() {
+            suit_counts[c.suit as usize] += 1;
+        }
+        let num_suited = suit_counts.iter().filter(|c| **c >= 2).count();
+
+        match self.suits {
+            SuitPattern::Any => true,
+            SuitPattern::DoubleSuited => num_suited == 2,
+            SuitPattern::SingleSuited => num_suited == 1,
+            SuitPattern::Rainbow => num_suited == 0,
+        }
+    }
+}
+
+fn parse_card_patterns(s: &str) -> Result<Vec<CardPattern>, PokerError> {
+    let chars = s.chars().collect_vec();
+    let mut card_patterns = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == 'x' || chars[i] == 'X' || chars[i] == '*' {
+            card_patterns.push(CardPattern::Any);
+            i += 1;
+            continue;
+        }
+
+        let value: CardValue = chars[i].try_into()?;
+        match chars.get(i + 1).map(|c| Suit::try_from(*c)) {
+            Some(Ok(suit)) => {
+                card_patterns.push(CardPattern::Exact(Card::new(value, suit)));
+                i += 2;
+            }
+            _ => {
+                card_patterns.push(CardPattern::Value(value));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(card_patterns)
+}
+
+impl FromStr for OmahaHandPattern {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //The suit requirement is ambiguous with a suit (d, s), so try it first
+        let suffixes = [
+            ("ds", SuitPattern::DoubleSuited),
+            ("ss", SuitPattern::SingleSuited),
+            ("r", SuitPattern::Rainbow),
+        ];
+        let (cards, suits) = suffixes
+            .iter()
+            .filter_map(|(suffix, suits)| {
+                let cards = parse_card_patterns(s.strip_suffix(suffix)?).ok()?;
+                OmahaVariant::from_num_hole_cards(cards.len())
+                    .ok()
+                    .map(|_| (cards, *suits))
+            })
+            .next()
+            .map_or_else(
+                || parse_card_patterns(s).map(|cards| (cards, SuitPattern::Any)),
+                Ok,
+            )?;
+
+        OmahaVariant::from_num_hole_cards(cards.len())
+            .map_err(|e| PokerError::from_string(format!("Invalid Omaha hand {}: {}", s, e)))?;
+
+        let exact_cards = cards
+            .iter()
+            .filter_map(|c| match c {
+                CardPattern::Exact(card) => Some(*card),
+                _ => None,
+            })
+            .collect_vec();
+        if exact_cards.iter().unique().count() != exact_cards.len() {
+            return Err(PokerError::from_string(format!(
+                "Same card used twice in {}",
+                s
+            )));
+        }
+
+        Ok(OmahaHandPattern { cards, suits })
+    }
+}
+
+impl OmahaRange {
+    //Any hand
+    pub fn new_any(variant: OmahaVariant) -> Self {
+        OmahaRange {
+            variant,
+            patterns: vec![OmahaHandPattern {
+                cards: vec![CardPattern::Any; variant.num_hole_cards()],
+                suits: SuitPattern::Any,
+            }],
+        }
+    }
+
+    //True if every hand is in the range, then we can deal random cards instead of listing the hands
+    pub fn is_any(&self) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.suits == SuitPattern::Any && p.cards.iter().all(|c| *c == CardPattern::Any))
+    }
+
+    pub fn matches(&self, hole_cards: &OmahaHoleCards) -> bool {
+        self.patterns.iter().any(|p| p.matches(hole_cards))
+    }
+
+    //Every hand in the range that doesn't use a dead card
+    pub fn get_hole_cards(&self, dead_cards: &[Card]) -> Vec<OmahaHoleCards> {
+        let live_cards = ALL_CARDS
+            .iter()
+            .filter(|c| !dead_cards.contains(c))
+            .cloned()
+            .collect_vec();
+
+        fn recurse(
+            range: &OmahaRange,
+            live_cards: &[Card],
+            start: usize,
+            current: &mut Vec<Card>,
+            out: &mut Vec<OmahaHoleCards>,
+        ) {
+            if current.len() == range.variant.num_hole_cards() {
+                let hole_cards = OmahaHoleCards::new(current).unwrap();
+                if range.matches(&hole_cards) {
+                    out.push(hole_cards);
+                }
+                return;
+            }
+            for card_index in start..live_cards.len() {
+                current.push(live_cards[card_index]);
+                recurse(range, live_cards, card_index + 1, current, out);
+                current.pop();
+            }
+        }
+
+        let mut out = Vec::new();
+        recurse(self, &live_cards, 0, &mut Vec::with_capacity(5), &mut out);
+        out
+    }
+}
+
+impl FromStr for OmahaRange {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let patterns = s
+            .split(',')
+            .map(|p| p.chars().filter(|c| !c.is_whitespace()).collect::<String>())
+            .filter(|p| !p.is_empty())
+            .map(|p| p.parse::<OmahaHandPattern>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let num_hole_cards = patterns
+            .first()
+            .ok_or(PokerError::from_str("Empty Omaha range"))?
+            .cards
+            .len();
+        if patterns.iter().any(|p| p.cards.len() != num_hole_cards) {
+            return Err(PokerError::from_string(format!(
+                "Every hand in the range must have {} cards: {}",
+                num_hole_cards, s
+            )));
+        }
+
+        Ok(OmahaRange {
+            variant: OmahaVariant::from_num_hole_cards(num_hole_cards)?,
+            patterns,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_omaha_range() {
+        let range: OmahaRange = "AAxxds, KhKsQJ".parse().unwrap();
+        assert_eq!(range.variant, OmahaVariant::Plo4);
+
+        let matches = |s: &str| range.matches(&s.parse().unwrap());
+        assert!(matches("AhAd2h3d"));
+        assert!(!matches("AhAd2h3c"));
+        assert!(!matches("AhAd2h3h"));
+        assert!(matches("KhKsQdJc"));
+        assert!(!matches("KhKdQdJc"));
+        assert!(!matches("KhKsQdTc"));
+
+        //6 ways to choose the aces, then 12 non aces of each of their suits, + KhKs with any Q and J
+        let hands = range.get_hole_cards(&[]);
+        assert!(hands.iter().all(|hc| range.matches(hc)));
+        assert_eq!(hands.len(), 6 * 12 * 12 + 4 * 4);
+
+        let dead: Card = "Ah".parse().unwrap();
+        assert!(range
+            .get_hole_cards(&[dead])
+            .iter()
+            .all(|hc| !hc.as_slice().contains(&dead)));
+
+        let range: OmahaRange = "JT98r".parse().unwrap();
+        assert!(range.matches(&"JhTs9d8c".parse().unwrap()));
+        assert!(!range.matches(&"JhTh9d8c".parse().unwrap()));
+
+        assert_eq!(
+            "xxxxx".parse::<OmahaRange>().unwrap().variant,
+            OmahaVariant::Plo5
+        );
+        assert!("JT98r,xxxxx".parse::<OmahaRange>().is_err());
+        assert!("AhAhxx".parse::<OmahaRange>().is_err());
+        assert!("AAx".parse::<OmahaRange>().is_err());
+    }
+}
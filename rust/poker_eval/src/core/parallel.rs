@@ -0,0 +1,43 @@
+/*
+Helpers to split simulations across threads so the results only depend on the seed and the number of threads
+*/
+
+//splitmix64, so nearby seeds / streams give unrelated rngs
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+//The first threads get 1 more iteration if it doesn't divide evenly
+pub fn split_iterations(num_iterations: usize, num_threads: usize) -> Vec<usize> {
+    let num_threads = num_threads.max(1);
+    (0..num_threads)
+        .map(|thread_index| {
+            num_iterations / num_threads + (thread_index < num_iterations % num_threads) as usize
+        })
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_num_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_iterations() {
+        assert_eq!(split_iterations(10, 3), vec![4, 3, 3]);
+        assert_eq!(split_iterations(2, 4), vec![1, 1, 0, 0]);
+        assert_eq!(split_iterations(5, 0), vec![5]);
+
+        assert_ne!(derive_seed(42, 0), derive_seed(42, 1));
+        assert_ne!(derive_seed(42, 0), derive_seed(43, 0));
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+
+use crate::{
+    Board, BoolRange, Card, HoleCards, PokerError, Suit, WeightedRange, ALL_CARDS, ALL_HOLE_CARDS,
+};
+
+/*
+Renaming the suits doesn't change anything strategically, Ah Kh 2c plays exactly like As Ks 2d,
+as long as the hole cards and ranges are renamed the same way.
+
+So we map boards to a canonical form, and keep the suit permutation to apply to everything else.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuitMapping {
+    //mapping[from suit] = to suit
+    mapping: [Suit; 4],
+}
+
+impl Default for SuitMapping {
+    fn default() -> Self {
+        SuitMapping {
+            mapping: Suit::suits(),
+        }
+    }
+}
+
+impl SuitMapping {
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    pub fn new(mapping: [Suit; 4]) -> Result<Self, PokerError> {
+        if mapping.iter().unique().count() != mapping.len() {
+            return Err(PokerError::from_string(format!(
+                "Suit mapping must be a permutation: {:?}",
+                mapping
+            )));
+        }
+        Ok(SuitMapping { mapping })
+    }
+
+    //All 24 permutations, starting with the identity
+    pub fn all() -> impl Iterator<Item = SuitMapping> {
+        Suit::suits()
+            .into_iter()
+            .permutations(4)
+            .map(|suits| SuitMapping {
+                mapping: [suits[0], suits[1], suits[2], suits[3]],
+            })
+    }
+
+    pub fn inverse(&self) -> SuitMapping {
+        let mut mapping = Suit::suits();
+        for from_suit in Suit::suits() {
+            mapping[self.map_suit(from_suit) as usize] = from_suit;
+        }
+        SuitMapping { mapping }
+    }
+
+    pub fn map_suit(&self, suit: Suit) -> Suit {
+        self.mapping[suit as usize]
+    }
+
+    pub fn map_card(&self, card: Card) -> Card {
+        Card::new(card.value, self.map_suit(card.suit))
+    }
+
+    pub fn map_cards(&self, cards: &[Card]) -> Vec<Card> {
+        cards.iter().map(|c| self.map_card(*c)).collect_vec()
+    }
+
+    pub fn map_board(&self, board: &Board) -> Board {
+        Board::new_from_cards(&self.map_cards(board.as_slice_card()))
+    }
+
+    pub fn map_hole_cards(&self, hole_cards: &HoleCards) -> HoleCards {
+        HoleCards::new(
+            self.map_card(hole_cards.get_hi_card()),
+            self.map_card(hole_cards.get_lo_card()),
+        )
+        .unwrap()
+    }
+
+    pub fn map_weighted_range(&self, range: &WeightedRange) -> WeightedRange {
+        let mut mapped = WeightedRange::new();
+        for hole_cards in ALL_HOLE_CARDS.iter() {
+            mapped.data[self.map_hole_cards(hole_cards).to_range_index()] =
+                range.data[hole_cards.to_range_index()];
+        }
+        mapped
+    }
+
+    pub fn map_bool_range(&self, range: &BoolRange) -> BoolRange {
+        let mut mapped = BoolRange::new();
+        for hole_cards in ALL_HOLE_CARDS.iter() {
+            mapped.data.set(
+                self.map_hole_cards(hole_cards).to_range_index(),
+                range.data[hole_cards.to_range_index()],
+            );
+        }
+        mapped
+    }
+}
+
+/*
+True if renaming the suits doesn't change the range, like ranges written as AKs, QQ+ or T9o.
+
+Only then can one flop stand in for all its isomorphic flops without also mapping the range
+*/
+pub fn is_suit_symmetric(range: &WeightedRange) -> bool {
+    SuitMapping::all()
+        .skip(1)
+        .all(|mapping| mapping.map_weighted_range(range) == *range)
+}
+
+//The flop is a set so its cards are sorted highest first, the turn and river keep their place
+fn board_key(cards: &[Card]) -> Vec<u8> {
+    let num_unordered = cards.len().min(3);
+    let mut key = cards.iter().map(|c| c.index).collect_vec();
+    key[..num_unordered].sort_by_key(|i| std::cmp::Reverse(*i));
+    key
+}
+
+/*
+The canonical form of a board, and the suit mapping that takes the board there.
+
+Isomorphic boards (same board up to suits and flop order) always give the same canonical board.
+Apply the mapping to hole cards and ranges to get the matching situation on the canonical board.
+
+When several mappings give the canonical board (suits not on the board can be swapped freely)
+the first one in SuitMapping::all order is used, so the result is deterministic.
+*/
+pub fn canonical_board(cards: &[Card]) -> (Board, SuitMapping) {
+    let (key, mapping) = SuitMapping::all()
+        .map(|mapping| (board_key(&mapping.map_cards(cards)), mapping))
+        .fold(
+            None,
+            |best: Option<(Vec<u8>, SuitMapping)>, (key, mapping)| match best {
+                Some((best_key, best_mapping)) if best_key >= key => Some((best_key, best_mapping)),
+                _ => Some((key, mapping)),
+            },
+        )
+        .unwrap();
+
+    let canonical_cards = key.iter().map(|i| ALL_CARDS[*i as usize]).collect_vec();
+    (Board::new_from_cards(&canonical_cards), mapping)
+}
+
+pub fn is_canonical_board(cards: &[Card]) -> bool {
+    canonical_board(cards).0.as_slice_card() == cards
+}
+
+/*
+The board index of the canonical form of a board as a set of cards, for cache keys.
+
+Unlike canonical_board the turn and river aren't told apart from the flop, as the cached results only
+depend on the set of cards.  The largest index of any suit renaming is used, returned with a mapping that gives it.
+*/
+pub fn canonical_board_index(cards: &[Card]) -> (u32, SuitMapping) {
+    SuitMapping::all()
+        .map(|mapping| {
+            (
+                Board::new_from_cards(&mapping.map_cards(cards)).get_index(),
+                mapping,
+            )
+        })
+        .max_by_key(|(index, _)| *index)
+        .unwrap()
+}
+
+/*
+Same as canonical_board_index but with hole cards, both get the same suit renaming.
+
+Returns the renaming with the largest (board index, hi card, lo card), the board has its index calculated
+*/
+pub fn canonical_board_hole_cards(cards: &[Card], hole_cards: &HoleCards) -> (Board, HoleCards) {
+    SuitMapping::all()
+        .map(|mapping| {
+            let mut board = Board::new_from_cards(&mapping.map_cards(cards));
+            board.get_index();
+            (board, mapping.map_hole_cards(hole_cards))
+        })
+        .max_by_key(|(board, hole_cards)| {
+            (
+                board.get_precalc_index().unwrap(),
+                hole_cards.get_hi_card().index,
+                hole_cards.get_lo_card().index,
+            )
+        })
+        .unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalFlop {
+    //Sorted highest card first
+    pub cards: [Card; 3],
+    //How many of the 22,100 flops map to this one
+    pub multiplicity: u32,
+}
+
+pub const NUMBER_OF_CANONICAL_FLOPS: usize = 1_755;
+
+/*
+The 1,755 strategically distinct flops sorted by their cards, so the position in this list
+is a canonical flop index that caches and reports can share
+*/
+pub static CANONICAL_FLOPS: Lazy<Vec<CanonicalFlop>> = Lazy::new(|| {
+    let mut multiplicities: HashMap<Vec<u8>, u32> = HashMap::new();
+    for flop in ALL_CARDS.iter().combinations(3) {
+        let flop = flop.into_iter().copied().collect_vec();
+        let (canonical, _) = canonical_board(&flop);
+        *multiplicities
+            .entry(
+                canonical
+                    .as_slice_card()
+                    .iter()
+                    .map(|c| c.index)
+                    .collect_vec(),
+            )
+            .or_default() += 1;
+    }
+
+    let flops = multiplicities
+        .into_iter()
+        .sorted()
+        .map(|(key, multiplicity)| CanonicalFlop {
+            cards: [
+                ALL_CARDS[key[0] as usize],
+                ALL_CARDS[key[1] as usize],
+                ALL_CARDS[key[2] as usize],
+            ],
+            multiplicity,
+        })
+        .collect_vec();
+
+    assert_eq!(NUMBER_OF_CANONICAL_FLOPS, flops.len());
+    flops
+});
+
+//The index in CANONICAL_FLOPS of any flop, in any order and with any suits
+pub fn canonical_flop_index(flop: &[Card]) -> Result<usize, PokerError> {
+    if flop.len() != 3 || flop.iter().unique().count() != 3 {
+        return Err(PokerError::from_string(format!(
+            "Flop must be 3 different cards: {:?}",
+            flop
+        )));
+    }
+    let (canonical, _) = canonical_board(flop);
+    CANONICAL_FLOPS
+        .binary_search_by(|f| f.cards.as_slice().cmp(canonical.as_slice_card()))
+        .map_err(|_| PokerError::from_string(format!("No canonical flop for {:?}", flop)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Board::try_from(s).unwrap().as_slice_card().to_vec()
+    }
+
+    #[test]
+    fn test_canonical_board() {
+        let (canonical, mapping) = canonical_board(&cards("Ah Kh 2c Td"));
+        assert_eq!(
+            board_key(&mapping.map_cards(&cards("Ah Kh 2c Td"))),
+            canonical.as_vec_u8()
+        );
+        assert!(is_canonical_board(canonical.as_slice_card()));
+
+        //Same board up to suits and flop order
+        for board in ["Ks 2h As Tc", "2d Ac Kc Th", "As Ks 2d Tc"] {
+            assert_eq!(
+                canonical.to_string(),
+                canonical_board(&cards(board)).0.to_string()
+            );
+        }
+        //The turn isn't part of the flop
+        assert_ne!(
+            canonical.to_string(),
+            canonical_board(&cards("Ah Kh Td 2c")).0.to_string()
+        );
+        assert_ne!(
+            canonical.to_string(),
+            canonical_board(&cards("Ah Kd 2c Td")).0.to_string()
+        );
+
+        //The ranges move with the board
+        let hole_cards: HoleCards = "QhJh".parse().unwrap();
+        let range: WeightedRange = "QhJh:0.5,22".parse().unwrap();
+        let mapped_range = mapping.map_weighted_range(&range);
+        let mapped_hole_cards = mapping.map_hole_cards(&hole_cards);
+        assert_eq!(
+            mapped_range.get_weight_for_holecards(&mapped_hole_cards),
+            0.5
+        );
+        assert_eq!(mapped_range.get_num_combos(), range.get_num_combos());
+        assert_eq!(mapping.inverse().map_weighted_range(&mapped_range), range);
+
+        let bool_range: BoolRange = "QhJh,22".parse().unwrap();
+        let mapped_bool_range = mapping.map_bool_range(&bool_range);
+        assert!(mapped_bool_range.data[mapped_hole_cards.to_range_index()]);
+        assert_eq!(mapped_bool_range.get_num_combos(), 7);
+
+        assert!(!is_suit_symmetric(&range));
+        assert!(is_suit_symmetric(&"AKs,QQ+,T9o:0.5".parse().unwrap()));
+
+        assert!(SuitMapping::new([Suit::Club, Suit::Club, Suit::Heart, Suit::Spade]).is_err());
+    }
+
+    #[test]
+    fn test_canonical_cache_keys() {
+        //Order doesn't matter for the set, including the turn
+        let (index, mapping) = canonical_board_index(&cards("Ah Kh 2c Td"));
+        for board in ["Ks 2h As Tc", "Ah Kh Td 2c", "2d Ac Kc Th"] {
+            assert_eq!(index, canonical_board_index(&cards(board)).0);
+        }
+        assert_ne!(index, canonical_board_index(&cards("Ah Kd 2c Td")).0);
+        assert_eq!(
+            index,
+            Board::new_from_cards(&mapping.map_cards(&cards("Ah Kh 2c Td"))).get_index()
+        );
+
+        let hole_cards = |s: &str| -> HoleCards { s.parse().unwrap() };
+        let (mut board, canonical_hole_cards) =
+            canonical_board_hole_cards(&cards("Ah Kh 2c"), &hole_cards("Qh 3s"));
+        assert_eq!((board.get_index(), canonical_hole_cards), {
+            let (mut b, hc) = canonical_board_hole_cards(&cards("2d Ks As"), &hole_cards("Qs 3c"));
+            (b.get_index(), hc)
+        });
+        //The 3 can be either of the 2 suits not on the board
+        assert_eq!(
+            canonical_hole_cards,
+            canonical_board_hole_cards(&cards("Ah Kh 2c"), &hole_cards("Qh 3d")).1
+        );
+        assert_ne!(
+            canonical_hole_cards,
+            canonical_board_hole_cards(&cards("Ah Kh 2c"), &hole_cards("Qh 3c")).1
+        );
+    }
+
+    #[test]
+    fn test_canonical_flops() {
+        assert_eq!(CANONICAL_FLOPS.len(), NUMBER_OF_CANONICAL_FLOPS);
+        assert_eq!(
+            CANONICAL_FLOPS.iter().map(|f| f.multiplicity).sum::<u32>(),
+            22_100
+        );
+
+        //monotone, 4 suits
+        let index = canonical_flop_index(&cards("Ah Kh Qh")).unwrap();
+        assert_eq!(index, canonical_flop_index(&cards("Qc Kc Ac")).unwrap());
+        assert_eq!(CANONICAL_FLOPS[index].multiplicity, 4);
+        //trips, 4 ways to choose the suits
+        let index = canonical_flop_index(&cards("7h 7d 7s")).unwrap();
+        assert_eq!(CANONICAL_FLOPS[index].multiplicity, 4);
+        //rainbow, 4 * 3 * 2
+        let index = canonical_flop_index(&cards("Ah 7d 2s")).unwrap();
+        assert_eq!(CANONICAL_FLOPS[index].multiplicity, 24);
+        //two tone, 4 * 3 ways, and which 2 of the 3 cards share the suit matters
+        let index = canonical_flop_index(&cards("Ah 7h 2s")).unwrap();
+        assert_eq!(CANONICAL_FLOPS[index].multiplicity, 12);
+        assert_ne!(index, canonical_flop_index(&cards("Ah 7s 2s")).unwrap());
+
+        for (index, flop) in CANONICAL_FLOPS.iter().enumerate() {
+            assert!(is_canonical_board(&flop.cards));
+            assert_eq!(index, canonical_flop_index(&flop.cards).unwrap());
+        }
+
+        assert!(canonical_flop_index(&cards("Ah Kh")).is_err());
+        assert!(canonical_flop_index(&cards("Ah Kh Ah")).is_err());
+    }
+}
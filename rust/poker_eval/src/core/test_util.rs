@@ -1,5 +1,7 @@
 use std::io::Write;
 
+use boomphf::Mphf;
+
 #[cfg(not(target_arch = "wasm32"))]
 use log::debug;
 
@@ -10,8 +12,7 @@ use crate::game_log_source::GameLogSource;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::{GameLog, GameRunner};
 
-#[cfg(not(target_arch = "wasm32"))]
-use crate::PokerError;
+use crate::{pre_calc::perfect_hash::BOOM_PERFECT_HASH, Board, Card, HoleCards, PokerError};
 
 pub fn init_test_logger() {
     let _ = env_logger::builder()
@@ -72,6 +73,20 @@ pub fn init_logger() {
         .try_init();
 }
 
+/*
+For the tests of the eval functions taking hole cards and a board,
+parses both and runs f with the shared hash
+*/
+pub fn eval_test_deal<T>(
+    hole_cards: &str,
+    board: &str,
+    f: impl FnOnce(&HoleCards, &[Card], &Mphf<u32>) -> Result<T, PokerError>,
+) -> T {
+    let hole_cards: HoleCards = hole_cards.parse().unwrap();
+    let board: Board = board.parse().unwrap();
+    f(&hole_cards, board.as_slice_card(), &BOOM_PERFECT_HASH).unwrap()
+}
+
 #[allow(dead_code)]
 fn take_after_last_slash(s: &str) -> &str {
     let mut last_slash = 0;
@@ -0,0 +1,372 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    offsuit_indices, pair_indices, pre_calc::NUMBER_OF_HOLE_CARDS, range_element_indices,
+    suited_indices, BoolRange, Card, CardValue, HoleCards, PokerError, ALL_HOLE_CARDS, COMBO_PAT,
+    TRIM_REGEX,
+};
+use itertools::Itertools;
+use log::trace;
+use once_cell::sync::Lazy;
+use rand::distributions::WeightedIndex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/*
+Like BoolRange, but each hole card combo has a weight between 0 and 1
+so we can represent mixed strategies, ex:
+
+AKs:0.5,QQ+:0.75,T2o:0.45
+
+An element without a weight has weight 1
+*/
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct WeightedRange {
+    //indexed by HoleCards::to_range_index
+    pub data: Vec<f32>,
+}
+
+const WEIGHT_PAT: &str = r"(?:(?:[01](?:\.\d*)?)|(?:\.\d+))";
+static WEIGHTED_RANGE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"^(?P<range>{COMBO_PAT}(?:\+|(?:-{COMBO_PAT}))?)(?::(?P<weight>{WEIGHT_PAT}))?$"
+    ))
+    .unwrap()
+});
+
+impl Default for WeightedRange {
+    fn default() -> Self {
+        WeightedRange {
+            data: vec![0.0; NUMBER_OF_HOLE_CARDS],
+        }
+    }
+}
+
+//Hole cards of a range along with what we need to sample them in proportion to their weights
+pub struct WeightedHoleCards {
+    pub hole_cards: Vec<HoleCards>,
+    //None when every combo has the same weight, then we can just choose uniformly
+    pub dist: Option<WeightedIndex<f32>>,
+}
+
+impl WeightedRange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn set_weight(&mut self, indices: &[usize], weight: f32) {
+        for &i in indices {
+            self.data[i] = weight;
+        }
+    }
+
+    pub fn set_weight_pair(&mut self, rank: CardValue, weight: f32) {
+        self.set_weight(&pair_indices(rank), weight)
+    }
+    pub fn set_weight_suited(&mut self, rank1: CardValue, rank2: CardValue, weight: f32) {
+        self.set_weight(&suited_indices(rank1, rank2), weight)
+    }
+    pub fn set_weight_offsuit(&mut self, rank1: CardValue, rank2: CardValue, weight: f32) {
+        self.set_weight(&offsuit_indices(rank1, rank2), weight)
+    }
+
+    pub fn get_weight_for_holecards(&self, hc: &HoleCards) -> f32 {
+        self.data[hc.to_range_index()]
+    }
+
+    //Average weight of the combos, used for the 13x13 grid
+    pub fn get_weight_pair(&self, rank: CardValue) -> f32 {
+        self.get_weight(&pair_indices(rank))
+    }
+    pub fn get_weight_suited(&self, rank1: CardValue, rank2: CardValue) -> f32 {
+        self.get_weight(&suited_indices(rank1, rank2))
+    }
+    pub fn get_weight_offsuit(&self, rank1: CardValue, rank2: CardValue) -> f32 {
+        self.get_weight(&offsuit_indices(rank1, rank2))
+    }
+
+    fn get_weight(&self, indices: &[usize]) -> f32 {
+        let total: f32 = indices.iter().map(|&i| self.data[i]).sum();
+        total / indices.len() as f32
+    }
+
+    //Sum of the weights, so AA:0.5 is 3 combos
+    pub fn get_num_combos(&self) -> f64 {
+        self.data.iter().map(|w| *w as f64).sum()
+    }
+
+    pub fn get_all_enabled_holecards_with_weights(&self) -> Vec<(HoleCards, f32)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w > 0.0)
+            .map(|(i, w)| (ALL_HOLE_CARDS[i], *w))
+            .collect_vec()
+    }
+
+    //Any combo with a non zero weight is in the range
+    pub fn to_bool_range(&self) -> BoolRange {
+        let mut result = BoolRange::default();
+        for (i, w) in self.data.iter().enumerate() {
+            if *w > 0.0 {
+                result.data.set(i, true);
+            }
+        }
+        result
+    }
+
+    //Same as the BoolRange operations; the weight of a combo in the union is the max of the two
+    pub fn union(&self, other: &WeightedRange) -> WeightedRange {
+        self.combine(other, |w1, w2| w1.max(w2))
+    }
+
+    pub fn intersection(&self, other: &WeightedRange) -> WeightedRange {
+        self.combine(other, |w1, w2| w1.min(w2))
+    }
+
+    //Removes any combo that is in the other range
+    pub fn difference(&self, other: &WeightedRange) -> WeightedRange {
+        self.combine(other, |w1, w2| if w2 > 0.0 { 0.0 } else { w1 })
+    }
+
+    pub fn complement(&self) -> WeightedRange {
+        WeightedRange {
+            data: self.data.iter().map(|w| 1.0 - w).collect_vec(),
+        }
+    }
+
+    fn combine<F>(&self, other: &WeightedRange, f: F) -> WeightedRange
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        WeightedRange {
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(w1, w2)| f(*w1, *w2))
+                .collect_vec(),
+        }
+    }
+
+    pub fn remove_cards(&mut self, dead_cards: &[Card]) {
+        for (i, hc) in ALL_HOLE_CARDS.iter().enumerate() {
+            if dead_cards
+                .iter()
+                .any(|c| *c == hc.get_hi_card() || *c == hc.get_lo_card())
+            {
+                self.data[i] = 0.0;
+            }
+        }
+    }
+
+    pub fn get_weighted_hole_cards(&self) -> Result<WeightedHoleCards, PokerError> {
+        let with_weights = self.get_all_enabled_holecards_with_weights();
+
+        if with_weights.is_empty() {
+            return Err(PokerError::from_str("Range has no hole cards"));
+        }
+
+        let is_uniform = with_weights.iter().all(|(_, w)| *w == with_weights[0].1);
+
+        let dist = if is_uniform {
+            None
+        } else {
+            Some(
+                WeightedIndex::new(with_weights.iter().map(|(_, w)| *w)).map_err(|e| {
+                    PokerError::from_string(format!("Unable to build weights: {}", e))
+                })?,
+            )
+        };
+
+        Ok(WeightedHoleCards {
+            hole_cards: with_weights.into_iter().map(|(hc, _)| hc).collect_vec(),
+            dist,
+        })
+    }
+}
+
+impl From<&BoolRange> for WeightedRange {
+    fn from(bool_range: &BoolRange) -> Self {
+        let mut result = WeightedRange::default();
+        for i in bool_range.data.iter_ones() {
+            if i >= NUMBER_OF_HOLE_CARDS {
+                break;
+            }
+            result.data[i] = 1.0;
+        }
+        result
+    }
+}
+
+impl FromStr for WeightedRange {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = TRIM_REGEX.replace_all(s, "$1").trim().to_string();
+        let mut ranges = s.split(',').collect::<Vec<_>>();
+
+        // remove last empty element if any
+        if ranges.last().unwrap().is_empty() {
+            ranges.pop();
+        }
+
+        let mut result = Self::new();
+
+        //Reversed so that the first element wins if they overlap, same as BoolRange
+        for range in ranges.into_iter().rev() {
+            let caps = WEIGHTED_RANGE_REGEX
+                .captures(range)
+                .ok_or_else(|| format!("Failed to parse range: {range}"))?;
+
+            let weight = caps.name("weight").map_or(Ok(1.0), |w| {
+                w.as_str()
+                    .parse::<f32>()
+                    .map_err(|e| format!("Invalid weight {}: {}", w.as_str(), e))
+            })?;
+
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(format!("Weight must be between 0 and 1: {range}").into());
+            }
+
+            let range = caps.name("range").unwrap().as_str();
+
+            trace!("range: {} weight: {}", range, weight);
+
+            result.set_weight(&range_element_indices(range)?, weight);
+        }
+
+        Ok(result)
+    }
+}
+
+impl Display for WeightedRange {
+    /*
+    Groups the combos by weight and reuses the BoolRange string for each group,
+    the full weight group comes first and has no :1 suffix
+    */
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let weights = self
+            .data
+            .iter()
+            .filter(|w| **w > 0.0)
+            .map(|w| w.to_bits())
+            .unique()
+            .map(f32::from_bits)
+            .sorted_by(|a, b| b.partial_cmp(a).unwrap())
+            .collect_vec();
+
+        let mut result: Vec<String> = Vec::new();
+
+        for weight in weights {
+            let mut group = BoolRange::default();
+            for (i, w) in self.data.iter().enumerate() {
+                if *w == weight {
+                    group.data.set(i, true);
+                }
+            }
+
+            for element in group.to_string().split(',') {
+                if weight == 1.0 {
+                    result.push(element.to_string());
+                } else {
+                    result.push(format!("{}:{}", element, weight));
+                }
+            }
+        }
+
+        write!(f, "{}", result.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_range_from_str() {
+        let range: WeightedRange = "AKs:0.5,QQ+:0.75,72o".parse().unwrap();
+
+        let aks: HoleCards = "As Ks".parse().unwrap();
+        let ako: HoleCards = "As Kd".parse().unwrap();
+        let kk: HoleCards = "Kc Kd".parse().unwrap();
+        let jj: HoleCards = "Jc Jd".parse().unwrap();
+        let sev_two: HoleCards = "7c 2d".parse().unwrap();
+
+        assert_eq!(range.get_weight_for_holecards(&aks), 0.5);
+        assert_eq!(range.get_weight_for_holecards(&ako), 0.0);
+        assert_eq!(range.get_weight_for_holecards(&kk), 0.75);
+        assert_eq!(range.get_weight_for_holecards(&jj), 0.0);
+        assert_eq!(range.get_weight_for_holecards(&sev_two), 1.0);
+
+        assert_eq!(range.get_weight_pair(CardValue::Ace), 0.75);
+        assert_eq!(
+            range.get_weight_suited(CardValue::Ace, CardValue::King),
+            0.5
+        );
+
+        //4 * 0.5 + 18 * 0.75 + 12
+        assert!((range.get_num_combos() - 27.5).abs() < 1e-5);
+
+        //first element wins
+        let range: WeightedRange = "AA:0.25,AA".parse().unwrap();
+        assert_eq!(range.get_weight_pair(CardValue::Ace), 0.25);
+
+        assert!("AQo:1.1".parse::<WeightedRange>().is_err());
+        assert!("AQo:".parse::<WeightedRange>().is_err());
+        assert!("AQo:.5".parse::<WeightedRange>().is_ok());
+        assert!("".parse::<WeightedRange>().is_ok());
+    }
+
+    #[test]
+    fn test_weighted_range_to_string() {
+        let range: WeightedRange = "QQ+:0.75,AKs:0.5,72o,T2o:0.45".parse().unwrap();
+        let range_str = range.to_string();
+        assert_eq!(range_str, "72o,QQ+:0.75,AKs:0.5,T2o:0.45");
+
+        let range2: WeightedRange = range_str.parse().unwrap();
+        assert_eq!(range, range2);
+
+        //Without weights it's the same as a bool range
+        let bool_range: BoolRange = "22+,A2s+,KTo+".parse().unwrap();
+        let range = WeightedRange::from(&bool_range);
+        assert_eq!(range.to_string(), bool_range.to_string());
+        assert_eq!(range.to_bool_range(), bool_range);
+    }
+
+    #[test]
+    fn test_weighted_range_algebra() {
+        let r1: WeightedRange = "AA:0.5,KK".parse().unwrap();
+        let r2: WeightedRange = "AA,KK:0.25,QQ:0.5".parse().unwrap();
+
+        assert_eq!(r1.union(&r2), "AA,KK,QQ:0.5".parse().unwrap());
+        assert_eq!(r1.intersection(&r2), "AA:0.5,KK:0.25".parse().unwrap());
+        assert_eq!(r2.difference(&r1), "QQ:0.5".parse().unwrap());
+
+        let complement = r1.complement();
+        assert_eq!(complement.get_weight_pair(CardValue::Ace), 0.5);
+        assert_eq!(complement.get_weight_pair(CardValue::King), 0.0);
+        assert_eq!(complement.get_weight_pair(CardValue::Queen), 1.0);
+
+        let mut r3 = r1.clone();
+        r3.remove_cards(&["Ac".parse().unwrap(), "Kd".parse().unwrap()]);
+        assert!((r3.get_num_combos() - (3.0 * 0.5 + 3.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_weighted_hole_cards() {
+        let range: WeightedRange = "AA,KK".parse().unwrap();
+        let whc = range.get_weighted_hole_cards().unwrap();
+        assert_eq!(whc.hole_cards.len(), 12);
+        assert!(whc.dist.is_none());
+
+        let range: WeightedRange = "AA,KK:0.5".parse().unwrap();
+        let whc = range.get_weighted_hole_cards().unwrap();
+        assert_eq!(whc.hole_cards.len(), 12);
+        assert!(whc.dist.is_some());
+
+        let range = WeightedRange::default();
+        assert!(range.get_weighted_hole_cards().is_err());
+    }
+}
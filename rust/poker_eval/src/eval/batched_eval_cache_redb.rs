@@ -0,0 +1,391 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, RwLock},
+};
+
+use log::{error, info};
+use redb::Database;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    board_eval_cache_redb::{check_schema_version, get_data_path, redb_error, EvalCacheEnum},
+    EvalCacheBackend, PokerError, SharedEvalCacheBackend,
+};
+
+pub const DEFAULT_WRITE_BATCH_SIZE: usize = 1_000;
+
+//Called with each stored key and its bincode bytes, returns false to stop
+pub type ReDbEntryVisitor<'a, K> = dyn FnMut(K, &[u8]) -> Result<bool, PokerError> + 'a;
+
+//How a key is stored in its redb table, the tables are defined next to the single threaded caches
+pub trait ReDbKey: Eq + Hash + Clone + Send {
+    fn create_table(db: &Database) -> Result<(), PokerError>;
+
+    fn read(db: &Database, key: &Self) -> Result<Option<Vec<u8>>, PokerError>;
+
+    fn count(db: &Database) -> Result<u64, PokerError>;
+
+    //Removes every entry, the schema version stamp is kept
+    fn clear(db: &Database) -> Result<(), PokerError>;
+
+    fn for_each(db: &Database, f: &mut ReDbEntryVisitor<Self>) -> Result<(), PokerError>;
+
+    //All in one write transaction
+    fn write_batch(db: &Database, batch: &HashMap<Self, Vec<u8>>) -> Result<(), PokerError>;
+}
+
+/*
+A redb file that can be shared between threads (it is Send + Sync).
+
+Reads go straight to redb, which allows any number of concurrent read transactions.
+
+Misses are buffered and written batch_size at a time in a single write transaction,
+instead of a transaction per put.  Buffered values are still returned by get.
+
+A full batch is swapped out of pending and written without holding the pending lock, so other
+threads can keep putting while it commits.  Until then it is kept in in_flight for get.
+
+Whatever is pending is written on flush or drop.
+*/
+//Keys to their bincode bytes, written in one transaction
+type WriteBatch<K> = HashMap<K, Vec<u8>>;
+
+pub struct BatchedReDb<K: ReDbKey> {
+    db: Database,
+    pending: Mutex<HashMap<K, Vec<u8>>>,
+    in_flight: RwLock<Vec<Arc<WriteBatch<K>>>>,
+    batch_size: usize,
+}
+
+impl<K: ReDbKey> BatchedReDb<K> {
+    pub fn new(cache_name: EvalCacheEnum, batch_size: usize) -> Result<Self, PokerError> {
+        let store = Self::open(&get_data_path(cache_name)?, batch_size)?;
+        check_schema_version::<K>(&store.db, cache_name)?;
+        Ok(store)
+    }
+
+    pub fn open(path: &Path, batch_size: usize) -> Result<Self, PokerError> {
+        info!("Opening db {:?} with write batches of {}", path, batch_size);
+        let db = Database::create(path).map_err(redb_error)?;
+        K::create_table(&db)?;
+
+        Ok(Self {
+            db,
+            pending: Mutex::new(HashMap::with_capacity(batch_size)),
+            in_flight: RwLock::new(Vec::new()),
+            batch_size: batch_size.max(1),
+        })
+    }
+
+    pub fn get<V: DeserializeOwned>(&self, key: &K) -> Result<Option<V>, PokerError> {
+        let bytes = match self.get_unwritten(key)? {
+            Some(bytes) => Some(bytes),
+            None => K::read(&self.db, key)?,
+        };
+
+        match bytes {
+            Some(bytes) => bincode::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| PokerError::from_string(format!("Unable to deserialize: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put<V: Serialize>(&self, key: K, value: &V) -> Result<(), PokerError> {
+        let bytes = bincode::serialize(value)
+            .map_err(|e| PokerError::from_string(format!("Unable to serialize: {}", e)))?;
+        self.put_bytes(key, bytes)
+    }
+
+    //Already serialized with bincode
+    pub fn put_bytes(&self, key: K, bytes: Vec<u8>) -> Result<(), PokerError> {
+        let mut pending = self.lock_pending()?;
+        pending.insert(key, bytes);
+
+        if pending.len() >= self.batch_size {
+            let batch = self.take_pending(&mut pending)?;
+            drop(pending);
+            self.write_in_flight(batch)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), PokerError> {
+        let mut pending = self.lock_pending()?;
+        if !pending.is_empty() {
+            let batch = self.take_pending(&mut pending)?;
+            drop(pending);
+            self.write_in_flight(batch)?;
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, key: &K) -> Result<bool, PokerError> {
+        if self.get_unwritten(key)?.is_some() {
+            return Ok(true);
+        }
+        Ok(K::read(&self.db, key)?.is_some())
+    }
+
+    //Written entries only, flush first to include the pending ones
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.lock_pending().map(|p| p.len()).unwrap_or(0)
+    }
+
+    fn lock_pending(&self) -> Result<MutexGuard<'_, HashMap<K, Vec<u8>>>, PokerError> {
+        self.pending
+            .lock()
+            .map_err(|_| PokerError::from_str("Pending writes lock poisoned"))
+    }
+
+    //Pending first, a value only moves pending -> in flight -> db so checking in that order never misses it
+    fn get_unwritten(&self, key: &K) -> Result<Option<Vec<u8>>, PokerError> {
+        if let Some(bytes) = self.lock_pending()?.get(key) {
+            return Ok(Some(bytes.clone()));
+        }
+
+        let in_flight = self
+            .in_flight
+            .read()
+            .map_err(|_| PokerError::from_str("In flight writes lock poisoned"))?;
+        Ok(in_flight.iter().find_map(|batch| batch.get(key)).cloned())
+    }
+
+    //Called with the pending lock held, so the batch is in flight before anyone can see it gone from pending
+    fn take_pending(
+        &self,
+        pending: &mut HashMap<K, Vec<u8>>,
+    ) -> Result<Arc<WriteBatch<K>>, PokerError> {
+        let batch = Arc::new(std::mem::replace(
+            pending,
+            HashMap::with_capacity(self.batch_size),
+        ));
+        self.in_flight
+            .write()
+            .map_err(|_| PokerError::from_str("In flight writes lock poisoned"))?
+            .push(batch.clone());
+        Ok(batch)
+    }
+
+    fn write_in_flight(&self, batch: Arc<WriteBatch<K>>) -> Result<(), PokerError> {
+        let result = K::write_batch(&self.db, &batch);
+
+        //Only dropped once committed, on an error the values stay readable but are not retried
+        if result.is_ok() {
+            self.in_flight
+                .write()
+                .map_err(|_| PokerError::from_str("In flight writes lock poisoned"))?
+                .retain(|b| !Arc::ptr_eq(b, &batch));
+        }
+
+        result
+    }
+}
+
+impl<K: ReDbKey> Drop for BatchedReDb<K> {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            error!("Unable to write pending cache entries: {}", e);
+        }
+    }
+}
+
+//So the single threaded EvalCache can also batch its writes
+impl<K, V> EvalCacheBackend<K, V> for BatchedReDb<K>
+where
+    K: ReDbKey,
+    V: Serialize + DeserializeOwned,
+{
+    fn get(&mut self, key: &K) -> Result<Option<V>, PokerError> {
+        BatchedReDb::get(self, key)
+    }
+
+    fn put(&mut self, key: K, value: &V) -> Result<(), PokerError> {
+        BatchedReDb::put(self, key, value)
+    }
+}
+
+impl<K, V> SharedEvalCacheBackend<K, V> for BatchedReDb<K>
+where
+    K: ReDbKey + Sync,
+    V: Serialize + DeserializeOwned,
+{
+    fn get(&self, key: &K) -> Result<Option<V>, PokerError> {
+        BatchedReDb::get(self, key)
+    }
+
+    fn put(&self, key: K, value: &V) -> Result<(), PokerError> {
+        BatchedReDb::put(self, key, value)
+    }
+
+    fn flush(&self) -> Result<(), PokerError> {
+        BatchedReDb::flush(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        board_eval_cache_redb::ProduceFlopTexture,
+        board_hc_eval_cache_redb::ProducePartialRankCards, canonical_board_index, Board, Deck,
+        HoleCards, SharedEvalCache, SharedEvalCacheWithHc,
+    };
+
+    fn temp_db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.db", name, std::process::id()));
+        std::fs::remove_file(&path).unwrap_or_default();
+        path
+    }
+
+    #[test]
+    fn test_shared_cache_threads() {
+        let path = temp_db_path("test_shared_cache_threads");
+
+        //Same boards and hole cards in every thread
+        let mut deck = Deck::new_with_seed(42);
+        let deals: Vec<(Board, HoleCards)> = (0..50)
+            .map(|_| {
+                deck.reset();
+                let mut board = deck.choose_new_board();
+                board.truncate(3);
+                let mut board = Board::new_from_cards(&board);
+                board.get_index();
+                let hole_cards = HoleCards::new(
+                    deck.get_unused_card().unwrap().try_into().unwrap(),
+                    deck.get_unused_card().unwrap().try_into().unwrap(),
+                )
+                .unwrap();
+                (board, hole_cards)
+            })
+            .collect();
+
+        let num_threads = 4;
+        {
+            let store = Arc::new(BatchedReDb::open(&path, 16).unwrap());
+            let cache: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>> =
+                Arc::new(SharedEvalCacheWithHc::new(Box::new(store.clone())));
+
+            std::thread::scope(|s| {
+                for _ in 0..num_threads {
+                    let cache = cache.clone();
+                    let deals = &deals;
+                    s.spawn(move || {
+                        for (board, hole_cards) in deals.iter() {
+                            cache.get_put(board, hole_cards, 0).unwrap();
+                        }
+                    });
+                }
+            });
+
+            //Counted across threads, every deal was a miss at most once per thread
+            assert_eq!(
+                cache.cache_hits() + cache.cache_misses(),
+                num_threads * deals.len() as u32
+            );
+            assert!(cache.cache_misses() >= deals.len() as u32);
+            assert!(cache.cache_hits() > 0);
+
+            cache.flush().unwrap();
+            assert_eq!(store.num_pending(), 0);
+        }
+
+        //Everything was written
+        let cache: SharedEvalCacheWithHc<ProducePartialRankCards> =
+            SharedEvalCacheWithHc::new(Box::new(BatchedReDb::open(&path, 16).unwrap()));
+        for (board, hole_cards) in deals.iter() {
+            cache.get_put(board, hole_cards, 0).unwrap();
+        }
+        assert_eq!(cache.cache_misses(), 0);
+        assert_eq!(cache.cache_hits(), deals.len() as u32);
+
+        drop(cache);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_batched_writes() {
+        let path = temp_db_path("test_batched_writes");
+
+        let store = Arc::new(BatchedReDb::open(&path, 3).unwrap());
+        let cache: SharedEvalCache<ProduceFlopTexture> =
+            SharedEvalCache::new(Box::new(store.clone()));
+
+        let mut boards: Vec<Board> = ["Ah 7d 2c", "Ks Qs 3d", "Ah 7d 2c 9h", "2c 2d 2h"]
+            .iter()
+            .map(|s| Board::try_from(*s).unwrap())
+            .collect();
+        for b in boards.iter_mut() {
+            b.get_index();
+        }
+
+        cache.get_put(&boards[0]).unwrap();
+        cache.get_put(&boards[1]).unwrap();
+        assert_eq!(store.num_pending(), 2);
+
+        //Still pending but already visible
+        let texture = cache.get_put(&boards[0]).unwrap();
+        assert_eq!(texture.same_suited_max_count, 1);
+        assert_eq!(cache.cache_hits(), 1);
+
+        //3rd miss fills the batch
+        cache.get_put(&boards[2]).unwrap();
+        assert_eq!(store.num_pending(), 0);
+
+        cache.get_put(&boards[3]).unwrap();
+        assert_eq!(store.num_pending(), 1);
+        cache.flush().unwrap();
+        assert_eq!(store.num_pending(), 0);
+
+        for b in boards.iter() {
+            let index = canonical_board_index(b.as_slice_card()).0;
+            assert!(store.contains(&index).unwrap());
+        }
+        assert_eq!(cache.cache_misses(), 4);
+
+        drop(cache);
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_in_flight_visible() {
+        let path = temp_db_path("test_in_flight_visible");
+
+        let store: BatchedReDb<u32> = BatchedReDb::open(&path, 10).unwrap();
+        store.put(1, &7u8).unwrap();
+        store.put(2, &8u8).unwrap();
+
+        //Swapped out of pending but not yet written
+        let batch = store
+            .take_pending(&mut store.lock_pending().unwrap())
+            .unwrap();
+        assert_eq!(store.num_pending(), 0);
+        assert_eq!(store.get::<u8>(&1).unwrap(), Some(7));
+        assert!(store.contains(&2).unwrap());
+        assert_eq!(<u32 as ReDbKey>::read(store.database(), &1).unwrap(), None);
+
+        //Puts are not blocked by the batch being written
+        store.put(3, &9u8).unwrap();
+
+        store.write_in_flight(batch).unwrap();
+        assert!(store.in_flight.read().unwrap().is_empty());
+        assert_eq!(store.get::<u8>(&1).unwrap(), Some(7));
+        assert_eq!(store.get::<u8>(&3).unwrap(), Some(9));
+        assert!(<u32 as ReDbKey>::read(store.database(), &1)
+            .unwrap()
+            .is_some());
+
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,20 @@
-use std::{env, path::PathBuf};
+use std::str::FromStr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::HashMap, env, path::PathBuf};
 
-use redb::{Database, Error as ReDbError, ReadTransaction, ReadableTable, TableDefinition};
+#[cfg(not(target_arch = "wasm32"))]
+use redb::{Database, ReadTransaction, ReadableTable, TableDefinition};
+#[cfg(not(target_arch = "wasm32"))]
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{calc_board_texture, Board, BoardTexture, Card};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{ReDbEntryVisitor, ReDbKey};
+
+use crate::{
+    calc_board_texture, canonical_board_index, Board, BoardTexture, Card, EvalCacheBackend,
+    InMemoryBackend, LruBackend, PokerError, SuitMapping,
+};
+#[cfg(not(target_arch = "wasm32"))]
 use dotenv::dotenv;
 
 //u32 is usually  enough
@@ -13,123 +24,367 @@ use dotenv::dotenv;
 // need 22 bits for 52 choose 5
 // need 11 bits for 52 choose 2
 
+//The cached values are bincode, see EvalCacheEnum::get_schema_version when a result changes
+#[cfg(not(target_arch = "wasm32"))]
 const PARTIAL_RANK_FILENAME: &str = "partial_rank_re.db";
+#[cfg(not(target_arch = "wasm32"))]
 const FLOP_TEXTURE_FILENAME: &str = "flop_texture_re.db";
+#[cfg(not(target_arch = "wasm32"))]
 const MONTE_CARLO_EVAL_FILENAME: &str = "monte_carlo_eval_re.db";
+#[cfg(not(target_arch = "wasm32"))]
+const HAND_STRENGTH_FILENAME: &str = "hand_strength_re.db";
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EvalCacheEnum {
     PartialRank,
     FlopTexture,
     MonteCarloEval,
+    HandStrength,
 }
 
-pub fn get_data_path(cache_name: EvalCacheEnum) -> PathBuf {
+impl EvalCacheEnum {
+    pub fn all() -> [EvalCacheEnum; 4] {
+        [
+            EvalCacheEnum::PartialRank,
+            EvalCacheEnum::FlopTexture,
+            EvalCacheEnum::MonteCarloEval,
+            EvalCacheEnum::HandStrength,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvalCacheEnum::PartialRank => "partial_rank",
+            EvalCacheEnum::FlopTexture => "flop_texture",
+            EvalCacheEnum::MonteCarloEval => "monte_carlo",
+            EvalCacheEnum::HandStrength => "hand_strength",
+        }
+    }
+
+    /*
+    Stamped in the db, bump it when the producer's results change (or the result struct does).
+
+    Entries under an older stamp are stale, cache_maint drop-stale clears them.
+    Files written before there was a stamp count as UNSTAMPED_SCHEMA_VERSION.
+    */
+    pub fn get_schema_version(&self) -> u32 {
+        match self {
+            //Nut flush draws, combo draws and backdoor straight draws were added
+            EvalCacheEnum::PartialRank => 2,
+            EvalCacheEnum::FlopTexture => 1,
+            EvalCacheEnum::MonteCarloEval => 1,
+            EvalCacheEnum::HandStrength => 1,
+        }
+    }
+}
+
+impl FromStr for EvalCacheEnum {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EvalCacheEnum::all()
+            .into_iter()
+            .find(|c| c.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                PokerError::from_string(format!(
+                    "Unknown cache {}, expected one of {}",
+                    s,
+                    EvalCacheEnum::all().map(|c| c.name()).join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_data_path(cache_name: EvalCacheEnum) -> Result<PathBuf, PokerError> {
     let file_name = match cache_name {
         EvalCacheEnum::PartialRank => PARTIAL_RANK_FILENAME,
         EvalCacheEnum::FlopTexture => FLOP_TEXTURE_FILENAME,
         EvalCacheEnum::MonteCarloEval => MONTE_CARLO_EVAL_FILENAME,
+        EvalCacheEnum::HandStrength => HAND_STRENGTH_FILENAME,
     };
 
     dotenv().ok();
 
-    let data_dir = env::var("DATA_DIR").unwrap();
+    let data_dir = env::var("DATA_DIR").map_err(|_| {
+        PokerError::from_string(format!(
+            "DATA_DIR is not set, needed for {}; use an in memory cache instead",
+            file_name
+        ))
+    })?;
 
-    let path = PathBuf::from(data_dir).join(file_name);
-    path
+    Ok(PathBuf::from(data_dir).join(file_name))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 const TABLE: TableDefinition<u32, &[u8]> = TableDefinition::new("eval_cache");
 
+#[cfg(not(target_arch = "wasm32"))]
+const META_TABLE: TableDefinition<&str, u32> = TableDefinition::new("eval_cache_meta");
+#[cfg(not(target_arch = "wasm32"))]
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+pub const UNSTAMPED_SCHEMA_VERSION: u32 = 1;
+
+//None if the db was never stamped
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_schema_version(db: &Database) -> Result<Option<u32>, PokerError> {
+    let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+    let table = match read_txn.open_table(META_TABLE) {
+        Ok(table) => table,
+        Err(redb::TableError::TableDoesNotExist(_)) => return Ok(None),
+        Err(e) => return Err(redb_error(e)),
+    };
+
+    let version = table.get(SCHEMA_VERSION_KEY).map_err(redb_error)?;
+    Ok(version.map(|v| v.value()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_schema_version(db: &Database, version: u32) -> Result<(), PokerError> {
+    let write_txn = db.begin_write().map_err(redb_error)?;
+    {
+        let mut table = write_txn.open_table(META_TABLE).map_err(redb_error)?;
+        table
+            .insert(SCHEMA_VERSION_KEY, version)
+            .map_err(redb_error)?;
+    }
+    write_txn.commit().map_err(redb_error)
+}
+
+/*
+Stamps a new db with the producer's schema version.
+
+Any other stamp is an error, the stale entries would not decode into the current result.
+cache_maint drop-stale opens the db by path to clear it.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+pub fn check_schema_version<K: ReDbKey>(
+    db: &Database,
+    cache_name: EvalCacheEnum,
+) -> Result<(), PokerError> {
+    let current_version = cache_name.get_schema_version();
+    let stored_version = match read_schema_version(db)? {
+        Some(version) => version,
+        None if K::count(db)? == 0 => return write_schema_version(db, current_version),
+        None => UNSTAMPED_SCHEMA_VERSION,
+    };
+
+    if stored_version != current_version {
+        return Err(PokerError::from_string(format!(
+            "The {} cache has schema version {} but its producer is at version {}, \
+            run cache_maint drop-stale {}",
+            cache_name.name(),
+            stored_version,
+            current_version,
+            cache_name.name()
+        )));
+    }
+
+    Ok(())
+}
+
 pub trait ProduceEvalResult {
     type Result;
 
     fn produce_eval_result(cards: &[Card]) -> Self::Result;
 
     fn get_cache_name() -> EvalCacheEnum;
+
+    /*
+    The cache is keyed by the canonical board, see canonical_board_index.
+    Gets the board's result from the canonical board's, only needed when the result has suits in it.
+    */
+    fn from_canonical_result(result: Self::Result, _to_canonical: &SuitMapping) -> Self::Result {
+        result
+    }
 }
 
-//P is producer type
-//R is result
-//K is the key type
-pub struct EvalCacheReDb<P> {
+//Keyed by the board index, one file per producer
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ReDbBackend {
     db: Database,
-    pub cache_hits: u32,
-    pub cache_misses: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ReDbBackend {
+    pub fn new(cache_name: EvalCacheEnum) -> Result<Self, PokerError> {
+        let db_name = get_data_path(cache_name)?;
+        let db = Database::create(db_name).map_err(redb_error)?;
+        //Make sure table exists
+        <u32 as ReDbKey>::create_table(&db)?;
+        check_schema_version::<u32>(&db, cache_name)?;
 
-    //We don't actually need an instance
-    phantom1: std::marker::PhantomData<P>,
-    //phantom2: std::marker::PhantomData<R>,
+        Ok(Self { db })
+    }
 }
 
-impl<P> EvalCacheReDb<P>
+#[cfg(not(target_arch = "wasm32"))]
+impl<V> EvalCacheBackend<u32, V> for ReDbBackend
 where
-    P: ProduceEvalResult,
-    P::Result: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
 {
-    //each different struct should get its own db path
-    pub fn new() -> Result<Self, ReDbError> {
-        let db_name = get_data_path(P::get_cache_name());
-        let db = Database::create(db_name)?;
+    fn get(&mut self, index: &u32) -> Result<Option<V>, PokerError> {
+        let read_txn: ReadTransaction = self.db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+
+        let data = table.get(index).map_err(redb_error)?;
+        if let Some(data) = data {
+            //let texture: BoardTexture = rmp_serde::from_slice(data.value()).unwrap();
+            let texture: V = bincode::deserialize(&data.value()).unwrap();
+
+            Ok(Some(texture))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn put(&mut self, index: u32, result: &V) -> Result<(), PokerError> {
+        let write_txn = self.db.begin_write().map_err(redb_error)?;
         {
-            //Make sure table exists
-            let write_txn = db.begin_write()?;
-            {
-                let _table = write_txn.open_table(TABLE)?;
-            }
-            write_txn.commit()?;
+            let mut table = write_txn.open_table(TABLE).map_err(redb_error)?;
+            //let texture_bytes = rmp_serde::to_vec(texture).unwrap();
+            let texture_bytes: Vec<u8> = bincode::serialize(&result).unwrap();
+
+            table
+                .insert(index, texture_bytes.as_slice())
+                .map_err(redb_error)?;
         }
 
-        Ok(Self {
-            db,
-            cache_hits: 0,
-            cache_misses: 0,
-            phantom1: std::marker::PhantomData,
-            //phantom2: std::marker::PhantomData,
-        })
+        write_txn.commit().map_err(redb_error)?;
+        Ok(())
     }
+}
 
-    pub fn get_put(&mut self, board: &Board) -> Result<P::Result, ReDbError> {
-        let opt = self.get(board.get_precalc_index().unwrap())?;
-        if opt.is_some() {
-            self.cache_hits += 1;
-            return Ok(opt.unwrap());
+#[cfg(not(target_arch = "wasm32"))]
+impl ReDbKey for u32 {
+    fn create_table(db: &Database) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
+        {
+            let _table = write_txn.open_table(TABLE).map_err(redb_error)?;
         }
+        write_txn.commit().map_err(redb_error)
+    }
 
-        let result = P::produce_eval_result(board.as_slice_card());
-        self.cache_misses += 1;
-
-        self.put(board.get_precalc_index().unwrap(), &result)?;
+    fn read(db: &Database, index: &u32) -> Result<Option<Vec<u8>>, PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
 
-        Ok(result)
+        let data = table.get(index).map_err(redb_error)?;
+        Ok(data.map(|d| d.value().to_vec()))
     }
 
-    fn get(&mut self, index: u32) -> Result<Option<P::Result>, ReDbError> {
-        let read_txn: ReadTransaction = self.db.begin_read()?;
-        let table = read_txn.open_table(TABLE)?;
+    fn count(db: &Database) -> Result<u64, PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+        table.len().map_err(redb_error)
+    }
 
-        let data = table.get(index)?;
-        if let Some(data) = data {
-            //let texture: BoardTexture = rmp_serde::from_slice(data.value()).unwrap();
-            let texture: P::Result = bincode::deserialize(&data.value()).unwrap();
+    fn clear(db: &Database) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
+        write_txn.delete_table(TABLE).map_err(redb_error)?;
+        write_txn.open_table(TABLE).map_err(redb_error)?;
+        write_txn.commit().map_err(redb_error)
+    }
 
-            Ok(Some(texture))
-        } else {
-            Ok(None)
+    fn for_each(db: &Database, f: &mut ReDbEntryVisitor<u32>) -> Result<(), PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+        for entry in table.iter().map_err(redb_error)? {
+            let (index, bytes) = entry.map_err(redb_error)?;
+            if !f(index.value(), bytes.value())? {
+                break;
+            }
         }
+        Ok(())
     }
 
-    fn put(&mut self, index: u32, result: &P::Result) -> Result<(), ReDbError> {
-        let write_txn = self.db.begin_write()?;
+    fn write_batch(db: &Database, batch: &HashMap<u32, Vec<u8>>) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
         {
-            let mut table = write_txn.open_table(TABLE)?;
-            //let texture_bytes = rmp_serde::to_vec(texture).unwrap();
-            let texture_bytes: Vec<u8> = bincode::serialize(&result).unwrap();
+            let mut table = write_txn.open_table(TABLE).map_err(redb_error)?;
+            for (index, bytes) in batch.iter() {
+                table.insert(index, bytes.as_slice()).map_err(redb_error)?;
+            }
+        }
+        write_txn.commit().map_err(redb_error)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn redb_error<E: Into<redb::Error>>(e: E) -> PokerError {
+    PokerError::from_string(format!("redb error: {}", e.into()))
+}
+
+//P is producer type
+//The backend decides where the results live, redb, in memory or a bounded LRU
+pub struct EvalCache<P: ProduceEvalResult> {
+    backend: Box<dyn EvalCacheBackend<u32, P::Result>>,
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+}
 
-            table.insert(index, texture_bytes.as_slice())?;
+impl<P> EvalCache<P>
+where
+    P: ProduceEvalResult,
+    P::Result: Clone + 'static,
+{
+    pub fn new(backend: Box<dyn EvalCacheBackend<u32, P::Result>>) -> Self {
+        Self {
+            backend,
+            cache_hits: 0,
+            cache_misses: 0,
         }
+    }
 
-        write_txn.commit()?;
-        Ok(())
+    pub fn new_in_memory() -> Self {
+        Self::new(Box::new(InMemoryBackend::new()))
+    }
+
+    pub fn new_lru(capacity: usize) -> Self {
+        Self::new(Box::new(LruBackend::new(capacity)))
+    }
+
+    pub fn get_put(&mut self, board: &Board) -> Result<P::Result, PokerError> {
+        let (result, is_hit) = get_put_canonical_board::<P>(self.backend.as_mut(), board)?;
+        if is_hit {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+        Ok(result)
+    }
+}
+
+/*
+Looks the board up by its canonical index, on a miss the result is produced for the canonical board and stored.
+
+Shared by EvalCache and SharedEvalCache, also returns if it was a cache hit
+*/
+pub(crate) fn get_put_canonical_board<P: ProduceEvalResult>(
+    backend: &mut dyn EvalCacheBackend<u32, P::Result>,
+    board: &Board,
+) -> Result<(P::Result, bool), PokerError> {
+    let (index, to_canonical) = canonical_board_index(board.as_slice_card());
+    if let Some(result) = backend.get(&index)? {
+        return Ok((P::from_canonical_result(result, &to_canonical), true));
+    }
+
+    let result = P::produce_eval_result(&to_canonical.map_cards(board.as_slice_card()));
+    backend.put(index, &result)?;
+
+    Ok((P::from_canonical_result(result, &to_canonical), false))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<P> EvalCache<P>
+where
+    P: ProduceEvalResult,
+    P::Result: Clone + Serialize + DeserializeOwned + 'static,
+{
+    //each different producer gets its own db path
+    pub fn new_redb() -> Result<Self, PokerError> {
+        Ok(Self::new(Box::new(ReDbBackend::new(P::get_cache_name())?)))
     }
 }
 
@@ -151,6 +406,15 @@ impl ProduceEvalResult for ProduceFlopTexture {
     fn get_cache_name() -> EvalCacheEnum {
         EvalCacheEnum::FlopTexture
     }
+
+    fn from_canonical_result(mut result: BoardTexture, to_canonical: &SuitMapping) -> BoardTexture {
+        let to_board = to_canonical.inverse();
+        for suit in result.suits_with_max_count.iter_mut() {
+            *suit = to_board.map_suit(*suit);
+        }
+        result.suits_with_max_count.sort();
+        result
+    }
 }
 
 pub struct ProduceRank {}
@@ -168,10 +432,50 @@ mod tests {
 
     use log::info;
 
-    use crate::{board_eval_cache_redb::EvalCacheReDb, init_test_logger, Deck};
+    use crate::{board_eval_cache_redb::EvalCache, init_test_logger, Deck, Suit};
 
     use super::*;
 
+    #[test]
+    fn test_in_memory_and_lru_cache() {
+        let boards: Vec<Board> = ["Ah 7d 2c", "Ks Qs 3d", "Ah 7d 2c 9h"]
+            .iter()
+            .map(|s| {
+                let mut b = Board::try_from(*s).unwrap();
+                b.get_index();
+                b
+            })
+            .collect();
+
+        let mut in_memory: EvalCache<ProduceFlopTexture> = EvalCache::new_in_memory();
+        for board in boards.iter().chain(boards.iter()) {
+            in_memory.get_put(board).unwrap();
+        }
+        assert_eq!(in_memory.cache_misses, 3);
+        assert_eq!(in_memory.cache_hits, 3);
+
+        //Only room for 2, so the 3rd board pushes out the 1st
+        let mut lru: EvalCache<ProduceFlopTexture> = EvalCache::new_lru(2);
+        for board in boards.iter() {
+            lru.get_put(board).unwrap();
+        }
+        let texture = lru.get_put(&boards[0]).unwrap();
+        assert_eq!(lru.cache_misses, 4);
+        assert_eq!(lru.cache_hits, 0);
+        assert_eq!(texture.same_suited_max_count, 1);
+
+        lru.get_put(&boards[2]).unwrap();
+        assert_eq!(lru.cache_hits, 1);
+
+        //Same board with the suits renamed, it gets the cached texture with its own suits
+        let mut isomorphic = Board::try_from("Kh Qh 3c").unwrap();
+        isomorphic.get_index();
+        let texture = in_memory.get_put(&isomorphic).unwrap();
+        assert_eq!(in_memory.cache_hits, 4);
+        assert_eq!(texture, calc_board_texture(isomorphic.as_slice_card()));
+        assert_eq!(texture.suits_with_max_count, vec![Suit::Heart]);
+    }
+
     // cargo test cache_perf --lib --release -- --nocapture
 
     //a bit slow
@@ -191,7 +495,7 @@ mod tests {
 
         //let mut flop_texture_db = FlopTextureReDb::new(re_db_name).unwrap();
 
-        let mut flop_texture_db: EvalCacheReDb<ProduceFlopTexture> = EvalCacheReDb::new().unwrap();
+        let mut flop_texture_db: EvalCache<ProduceFlopTexture> = EvalCache::new_redb().unwrap();
         let now = Instant::now();
         let iter_count = 100_000;
         // Code block to measure.
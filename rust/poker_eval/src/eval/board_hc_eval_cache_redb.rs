@@ -1,12 +1,22 @@
+#[cfg(not(target_arch = "wasm32"))]
 use log::info;
-use redb::{Database, Error as ReDbError, ReadTransaction, ReadableTable, TableDefinition};
+#[cfg(not(target_arch = "wasm32"))]
+use redb::{Database, ReadTransaction, ReadableTable, TableDefinition};
+#[cfg(not(target_arch = "wasm32"))]
 use serde::{de::DeserializeOwned, Serialize};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::board_eval_cache_redb::{check_schema_version, get_data_path, redb_error};
 use crate::{
-    board_eval_cache_redb::{get_data_path, EvalCacheEnum},
-    monte_carlo_equity::calc_equity_vs_random,
-    partial_rank_cards, Board, Card, HoleCards, PartialRankContainer,
+    board_eval_cache_redb::EvalCacheEnum, calc_hand_strength, canonical_board_hole_cards,
+    monte_carlo_equity::calc_equity_vs_random, partial_rank_cards,
+    pre_calc::perfect_hash::BOOM_PERFECT_HASH, Board, BoolRange, Card, EvalCacheBackend,
+    HandStrength, HoleCards, InMemoryBackend, LruBackend, PartialRankContainer, PokerError,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{ReDbEntryVisitor, ReDbKey};
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
 
 //u32 is usually  enough
 //In the worst case we have 5 cards * 2 cards
@@ -15,53 +25,215 @@ use crate::{
 // need 22 bits for 52 choose 5
 // need 11 bits for 52 choose 2
 
+//Board index, hi card, lo card, num players
+pub type EvalWithHcKey = [u8; 7];
+
+pub fn eval_with_hc_key(
+    cards: &Board,
+    hole_cards: &HoleCards,
+    num_players: u8,
+) -> Result<EvalWithHcKey, PokerError> {
+    let index = cards.get_precalc_index()?;
+
+    let mut index_bytes: EvalWithHcKey = [0; 7];
+    // Packing the u32 into the first 4 bytes of the array
+    index_bytes[0] = (index >> 24) as u8; // Extracts the first byte
+    index_bytes[1] = (index >> 16) as u8; // Extracts the second byte
+    index_bytes[2] = (index >> 8) as u8; // Extracts the third byte
+    index_bytes[3] = index as u8; // Extracts the fourth byte
+    index_bytes[4] = hole_cards.get_hi_card().into();
+    index_bytes[5] = hole_cards.get_lo_card().into();
+    index_bytes[6] = num_players;
+
+    Ok(index_bytes)
+}
+
+/*
+The key of the canonical board and hole cards, see canonical_board_hole_cards.
+
+None of the cached results depend on the suits, so isomorphic deals share an entry
+*/
+pub fn canonical_eval_with_hc_key(
+    cards: &[Card],
+    hole_cards: &HoleCards,
+    num_players: u8,
+) -> Result<EvalWithHcKey, PokerError> {
+    let (board, hole_cards) = canonical_board_hole_cards(cards, hole_cards);
+    eval_with_hc_key(&board, &hole_cards, num_players)
+}
+
+//The board, hole cards and num players back from the key
+pub fn decode_eval_with_hc_key(key: &EvalWithHcKey) -> Result<(Board, HoleCards, u8), PokerError> {
+    let index = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+    let board = Board::from_index(index)?;
+    let hole_cards = HoleCards::new(key[4].try_into()?, key[5].try_into()?)?;
+
+    Ok((board, hole_cards, key[6]))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 const TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("eval_cache");
 
 pub trait ProduceEvalWithHcResult {
     type Result;
 
-    fn produce_eval_result(cards: &[Card], hole_cards: &HoleCards, num_players: u8)
-        -> Self::Result;
+    fn produce_eval_result(
+        cards: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<Self::Result, PokerError>;
 
     fn get_cache_name() -> EvalCacheEnum;
 }
 
-//P is producer type
-//R is result
-//K is the key type
-pub struct EvalCacheWithHcReDb<P> {
+//One file per producer
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ReDbWithHcBackend {
     db: Database,
-    pub cache_hits: u32,
-    pub cache_misses: u32,
+}
 
-    phantom1: std::marker::PhantomData<P>,
+#[cfg(not(target_arch = "wasm32"))]
+impl ReDbWithHcBackend {
+    pub fn new(cache_name: EvalCacheEnum) -> Result<Self, PokerError> {
+        let db_name = get_data_path(cache_name)?;
+        info!("Opening db {:?}", db_name);
+        let db = Database::create(db_name).map_err(redb_error)?;
+        //Make sure table exists
+        EvalWithHcKey::create_table(&db)?;
+        check_schema_version::<EvalWithHcKey>(&db, cache_name)?;
+
+        Ok(Self { db })
+    }
 }
 
-impl<P> EvalCacheWithHcReDb<P>
+#[cfg(not(target_arch = "wasm32"))]
+impl<V> EvalCacheBackend<EvalWithHcKey, V> for ReDbWithHcBackend
 where
-    P: ProduceEvalWithHcResult,
-    P::Result: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
 {
-    //each different struct should get its own db path
-    pub fn new() -> Result<Self, ReDbError> {
-        let db_name = get_data_path(P::get_cache_name());
-        info!("Opening db {:?}", db_name);
-        let db = Database::create(db_name)?;
+    fn get(&mut self, index: &EvalWithHcKey) -> Result<Option<V>, PokerError> {
+        let read_txn: ReadTransaction = self.db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+
+        let data = table.get(index.as_slice()).map_err(redb_error)?;
+        if let Some(data) = data {
+            //let texture: BoardTexture = rmp_serde::from_slice(data.value()).unwrap();
+            let texture: V = bincode::deserialize(&data.value()).unwrap();
+
+            Ok(Some(texture))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn put(&mut self, index: EvalWithHcKey, result: &V) -> Result<(), PokerError> {
+        let write_txn = self.db.begin_write().map_err(redb_error)?;
         {
-            //Make sure table exists
-            let write_txn = db.begin_write()?;
-            {
-                let _table = write_txn.open_table(TABLE)?;
+            let mut table = write_txn.open_table(TABLE).map_err(redb_error)?;
+            //let texture_bytes = rmp_serde::to_vec(texture).unwrap();
+            let texture_bytes: Vec<u8> = bincode::serialize(&result).unwrap();
+
+            table
+                .insert(index.as_slice(), texture_bytes.as_slice())
+                .map_err(redb_error)?;
+        }
+
+        write_txn.commit().map_err(redb_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ReDbKey for EvalWithHcKey {
+    fn create_table(db: &Database) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
+        {
+            let _table = write_txn.open_table(TABLE).map_err(redb_error)?;
+        }
+        write_txn.commit().map_err(redb_error)
+    }
+
+    fn read(db: &Database, index: &EvalWithHcKey) -> Result<Option<Vec<u8>>, PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+
+        let data = table.get(index.as_slice()).map_err(redb_error)?;
+        Ok(data.map(|d| d.value().to_vec()))
+    }
+
+    fn count(db: &Database) -> Result<u64, PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+        table.len().map_err(redb_error)
+    }
+
+    fn clear(db: &Database) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
+        write_txn.delete_table(TABLE).map_err(redb_error)?;
+        write_txn.open_table(TABLE).map_err(redb_error)?;
+        write_txn.commit().map_err(redb_error)
+    }
+
+    fn for_each(db: &Database, f: &mut ReDbEntryVisitor<EvalWithHcKey>) -> Result<(), PokerError> {
+        let read_txn: ReadTransaction = db.begin_read().map_err(redb_error)?;
+        let table = read_txn.open_table(TABLE).map_err(redb_error)?;
+        for entry in table.iter().map_err(redb_error)? {
+            let (index, bytes) = entry.map_err(redb_error)?;
+            let key: EvalWithHcKey = index
+                .value()
+                .try_into()
+                .map_err(|_| PokerError::from_string(format!("Invalid key {:?}", index.value())))?;
+            if !f(key, bytes.value())? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_batch(
+        db: &Database,
+        batch: &HashMap<EvalWithHcKey, Vec<u8>>,
+    ) -> Result<(), PokerError> {
+        let write_txn = db.begin_write().map_err(redb_error)?;
+        {
+            let mut table = write_txn.open_table(TABLE).map_err(redb_error)?;
+            for (index, bytes) in batch.iter() {
+                table
+                    .insert(index.as_slice(), bytes.as_slice())
+                    .map_err(redb_error)?;
             }
-            write_txn.commit()?;
         }
+        write_txn.commit().map_err(redb_error)
+    }
+}
 
-        Ok(Self {
-            db,
+//P is producer type
+//The backend decides where the results live, redb, in memory or a bounded LRU
+pub struct EvalCacheWithHc<P: ProduceEvalWithHcResult> {
+    backend: Box<dyn EvalCacheBackend<EvalWithHcKey, P::Result>>,
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+}
+
+impl<P> EvalCacheWithHc<P>
+where
+    P: ProduceEvalWithHcResult,
+    P::Result: Clone + 'static,
+{
+    pub fn new(backend: Box<dyn EvalCacheBackend<EvalWithHcKey, P::Result>>) -> Self {
+        Self {
+            backend,
             cache_hits: 0,
             cache_misses: 0,
-            phantom1: std::marker::PhantomData,
-        })
+        }
+    }
+
+    pub fn new_in_memory() -> Self {
+        Self::new(Box::new(InMemoryBackend::new()))
+    }
+
+    pub fn new_lru(capacity: usize) -> Self {
+        Self::new(Box::new(LruBackend::new(capacity)))
     }
 
     pub fn get_put(
@@ -69,60 +241,52 @@ where
         cards: &Board,
         hole_cards: &HoleCards,
         num_players: u8,
-    ) -> Result<P::Result, ReDbError> {
-        let index = cards.get_precalc_index().unwrap();
-
-        let mut index_bytes: [u8; 7] = [0; 7];
-        // Packing the u32 into the first 4 bytes of the array
-        index_bytes[0] = (index >> 24) as u8; // Extracts the first byte
-        index_bytes[1] = (index >> 16) as u8; // Extracts the second byte
-        index_bytes[2] = (index >> 8) as u8; // Extracts the third byte
-        index_bytes[3] = index as u8; // Extracts the fourth byte
-        index_bytes[4] = hole_cards.get_hi_card().into();
-        index_bytes[5] = hole_cards.get_lo_card().into();
-        index_bytes[6] = num_players;
-
-        let opt = self.get(&index_bytes)?;
-        if opt.is_some() {
+    ) -> Result<P::Result, PokerError> {
+        let (result, is_hit) =
+            get_put_canonical_with_hc::<P>(self.backend.as_mut(), cards, hole_cards, num_players)?;
+        if is_hit {
             self.cache_hits += 1;
-            return Ok(opt.unwrap());
+        } else {
+            self.cache_misses += 1;
         }
-
-        let result = P::produce_eval_result(cards.as_slice_card(), hole_cards, num_players);
-        self.cache_misses += 1;
-
-        self.put(&index_bytes, &result)?;
-
         Ok(result)
     }
+}
 
-    fn get(&mut self, index: &[u8]) -> Result<Option<P::Result>, ReDbError> {
-        let read_txn: ReadTransaction = self.db.begin_read()?;
-        let table = read_txn.open_table(TABLE)?;
-
-        let data = table.get(index)?;
-        if let Some(data) = data {
-            //let texture: BoardTexture = rmp_serde::from_slice(data.value()).unwrap();
-            let texture: P::Result = bincode::deserialize(&data.value()).unwrap();
-
-            Ok(Some(texture))
-        } else {
-            Ok(None)
-        }
+/*
+Looks the board and hole cards up by their canonical key, on a miss the result is produced and stored.
+
+Shared by EvalCacheWithHc and SharedEvalCacheWithHc, also returns if it was a cache hit
+*/
+pub(crate) fn get_put_canonical_with_hc<P: ProduceEvalWithHcResult>(
+    backend: &mut dyn EvalCacheBackend<EvalWithHcKey, P::Result>,
+    cards: &Board,
+    hole_cards: &HoleCards,
+    num_players: u8,
+) -> Result<(P::Result, bool), PokerError> {
+    let index_bytes = canonical_eval_with_hc_key(cards.as_slice_card(), hole_cards, num_players)?;
+
+    if let Some(result) = backend.get(&index_bytes)? {
+        return Ok((result, true));
     }
 
-    fn put(&mut self, index: &[u8], result: &P::Result) -> Result<(), ReDbError> {
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut table = write_txn.open_table(TABLE)?;
-            //let texture_bytes = rmp_serde::to_vec(texture).unwrap();
-            let texture_bytes: Vec<u8> = bincode::serialize(&result).unwrap();
+    let result = P::produce_eval_result(cards.as_slice_card(), hole_cards, num_players)?;
+    backend.put(index_bytes, &result)?;
 
-            table.insert(index, texture_bytes.as_slice())?;
-        }
+    Ok((result, false))
+}
 
-        write_txn.commit()?;
-        Ok(())
+#[cfg(not(target_arch = "wasm32"))]
+impl<P> EvalCacheWithHc<P>
+where
+    P: ProduceEvalWithHcResult,
+    P::Result: Clone + Serialize + DeserializeOwned + 'static,
+{
+    //each different producer gets its own db path
+    pub fn new_redb() -> Result<Self, PokerError> {
+        Ok(Self::new(Box::new(ReDbWithHcBackend::new(
+            P::get_cache_name(),
+        )?)))
     }
 }
 
@@ -136,10 +300,10 @@ impl ProduceEvalWithHcResult for ProducePartialRankCards {
         board: &[Card],
         hole_cards: &HoleCards,
         num_players: u8,
-    ) -> PartialRankContainer {
+    ) -> Result<PartialRankContainer, PokerError> {
         //Num players has no effect, to make sure we aren't bloating the cache we enforce it is 0
         assert_eq!(num_players, 0);
-        partial_rank_cards(&hole_cards, board)
+        Ok(partial_rank_cards(&hole_cards, board))
     }
 
     fn get_cache_name() -> EvalCacheEnum {
@@ -157,12 +321,13 @@ impl ProduceEvalWithHcResult for ProduceMonteCarloEval {
     type Result = f64;
 
     //num_players -- This is indcluing the hero
-    fn produce_eval_result(cards: &[Card], hole_cards: &HoleCards, num_players: u8) -> f64 {
+    fn produce_eval_result(
+        cards: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<f64, PokerError> {
         let board: Board = Board::new_from_cards(cards);
-        let eq = calc_equity_vs_random(&board, &hole_cards, num_players as usize, NUM_SIMULATIONS)
-            .unwrap();
-
-        eq
+        calc_equity_vs_random(&board, &hole_cards, num_players as usize, NUM_SIMULATIONS)
     }
 
     fn get_cache_name() -> EvalCacheEnum {
@@ -170,6 +335,40 @@ impl ProduceEvalWithHcResult for ProduceMonteCarloEval {
     }
 }
 
+//Link with the hand strength / potential metrics
+
+pub struct ProduceHandStrength {}
+
+impl ProduceEvalWithHcResult for ProduceHandStrength {
+    type Result = HandStrength;
+
+    //Heads up vs any hand, with the potentials over all the cards to come.
+    //The key has no range, so only num_players 2 is cached
+    fn produce_eval_result(
+        cards: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<HandStrength, PokerError> {
+        if num_players != 2 {
+            return Err(PokerError::from_string(format!(
+                "Hand strength is only cached heads up, not for {} players",
+                num_players
+            )));
+        }
+        calc_hand_strength(
+            hole_cards,
+            cards,
+            &BoolRange::new().complement(),
+            5 - cards.len(),
+            &BOOM_PERFECT_HASH,
+        )
+    }
+
+    fn get_cache_name() -> EvalCacheEnum {
+        EvalCacheEnum::HandStrength
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Instant;
@@ -177,10 +376,27 @@ mod tests {
     use log::info;
 
     use crate::{
-        board_hc_eval_cache_redb::{EvalCacheWithHcReDb, ProducePartialRankCards},
+        board_hc_eval_cache_redb::{EvalCacheWithHc, ProducePartialRankCards},
         init_test_logger, Board, Card, Deck, HoleCards,
     };
 
+    use super::{ProduceEvalWithHcResult, ProduceHandStrength};
+
+    #[test]
+    fn test_hand_strength_only_heads_up() {
+        let board = Board::try_from("Ah 7d 2c").unwrap();
+        let hole_cards: HoleCards = "Kh Qh".parse().unwrap();
+
+        let mut cache: EvalCacheWithHc<ProduceHandStrength> = EvalCacheWithHc::new_in_memory();
+        assert!(cache.get_put(&board, &hole_cards, 3).is_err());
+        assert_eq!(cache.cache_misses, 0);
+
+        let strength =
+            ProduceHandStrength::produce_eval_result(board.as_slice_card(), &hole_cards, 2)
+                .unwrap();
+        assert_eq!(strength, cache.get_put(&board, &hole_cards, 2).unwrap());
+    }
+
     //// cargo test cache_perf --lib --release -- --nocapture
 
     //a bit slow
@@ -199,8 +415,8 @@ mod tests {
         //let mut flop_texture_db = FlopTextureJamDb::new(db_name).unwrap();
 
         //let mut flop_texture_db = FlopTextureReDb::new(re_db_name).unwrap();
-        let mut partial_rank_db: EvalCacheWithHcReDb<ProducePartialRankCards> =
-            EvalCacheWithHcReDb::new().unwrap();
+        let mut partial_rank_db: EvalCacheWithHc<ProducePartialRankCards> =
+            EvalCacheWithHc::new_redb().unwrap();
         let now = Instant::now();
         let iter_count = 500_000;
         // Code block to measure.
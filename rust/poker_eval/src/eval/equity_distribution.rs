@@ -0,0 +1,269 @@
+use itertools::Itertools;
+use rand::rngs::StdRng;
+
+use crate::{
+    monte_carlo_equity::MonteCarloEquity, pre_calc::NUMBER_OF_HOLE_CARDS, Board, DeckVariant,
+    FastEvaluator, HoleCards, PokerError, WeightedRange, ALL_HOLE_CARDS,
+};
+
+/*
+A single equity hides how the range is made up, 50% could be every hand at 50% or
+half the range at 100% and the other half at 0% (polarized).
+
+So we keep the equity of each combo of each range
+*/
+
+//A combo with at least this much equity counts as a nut hand for the nut advantage
+pub const NUT_EQUITY: f64 = 0.8;
+
+#[derive(Debug, Clone)]
+pub struct ComboEquity {
+    pub hole_cards: HoleCards,
+    pub equity: f64,
+    //How many times the combo was dealt, this follows its weight in the range
+    pub num_samples: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct EquityDistribution {
+    //Sorted lowest equity first, so this is also the equity curve
+    pub combos: Vec<ComboEquity>,
+}
+
+impl EquityDistribution {
+    fn total_samples(&self) -> f64 {
+        self.combos.iter().map(|c| c.num_samples).sum::<usize>() as f64
+    }
+
+    //Same as the player's equity, 0 if no combo was dealt
+    pub fn mean_equity(&self) -> f64 {
+        let total_samples = self.total_samples();
+        if total_samples == 0.0 {
+            return 0.0;
+        }
+        self.combos
+            .iter()
+            .map(|c| c.equity * c.num_samples as f64)
+            .sum::<f64>()
+            / total_samples
+    }
+
+    /*
+    How much of the range has equity in each of num_buckets equal width buckets from 0 to 1,
+    all 0 if no combo was dealt
+    */
+    pub fn histogram(&self, num_buckets: usize) -> Result<Vec<f64>, PokerError> {
+        if num_buckets == 0 {
+            return Err(PokerError::from_str("Histogram needs at least 1 bucket"));
+        }
+        let mut buckets = vec![0.0; num_buckets];
+        let total_samples = self.total_samples();
+        if total_samples == 0.0 {
+            return Ok(buckets);
+        }
+        for c in self.combos.iter() {
+            let bucket = ((c.equity * num_buckets as f64) as usize).min(num_buckets - 1);
+            buckets[bucket] += c.num_samples as f64 / total_samples;
+        }
+        Ok(buckets)
+    }
+
+    /*
+    The equity at num_points evenly spaced percentiles of the range, weakest first,
+    ex: with 101 points [50] is the median equity
+    */
+    pub fn equity_curve(&self, num_points: usize) -> Vec<f64> {
+        let total_samples = self.total_samples();
+        let mut curve = Vec::with_capacity(num_points);
+        let mut combos = self.combos.iter().peekable();
+        let mut cumulative = 0.0;
+
+        for point in 0..num_points {
+            let percentile = point as f64 / (num_points - 1).max(1) as f64;
+            //Advance to the combo that covers this percentile
+            while let Some(c) = combos.peek() {
+                let next = cumulative + c.num_samples as f64 / total_samples;
+                if next >= percentile || combos.len() == 1 {
+                    break;
+                }
+                cumulative = next;
+                combos.next();
+            }
+            curve.push(combos.peek().map_or(0.0, |c| c.equity));
+        }
+        curve
+    }
+
+    //How much of the range has at least this equity, 0 if no combo was dealt
+    pub fn fraction_at_least(&self, equity: f64) -> f64 {
+        let total_samples = self.total_samples();
+        if total_samples == 0.0 {
+            return 0.0;
+        }
+        self.combos
+            .iter()
+            .filter(|c| c.equity >= equity)
+            .map(|c| c.num_samples as f64)
+            .sum::<f64>()
+            / total_samples
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RangeEquityDistributions {
+    //In the same order as the ranges
+    pub players: Vec<EquityDistribution>,
+    pub num_simulations: usize,
+}
+
+impl RangeEquityDistributions {
+    //Player's equity minus the best other player's equity, > 0 means the range is ahead overall
+    pub fn range_advantage(&self, player_index: usize) -> f64 {
+        self.players[player_index].mean_equity()
+            - self
+                .others(player_index)
+                .map(|d| d.mean_equity())
+                .fold(0.0, f64::max)
+    }
+
+    //How much more of the player's range has NUT_EQUITY or more than any other player's range
+    pub fn nut_advantage(&self, player_index: usize) -> f64 {
+        self.players[player_index].fraction_at_least(NUT_EQUITY)
+            - self
+                .others(player_index)
+                .map(|d| d.fraction_at_least(NUT_EQUITY))
+                .fold(0.0, f64::max)
+    }
+
+    fn others(&self, player_index: usize) -> impl Iterator<Item = &EquityDistribution> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| *i != player_index)
+            .map(|(_, d)| d)
+    }
+}
+
+/*
+Monte Carlo of ranges vs each other on the board like calc_equity_weighted, but keeping
+each combo's share of the pot, so combos are compared against the other ranges (not other combos)
+*/
+pub fn calc_equity_distributions(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<RangeEquityDistributions, PokerError> {
+    if ranges.len() < 2 {
+        return Err(PokerError::from_str("Need at least 2 ranges"));
+    }
+
+    //[player][range index]
+    let mut equity_sums = vec![vec![0.0; NUMBER_OF_HOLE_CARDS]; ranges.len()];
+    let mut num_samples = vec![vec![0usize; NUMBER_OF_HOLE_CARDS]; ranges.len()];
+
+    let evaluator = FastEvaluator::default();
+    let mut monte_carlo =
+        MonteCarloEquity::new(board, ranges, rng, DeckVariant::Standard, &evaluator)?;
+    monte_carlo.run_with(num_simulations, |player_hole_cards, shares| {
+        for (player_index, hole_cards) in player_hole_cards.iter().enumerate() {
+            let range_index = hole_cards.to_range_index();
+            equity_sums[player_index][range_index] += shares[player_index];
+            num_samples[player_index][range_index] += 1;
+        }
+    })?;
+
+    let players = equity_sums
+        .iter()
+        .zip(num_samples.iter())
+        .map(|(sums, counts)| {
+            let combos = ALL_HOLE_CARDS
+                .iter()
+                .filter(|hc| counts[hc.to_range_index()] > 0)
+                .map(|hc| ComboEquity {
+                    hole_cards: *hc,
+                    equity: sums[hc.to_range_index()] / counts[hc.to_range_index()] as f64,
+                    num_samples: counts[hc.to_range_index()],
+                })
+                .sorted_by(|a, b| a.equity.total_cmp(&b.equity))
+                .collect_vec();
+            EquityDistribution { combos }
+        })
+        .collect_vec();
+
+    Ok(RangeEquityDistributions {
+        players,
+        num_simulations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::DEFAULT_SEED;
+
+    use super::*;
+
+    #[test]
+    fn test_equity_distributions() {
+        //Polarized, the aces are way ahead and the 32 is way behind
+        let board: Board = "Jc 8d 4s 2h".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["AA,32o".parse().unwrap(), "QQ".parse().unwrap()];
+        let result =
+            calc_equity_distributions(&board, &ranges, 20_000, StdRng::seed_from_u64(DEFAULT_SEED))
+                .unwrap();
+
+        let hero = &result.players[0];
+        let villian = &result.players[1];
+        //6 AA + 9 32o (2h is on the board)
+        assert_eq!(hero.combos.len(), 6 + 9);
+        assert_eq!(villian.combos.len(), 6);
+        assert!((hero.mean_equity() + villian.mean_equity() - 1.0).abs() < 1e-9);
+
+        let histogram = hero.histogram(10).unwrap();
+        assert!((histogram.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        //Nothing in the middle
+        assert!(histogram[2..8].iter().all(|b| *b == 0.0), "{:?}", histogram);
+        assert!((histogram[9] - 6.0 / 15.0).abs() < 0.05);
+
+        assert!(hero.histogram(0).is_err());
+
+        let curve = hero.equity_curve(101);
+        assert!(curve.windows(2).all(|w| w[0] <= w[1]));
+        assert!(curve[0] < 0.2 && curve[100] > 0.9);
+
+        //Villian's QQ is always in between
+        assert!(villian.fraction_at_least(NUT_EQUITY) == 0.0);
+        assert!(result.nut_advantage(0) > 0.3);
+        assert!(result.nut_advantage(1) < 0.0);
+        assert!(
+            (result.range_advantage(0) + result.range_advantage(1)).abs() < 1e-9,
+            "Heads up, one player's advantage is the other's disadvantage"
+        );
+
+        assert!(calc_equity_distributions(
+            &board,
+            &ranges[..1],
+            100,
+            StdRng::seed_from_u64(DEFAULT_SEED)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_empty_equity_distribution() {
+        let empty = EquityDistribution { combos: vec![] };
+        assert_eq!(empty.mean_equity(), 0.0);
+        assert_eq!(empty.fraction_at_least(NUT_EQUITY), 0.0);
+        assert_eq!(empty.histogram(4).unwrap(), vec![0.0; 4]);
+        assert_eq!(empty.equity_curve(3), vec![0.0; 3]);
+
+        let distributions = RangeEquityDistributions {
+            players: vec![empty.clone(), empty],
+            num_simulations: 0,
+        };
+        assert_eq!(distributions.range_advantage(0), 0.0);
+        assert_eq!(distributions.nut_advantage(1), 0.0);
+    }
+}
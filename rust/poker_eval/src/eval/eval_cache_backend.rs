@@ -0,0 +1,262 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use crate::PokerError;
+
+/*
+Where EvalCache and EvalCacheWithHc keep their results.
+
+K is the key, the board index or the board index + hole cards + num players
+V is the cached result
+
+The redb backends are in board_eval_cache_redb / board_hc_eval_cache_redb, those need DATA_DIR
+and a file system.  The in memory ones work anywhere, including wasm.
+*/
+pub trait EvalCacheBackend<K, V> {
+    fn get(&mut self, key: &K) -> Result<Option<V>, PokerError>;
+
+    fn put(&mut self, key: K, value: &V) -> Result<(), PokerError>;
+}
+
+//Never evicts, fine for tests and anything that only sees a limited number of boards
+pub struct InMemoryBackend<K, V> {
+    entries: HashMap<K, V>,
+}
+
+impl<K, V> Default for InMemoryBackend<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<K, V> InMemoryBackend<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K, V> EvalCacheBackend<K, V> for InMemoryBackend<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    fn get(&mut self, key: &K) -> Result<Option<V>, PokerError> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn put(&mut self, key: K, value: &V) -> Result<(), PokerError> {
+        self.entries.insert(key, value.clone());
+        Ok(())
+    }
+}
+
+/*
+Keeps at most capacity entries, when full the least recently used (get or put) is dropped.
+
+Each access gets the next tick, recency orders the keys by their last tick
+*/
+pub struct LruBackend<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (V, u64)>,
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K, V> LruBackend<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<K, V> EvalCacheBackend<K, V> for LruBackend<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn get(&mut self, key: &K) -> Result<Option<V>, PokerError> {
+        let Some((value, last_tick)) = self.entries.get_mut(key) else {
+            return Ok(None);
+        };
+
+        self.tick += 1;
+        let prev_key = self.recency.remove(last_tick);
+        self.recency.insert(self.tick, prev_key.unwrap());
+        *last_tick = self.tick;
+
+        Ok(Some(value.clone()))
+    }
+
+    fn put(&mut self, key: K, value: &V) -> Result<(), PokerError> {
+        self.tick += 1;
+
+        if let Some((_, last_tick)) = self.entries.insert(key.clone(), (value.clone(), self.tick)) {
+            self.recency.remove(&last_tick);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let (_, oldest_key) = self.recency.pop_first().unwrap();
+            self.entries.remove(&oldest_key);
+        }
+
+        Ok(())
+    }
+}
+
+/*
+Where SharedEvalCache and SharedEvalCacheWithHc keep their results, used from any number of threads.
+
+BatchedReDb is the redb one.  Any EvalCacheBackend can be shared by putting it behind a Mutex.
+*/
+pub trait SharedEvalCacheBackend<K, V>: Send + Sync {
+    fn get(&self, key: &K) -> Result<Option<V>, PokerError>;
+
+    fn put(&self, key: K, value: &V) -> Result<(), PokerError>;
+
+    //Writes anything buffered
+    fn flush(&self) -> Result<(), PokerError>;
+}
+
+//So the lookups written for EvalCacheBackend also work on a shared backend
+impl<K, V, S> EvalCacheBackend<K, V> for &S
+where
+    S: SharedEvalCacheBackend<K, V> + ?Sized,
+{
+    fn get(&mut self, key: &K) -> Result<Option<V>, PokerError> {
+        (**self).get(key)
+    }
+
+    fn put(&mut self, key: K, value: &V) -> Result<(), PokerError> {
+        (**self).put(key, value)
+    }
+}
+
+impl<K, V, B> SharedEvalCacheBackend<K, V> for Mutex<B>
+where
+    B: EvalCacheBackend<K, V> + Send,
+{
+    fn get(&self, key: &K) -> Result<Option<V>, PokerError> {
+        self.lock()
+            .map_err(|_| PokerError::from_str("Eval cache lock poisoned"))?
+            .get(key)
+    }
+
+    fn put(&self, key: K, value: &V) -> Result<(), PokerError> {
+        self.lock()
+            .map_err(|_| PokerError::from_str("Eval cache lock poisoned"))?
+            .put(key, value)
+    }
+
+    fn flush(&self) -> Result<(), PokerError> {
+        Ok(())
+    }
+}
+
+//So the caller can keep its own handle on the backend
+impl<K, V, B> SharedEvalCacheBackend<K, V> for Arc<B>
+where
+    B: SharedEvalCacheBackend<K, V>,
+{
+    fn get(&self, key: &K) -> Result<Option<V>, PokerError> {
+        B::get(self, key)
+    }
+
+    fn put(&self, key: K, value: &V) -> Result<(), PokerError> {
+        B::put(self, key, value)
+    }
+
+    fn flush(&self) -> Result<(), PokerError> {
+        B::flush(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_backend() {
+        let mut lru: LruBackend<u32, String> = LruBackend::new(2);
+
+        lru.put(1, &"one".to_string()).unwrap();
+        lru.put(2, &"two".to_string()).unwrap();
+
+        //1 is now more recent than 2
+        assert_eq!(lru.get(&1).unwrap(), Some("one".to_string()));
+
+        lru.put(3, &"three".to_string()).unwrap();
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get(&2).unwrap(), None);
+        assert_eq!(lru.get(&1).unwrap(), Some("one".to_string()));
+        assert_eq!(lru.get(&3).unwrap(), Some("three".to_string()));
+
+        //Replacing a value doesn't grow the cache
+        lru.put(3, &"trois".to_string()).unwrap();
+        assert_eq!(lru.len(), 2);
+        lru.put(4, &"four".to_string()).unwrap();
+        assert_eq!(lru.get(&1).unwrap(), None);
+        assert_eq!(lru.get(&3).unwrap(), Some("trois".to_string()));
+        assert_eq!(lru.get(&4).unwrap(), Some("four".to_string()));
+
+        let mut in_memory: InMemoryBackend<u32, String> = InMemoryBackend::new();
+        assert!(in_memory.is_empty());
+        for i in 0..10 {
+            in_memory.put(i, &i.to_string()).unwrap();
+        }
+        assert_eq!(in_memory.len(), 10);
+        assert_eq!(in_memory.get(&0).unwrap(), Some("0".to_string()));
+    }
+
+    #[test]
+    fn test_shared_lru_backend() {
+        let shared = Arc::new(Mutex::new(LruBackend::<u32, u8>::new(2)));
+
+        std::thread::scope(|s| {
+            for t in 0..4u8 {
+                let shared = shared.clone();
+                s.spawn(move || {
+                    for i in 0..10 {
+                        SharedEvalCacheBackend::put(&shared, i, &t).unwrap();
+                    }
+                });
+            }
+        });
+
+        //Every thread ends with 9
+        assert!(shared.lock().unwrap().len() <= 2);
+        assert!(SharedEvalCacheBackend::get(&shared, &9).unwrap().is_some());
+        assert!(SharedEvalCacheBackend::get(&shared, &0).unwrap().is_none());
+        shared.flush().unwrap();
+    }
+}
@@ -0,0 +1,799 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+};
+
+use itertools::Itertools;
+use log::info;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+
+use crate::{
+    board_eval_cache_redb::{
+        read_schema_version, write_schema_version, EvalCacheEnum, ProduceEvalResult,
+        ProduceFlopTexture, UNSTAMPED_SCHEMA_VERSION,
+    },
+    board_hc_eval_cache_redb::{
+        canonical_eval_with_hc_key, decode_eval_with_hc_key, EvalWithHcKey,
+        ProduceEvalWithHcResult, ProduceHandStrength, ProduceMonteCarloEval,
+        ProducePartialRankCards,
+    },
+    canonical_board_index,
+    monte_carlo_equity::get_equivalent_hole_board,
+    BatchedReDb, Board, Card, HoleCards, PokerError, ReDbKey, ALL_CARDS, ALL_HOLE_CARDS,
+};
+
+/*
+Warming, inspecting and invalidating the redb evaluation caches, see the cache_maint binary.
+
+A producer's keys are what the agents look it up with.  The caches key by the canonical board
+(and hole cards), see canonical_board_index and canonical_eval_with_hc_key, so there is one key
+per isomorphic deal.  All river boards with hole cards are still over 1e8 keys.
+*/
+pub trait MaintainedProducer {
+    type Key: ReDbKey + Sync;
+
+    fn cache_name() -> EvalCacheEnum;
+
+    //The only num players the producer accepts, None if any
+    fn fixed_num_players() -> Option<u8>;
+
+    //Every key for boards of num_board_cards cards, stops when f returns false
+    fn for_each_key(
+        num_board_cards: usize,
+        num_players: u8,
+        f: &mut dyn FnMut(Self::Key) -> bool,
+    ) -> Result<(), PokerError>;
+
+    //Same key an agent would use for this board and hole cards
+    fn lookup_key(
+        board: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<Self::Key, PokerError>;
+
+    fn num_board_cards(key: &Self::Key) -> Result<usize, PokerError>;
+
+    //A fresh result, serialized like the stored ones
+    fn produce_bytes(key: &Self::Key) -> Result<Vec<u8>, PokerError>;
+
+    //Exact unless the producer is random
+    fn results_match(stored: &[u8], fresh: &[u8]) -> Result<bool, PokerError> {
+        Ok(stored == fresh)
+    }
+
+    fn key_to_string(key: &Self::Key) -> String;
+}
+
+fn serialize<R: Serialize>(result: &R) -> Result<Vec<u8>, PokerError> {
+    bincode::serialize(result)
+        .map_err(|e| PokerError::from_string(format!("Unable to serialize: {}", e)))
+}
+
+fn deserialize<R: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<R, PokerError> {
+    bincode::deserialize(bytes)
+        .map_err(|e| PokerError::from_string(format!("Unable to deserialize: {}", e)))
+}
+
+fn board_of_index(index: u32) -> Result<usize, PokerError> {
+    Ok(Board::from_index(index)?.as_slice_card().len())
+}
+
+/*
+One board per suit isomorphism class with its index calculated, stops when f returns false.
+
+A board is the class's canonical one when no suit renaming gives a larger index, see canonical_board_index
+*/
+fn for_each_canonical_board(num_board_cards: usize, f: &mut dyn FnMut(Board) -> bool) {
+    for cards in ALL_CARDS.iter().copied().combinations(num_board_cards) {
+        let mut board = Board::new_from_cards(&cards);
+        if canonical_board_index(&cards).0 != board.get_index() {
+            continue;
+        }
+        if !f(board) {
+            return;
+        }
+    }
+}
+
+/*
+The canonical keys of every hole cards not on the board.
+
+Suit renamings that keep the board give the same key, those are only passed to f once.
+*/
+fn for_each_hc_key(
+    board: &Board,
+    num_players: u8,
+    f: &mut dyn FnMut(EvalWithHcKey) -> bool,
+) -> Result<bool, PokerError> {
+    let mut seen: HashSet<EvalWithHcKey> = HashSet::new();
+    for hole_cards in ALL_HOLE_CARDS.iter() {
+        if board.intersects_holecards(hole_cards) {
+            continue;
+        }
+        let key = canonical_eval_with_hc_key(board.as_slice_card(), hole_cards, num_players)?;
+        if !seen.insert(key) {
+            continue;
+        }
+        if !f(key) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+//For each canonical board, the keys of all its hole cards
+fn for_each_canonical_hc_key(
+    num_board_cards: usize,
+    num_players: u8,
+    f: &mut dyn FnMut(EvalWithHcKey) -> bool,
+) -> Result<(), PokerError> {
+    let mut result = Ok(());
+    for_each_canonical_board(num_board_cards, &mut |board| {
+        let keep_going = for_each_hc_key(&board, num_players, f);
+        keep_going.unwrap_or_else(|e| {
+            result = Err(e);
+            false
+        })
+    });
+    result
+}
+
+impl MaintainedProducer for ProduceFlopTexture {
+    type Key = u32;
+
+    fn cache_name() -> EvalCacheEnum {
+        <ProduceFlopTexture as ProduceEvalResult>::get_cache_name()
+    }
+
+    fn fixed_num_players() -> Option<u8> {
+        Some(0)
+    }
+
+    fn for_each_key(
+        num_board_cards: usize,
+        _num_players: u8,
+        f: &mut dyn FnMut(u32) -> bool,
+    ) -> Result<(), PokerError> {
+        for_each_canonical_board(num_board_cards, &mut |mut board| f(board.get_index()));
+        Ok(())
+    }
+
+    fn lookup_key(
+        board: &[Card],
+        _hole_cards: &HoleCards,
+        _num_players: u8,
+    ) -> Result<u32, PokerError> {
+        Ok(canonical_board_index(board).0)
+    }
+
+    fn num_board_cards(key: &u32) -> Result<usize, PokerError> {
+        board_of_index(*key)
+    }
+
+    fn produce_bytes(key: &u32) -> Result<Vec<u8>, PokerError> {
+        let board = Board::from_index(*key)?;
+        serialize(
+            &<ProduceFlopTexture as ProduceEvalResult>::produce_eval_result(board.as_slice_card()),
+        )
+    }
+
+    fn key_to_string(key: &u32) -> String {
+        match Board::from_index(*key) {
+            Ok(board) => board.to_string(),
+            Err(e) => format!("{} ({})", key, e),
+        }
+    }
+}
+
+/*
+The producers keyed by board + hole cards + num players only differ in these,
+the rest of MaintainedProducer comes from ProduceEvalWithHcResult
+*/
+pub trait MaintainedWithHcProducer: ProduceEvalWithHcResult {
+    //The only num players the producer accepts, None if any
+    fn fixed_num_players() -> Option<u8>;
+
+    //Same key an agent would use for this board and hole cards
+    fn lookup_key(
+        board: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<EvalWithHcKey, PokerError> {
+        canonical_eval_with_hc_key(board, hole_cards, num_players)
+    }
+
+    //Exact unless the producer is random
+    fn results_match(stored: &[u8], fresh: &[u8]) -> Result<bool, PokerError> {
+        Ok(stored == fresh)
+    }
+}
+
+impl<P> MaintainedProducer for P
+where
+    P: MaintainedWithHcProducer,
+    P::Result: Serialize,
+{
+    type Key = EvalWithHcKey;
+
+    fn cache_name() -> EvalCacheEnum {
+        P::get_cache_name()
+    }
+
+    fn fixed_num_players() -> Option<u8> {
+        <P as MaintainedWithHcProducer>::fixed_num_players()
+    }
+
+    fn for_each_key(
+        num_board_cards: usize,
+        num_players: u8,
+        f: &mut dyn FnMut(EvalWithHcKey) -> bool,
+    ) -> Result<(), PokerError> {
+        for_each_canonical_hc_key(num_board_cards, num_players, f)
+    }
+
+    fn lookup_key(
+        board: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<EvalWithHcKey, PokerError> {
+        <P as MaintainedWithHcProducer>::lookup_key(board, hole_cards, num_players)
+    }
+
+    fn num_board_cards(key: &EvalWithHcKey) -> Result<usize, PokerError> {
+        Ok(decode_eval_with_hc_key(key)?.0.as_slice_card().len())
+    }
+
+    fn produce_bytes(key: &EvalWithHcKey) -> Result<Vec<u8>, PokerError> {
+        let (board, hole_cards, num_players) = decode_eval_with_hc_key(key)?;
+        serialize(&P::produce_eval_result(
+            board.as_slice_card(),
+            &hole_cards,
+            num_players,
+        )?)
+    }
+
+    fn results_match(stored: &[u8], fresh: &[u8]) -> Result<bool, PokerError> {
+        <P as MaintainedWithHcProducer>::results_match(stored, fresh)
+    }
+
+    fn key_to_string(key: &EvalWithHcKey) -> String {
+        match decode_eval_with_hc_key(key) {
+            Ok((board, hole_cards, num_players)) => {
+                format!("{} {} players {}", board, hole_cards, num_players)
+            }
+            Err(e) => format!("{:?} ({})", key, e),
+        }
+    }
+}
+
+impl MaintainedWithHcProducer for ProducePartialRankCards {
+    fn fixed_num_players() -> Option<u8> {
+        Some(0)
+    }
+}
+
+//The equity is a simulation, two runs differ by a bit
+const MONTE_CARLO_TOLERANCE: f64 = 0.03;
+
+impl MaintainedWithHcProducer for ProduceMonteCarloEval {
+    fn fixed_num_players() -> Option<u8> {
+        None
+    }
+
+    //The agents pass the equivalent board to the cache, which is a suit renaming so has the same canonical key
+    fn lookup_key(
+        board: &[Card],
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<EvalWithHcKey, PokerError> {
+        let (eq_hole_cards, eq_board) = get_equivalent_hole_board(hole_cards, board);
+        canonical_eval_with_hc_key(eq_board.as_slice_card(), &eq_hole_cards, num_players)
+    }
+
+    fn results_match(stored: &[u8], fresh: &[u8]) -> Result<bool, PokerError> {
+        let stored: f64 = deserialize(stored)?;
+        let fresh: f64 = deserialize(fresh)?;
+        Ok((stored - fresh).abs() <= MONTE_CARLO_TOLERANCE)
+    }
+}
+
+impl MaintainedWithHcProducer for ProduceHandStrength {
+    fn fixed_num_players() -> Option<u8> {
+        Some(2)
+    }
+}
+
+fn check_num_players<P: MaintainedProducer>(num_players: u8) -> Result<(), PokerError> {
+    match P::fixed_num_players() {
+        Some(fixed) if fixed != num_players => Err(PokerError::from_string(format!(
+            "The {} cache only has num players {}, not {}",
+            P::cache_name().name(),
+            fixed,
+            num_players
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_num_board_cards(num_board_cards: usize) -> Result<(), PokerError> {
+    if !(3..=5).contains(&num_board_cards) {
+        return Err(PokerError::from_string(format!(
+            "Boards have 3 to 5 cards, not {}",
+            num_board_cards
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct PrecomputeReport {
+    pub num_board_cards: usize,
+    pub already_present: usize,
+    pub computed: usize,
+}
+
+impl Display for PrecomputeReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} card boards: computed {} keys, {} were already present",
+            self.num_board_cards, self.computed, self.already_present
+        )
+    }
+}
+
+//Produced this many at a time, split across the threads
+const PRECOMPUTE_CHUNK_SIZE: usize = 10_000;
+
+/*
+Produces and stores every key missing from the cache for boards of num_board_cards cards.
+
+max_to_compute stops early, the keys are always enumerated in the same order so a later
+run picks up where this one stopped.
+*/
+pub fn precompute<P: MaintainedProducer>(
+    store: &BatchedReDb<P::Key>,
+    num_board_cards: usize,
+    num_players: u8,
+    max_to_compute: Option<usize>,
+    num_threads: usize,
+) -> Result<PrecomputeReport, PokerError> {
+    check_num_board_cards(num_board_cards)?;
+    check_num_players::<P>(num_players)?;
+
+    let max_to_compute = max_to_compute.unwrap_or(usize::MAX);
+    let num_threads = num_threads.max(1);
+
+    let mut report = PrecomputeReport {
+        num_board_cards,
+        ..Default::default()
+    };
+
+    let mut chunk: Vec<P::Key> = Vec::with_capacity(PRECOMPUTE_CHUNK_SIZE);
+    let mut result = Ok(());
+
+    let produce_chunk = |chunk: &mut Vec<P::Key>| -> Result<(), PokerError> {
+        let thread_results: Vec<Result<(), PokerError>> = std::thread::scope(|s| {
+            let handles = (0..num_threads)
+                .map(|thread_index| {
+                    let chunk = &chunk;
+                    s.spawn(move || -> Result<(), PokerError> {
+                        for key in chunk.iter().skip(thread_index).step_by(num_threads) {
+                            let bytes = P::produce_bytes(key)?;
+                            store.put_bytes(key.clone(), bytes)?;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect_vec();
+            handles
+                .into_iter()
+                .map(|h| {
+                    h.join()
+                        .unwrap_or_else(|_| Err(PokerError::from_str("Precompute thread panicked")))
+                })
+                .collect_vec()
+        });
+        chunk.clear();
+        thread_results.into_iter().collect()
+    };
+
+    P::for_each_key(num_board_cards, num_players, &mut |key| {
+        match store.contains(&key) {
+            Ok(true) => {
+                report.already_present += 1;
+                return true;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                result = Err(e);
+                return false;
+            }
+        }
+
+        chunk.push(key);
+        report.computed += 1;
+
+        if chunk.len() >= PRECOMPUTE_CHUNK_SIZE || report.computed >= max_to_compute {
+            if let Err(e) = produce_chunk(&mut chunk) {
+                result = Err(e);
+                return false;
+            }
+            info!(
+                "{} card boards: computed {}, {} already present",
+                num_board_cards, report.computed, report.already_present
+            );
+        }
+
+        report.computed < max_to_compute
+    })?;
+    result?;
+
+    produce_chunk(&mut chunk)?;
+    store.flush()?;
+
+    Ok(report)
+}
+
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    pub cache_name: String,
+    //None when the db was never stamped
+    pub schema_version: Option<u32>,
+    pub current_schema_version: u32,
+    pub num_entries: u64,
+    //Indexed by number of board cards
+    pub entries_by_board_cards: [u64; 8],
+    //(num board cards, num sampled, num found)
+    pub sampled_hits: Vec<(usize, usize, usize)>,
+}
+
+impl CacheStats {
+    pub fn is_stale(&self) -> bool {
+        self.num_entries > 0
+            && self.schema_version.unwrap_or(UNSTAMPED_SCHEMA_VERSION)
+                != self.current_schema_version
+    }
+}
+
+impl Display for CacheStats {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} cache: {} entries, schema version {} (producer is at {}){}",
+            self.cache_name,
+            self.num_entries,
+            self.schema_version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "unstamped".to_string()),
+            self.current_schema_version,
+            if self.is_stale() { ", STALE" } else { "" }
+        )?;
+        for (num_board_cards, count) in self.entries_by_board_cards.iter().enumerate() {
+            if *count > 0 {
+                writeln!(f, "  {} card boards: {} entries", num_board_cards, count)?;
+            }
+        }
+        for (num_board_cards, num_sampled, num_found) in self.sampled_hits.iter() {
+            writeln!(
+                f,
+                "  {} card boards: {:.1}% hit rate on {} random lookups",
+                num_board_cards,
+                100.0 * *num_found as f64 / (*num_sampled).max(1) as f64,
+                num_sampled
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/*
+Entry counts by street, and how many of num_samples random deals per street are already cached
+(the hit rate an agent would see on a random deal)
+*/
+pub fn cache_stats<P: MaintainedProducer>(
+    store: &BatchedReDb<P::Key>,
+    num_players: u8,
+    num_samples: usize,
+    seed: u64,
+) -> Result<CacheStats, PokerError> {
+    check_num_players::<P>(num_players)?;
+    store.flush()?;
+
+    let db = store.database();
+    let mut stats = CacheStats {
+        cache_name: P::cache_name().name().to_string(),
+        schema_version: read_schema_version(db)?,
+        current_schema_version: P::cache_name().get_schema_version(),
+        num_entries: P::Key::count(db)?,
+        ..Default::default()
+    };
+
+    let mut result = Ok(());
+    P::Key::for_each(db, &mut |key, _| {
+        match P::num_board_cards(&key) {
+            Ok(n) => stats.entries_by_board_cards[n] += 1,
+            Err(e) => {
+                result = Err(e);
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    })?;
+    result?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for num_board_cards in 3..=5 {
+        let mut num_found = 0;
+        for _ in 0..num_samples {
+            let cards = ALL_CARDS
+                .choose_multiple(&mut rng, num_board_cards + 2)
+                .copied()
+                .collect_vec();
+            let hole_cards = HoleCards::new(cards[0], cards[1])?;
+            let key = P::lookup_key(&cards[2..], &hole_cards, num_players)?;
+            if store.contains(&key)? {
+                num_found += 1;
+            }
+        }
+        stats
+            .sampled_hits
+            .push((num_board_cards, num_samples, num_found));
+    }
+
+    Ok(stats)
+}
+
+//Only the first few mismatches are kept
+pub const MAX_REPORTED_CACHE_MISMATCHES: usize = 20;
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub num_checked: usize,
+    pub num_mismatches: usize,
+    pub mismatches: Vec<String>,
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Checked {} entries, {} did not match a fresh result",
+            self.num_checked, self.num_mismatches
+        )?;
+        for m in self.mismatches.iter() {
+            writeln!(f, "  {}", m)?;
+        }
+        Ok(())
+    }
+}
+
+//Recalculates up to max_entries stored entries and compares them to what is stored
+pub fn verify_cache<P: MaintainedProducer>(
+    store: &BatchedReDb<P::Key>,
+    max_entries: Option<usize>,
+) -> Result<VerifyReport, PokerError> {
+    store.flush()?;
+    let max_entries = max_entries.unwrap_or(usize::MAX);
+
+    let mut report = VerifyReport::default();
+    P::Key::for_each(store.database(), &mut |key, stored| {
+        if report.num_checked >= max_entries {
+            return Ok(false);
+        }
+        report.num_checked += 1;
+
+        let fresh = P::produce_bytes(&key)?;
+        if !P::results_match(stored, &fresh)? {
+            report.num_mismatches += 1;
+            if report.mismatches.len() < MAX_REPORTED_CACHE_MISMATCHES {
+                report.mismatches.push(P::key_to_string(&key));
+            }
+        }
+        Ok(true)
+    })?;
+
+    Ok(report)
+}
+
+/*
+Clears the cache when its schema version stamp is not the producer's, and stamps it.
+
+Returns how many entries were dropped
+*/
+pub fn drop_stale<P: MaintainedProducer>(store: &BatchedReDb<P::Key>) -> Result<u64, PokerError> {
+    store.flush()?;
+    let db = store.database();
+
+    let current_version = P::cache_name().get_schema_version();
+    let num_entries = P::Key::count(db)?;
+    let stored_version = read_schema_version(db)?;
+
+    if stored_version == Some(current_version)
+        || (stored_version.is_none() && UNSTAMPED_SCHEMA_VERSION == current_version)
+    {
+        if stored_version.is_none() {
+            write_schema_version(db, current_version)?;
+        }
+        return Ok(0);
+    }
+
+    info!(
+        "Dropping {} entries with schema version {:?}, now at {}",
+        num_entries, stored_version, current_version
+    );
+    P::Key::clear(db)?;
+    write_schema_version(db, current_version)?;
+
+    Ok(num_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        board_eval_cache_redb::check_schema_version, board_hc_eval_cache_redb::eval_with_hc_key,
+        NUMBER_OF_CANONICAL_FLOPS,
+    };
+
+    fn temp_db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.db", name, std::process::id()));
+        std::fs::remove_file(&path).unwrap_or_default();
+        path
+    }
+
+    #[test]
+    fn test_hc_key_to_string() {
+        let hole_cards: HoleCards = "Kd 2h".parse().unwrap();
+        let mut board = Board::try_from("Ah 7d 2c 9s").unwrap();
+        board.get_index();
+        let key = eval_with_hc_key(&board, &hole_cards, 3).unwrap();
+        assert_eq!(
+            ProducePartialRankCards::key_to_string(&key),
+            "Ah 9s 7d 2c Kd2h players 3"
+        );
+    }
+
+    #[test]
+    fn test_cache_maintenance() {
+        let path = temp_db_path("test_cache_maintenance");
+
+        {
+            let store: BatchedReDb<u32> = BatchedReDb::open(&path, 100).unwrap();
+
+            let report = precompute::<ProduceFlopTexture>(&store, 3, 0, Some(500), 2).unwrap();
+            assert_eq!(report.computed, 500);
+            assert_eq!(report.already_present, 0);
+
+            //Picks up where it stopped
+            let report = precompute::<ProduceFlopTexture>(&store, 3, 0, Some(100), 2).unwrap();
+            assert_eq!(report.computed, 100);
+            assert_eq!(report.already_present, 500);
+
+            let stats = cache_stats::<ProduceFlopTexture>(&store, 0, 200, 7).unwrap();
+            assert_eq!(stats.num_entries, 600);
+            assert_eq!(stats.entries_by_board_cards[3], 600);
+            //Never stamped as it was opened by path
+            assert_eq!(stats.schema_version, None);
+            assert!(!stats.is_stale());
+            let (num_board_cards, num_sampled, num_found) = stats.sampled_hits[0];
+            assert_eq!((num_board_cards, num_sampled), (3, 200));
+            //600 of the 1,755 canonical flops
+            assert!(num_found > 0 && num_found < 200);
+            assert_eq!(stats.sampled_hits[1].2, 0);
+
+            //Every flop an agent can look up
+            let report = precompute::<ProduceFlopTexture>(&store, 3, 0, None, 2).unwrap();
+            assert_eq!(report.computed, NUMBER_OF_CANONICAL_FLOPS - 600);
+            let stats = cache_stats::<ProduceFlopTexture>(&store, 0, 200, 7).unwrap();
+            assert_eq!(stats.num_entries, NUMBER_OF_CANONICAL_FLOPS as u64);
+            assert_eq!(stats.sampled_hits[0], (3, 200, 200));
+
+            let report = verify_cache::<ProduceFlopTexture>(&store, None).unwrap();
+            assert_eq!(report.num_checked, NUMBER_OF_CANONICAL_FLOPS);
+            assert_eq!(report.num_mismatches, 0);
+
+            //A wrong entry
+            let first_key = ProduceFlopTexture::lookup_key(
+                Board::try_from("2c 2d 2h").unwrap().as_slice_card(),
+                &"As Ks".parse().unwrap(),
+                0,
+            )
+            .unwrap();
+            assert!(store.contains(&first_key).unwrap());
+            store
+                .put(
+                    first_key,
+                    &<ProduceFlopTexture as ProduceEvalResult>::produce_eval_result(
+                        Board::try_from("Ac Kc Qc").unwrap().as_slice_card(),
+                    ),
+                )
+                .unwrap();
+            let report = verify_cache::<ProduceFlopTexture>(&store, Some(50)).unwrap();
+            assert_eq!(report.num_checked, 50);
+            assert_eq!(report.num_mismatches, 1);
+            //Stored under the canonical board
+            assert_eq!(report.mismatches, vec!["2s 2h 2d".to_string()]);
+
+            //Unstamped counts as version 1, same as the flop texture producer
+            assert_eq!(drop_stale::<ProduceFlopTexture>(&store).unwrap(), 0);
+            assert_eq!(read_schema_version(store.database()).unwrap(), Some(1));
+
+            //The producer moved on
+            write_schema_version(store.database(), 0).unwrap();
+            assert!(cache_stats::<ProduceFlopTexture>(&store, 0, 0, 7)
+                .unwrap()
+                .is_stale());
+            assert!(
+                check_schema_version::<u32>(store.database(), EvalCacheEnum::FlopTexture).is_err()
+            );
+            assert_eq!(
+                drop_stale::<ProduceFlopTexture>(&store).unwrap(),
+                NUMBER_OF_CANONICAL_FLOPS as u64
+            );
+            assert!(
+                check_schema_version::<u32>(store.database(), EvalCacheEnum::FlopTexture).is_ok()
+            );
+            let stats = cache_stats::<ProduceFlopTexture>(&store, 0, 0, 7).unwrap();
+            assert_eq!(stats.num_entries, 0);
+            assert_eq!(stats.schema_version, Some(1));
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let path = temp_db_path("test_cache_maintenance_hc");
+        {
+            let store: BatchedReDb<EvalWithHcKey> = BatchedReDb::open(&path, 100).unwrap();
+            assert!(precompute::<ProducePartialRankCards>(&store, 3, 4, Some(10), 2).is_err());
+
+            let report = precompute::<ProducePartialRankCards>(&store, 4, 0, Some(300), 3).unwrap();
+            assert_eq!(report.computed, 300);
+            let report = verify_cache::<ProducePartialRankCards>(&store, None).unwrap();
+            assert_eq!(report.num_checked, 300);
+            assert_eq!(report.num_mismatches, 0);
+
+            //Every hole cards on the first turn, then the next turn
+            let stats = cache_stats::<ProducePartialRankCards>(&store, 0, 10, 7).unwrap();
+            assert_eq!(stats.entries_by_board_cards[4], 300);
+
+            //Isomorphic deals share a key
+            let mut num_keys = 0;
+            ProduceMonteCarloEval::for_each_key(3, 2, &mut |_| {
+                num_keys += 1;
+                num_keys < 5_000
+            })
+            .unwrap();
+            assert_eq!(num_keys, 5_000);
+            let key1 = <ProduceMonteCarloEval as MaintainedProducer>::lookup_key(
+                Board::try_from("Ah 7d 2c").unwrap().as_slice_card(),
+                &"Kh Qd".parse().unwrap(),
+                3,
+            )
+            .unwrap();
+            let key2 = <ProduceMonteCarloEval as MaintainedProducer>::lookup_key(
+                Board::try_from("As 7c 2d").unwrap().as_slice_card(),
+                &"Ks Qc".parse().unwrap(),
+                3,
+            )
+            .unwrap();
+            assert_eq!(key1, key2);
+            assert_eq!(
+                <ProducePartialRankCards as MaintainedProducer>::lookup_key(
+                    Board::try_from("Ah 7d 2c").unwrap().as_slice_card(),
+                    &"Kh Qd".parse().unwrap(),
+                    0,
+                )
+                .unwrap(),
+                <ProducePartialRankCards as MaintainedProducer>::lookup_key(
+                    Board::try_from("2d 7c As").unwrap().as_slice_card(),
+                    &"Qc Ks".parse().unwrap(),
+                    0,
+                )
+                .unwrap()
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
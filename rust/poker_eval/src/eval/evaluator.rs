@@ -0,0 +1,462 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    eval::kev::{eval_5cards, eval_6cards, eval_7cards},
+    pre_calc::{
+        fast_eval::fast_hand_eval_for_variant, perfect_hash::BOOM_PERFECT_HASH, rank::RankEnum,
+    },
+    rank_cards, Card, Deck, DeckVariant, OldRank, PokerError, ALL_CARDS,
+};
+
+/*
+The crate has 3 hand evaluators, the perfect hash lookup (fast_hand_eval), the Cactus Kev port
+and the original rank_cards.  They number hands differently, so each one gives a HandValue,
+with a strength that can only be compared to strengths from the same evaluator
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HandValue {
+    pub family: RankEnum,
+    //Higher is better
+    pub strength: u32,
+}
+
+//Below any real hand, for buffers that are filled in later
+impl Default for HandValue {
+    fn default() -> Self {
+        HandValue {
+            family: RankEnum::HighCard,
+            strength: 0,
+        }
+    }
+}
+
+pub trait HandEvaluator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    //Best hand of 5 to 7 cards
+    fn evaluate(&self, cards: &[Card]) -> HandValue;
+}
+
+//Also evaluates the short deck, where a flush beats a full house
+#[derive(Default)]
+pub struct FastEvaluator {
+    deck_variant: DeckVariant,
+}
+
+impl FastEvaluator {
+    pub fn new_for_variant(deck_variant: DeckVariant) -> Self {
+        FastEvaluator { deck_variant }
+    }
+}
+
+impl HandEvaluator for FastEvaluator {
+    fn name(&self) -> &'static str {
+        EvaluatorBackend::Fast.name()
+    }
+
+    fn evaluate(&self, cards: &[Card]) -> HandValue {
+        let rank = fast_hand_eval_for_variant(cards.iter(), &BOOM_PERFECT_HASH, self.deck_variant);
+        HandValue {
+            family: rank.get_rank_enum(),
+            strength: rank.strength(self.deck_variant) as u32,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct KevEvaluator;
+
+//Cactus Kev numbers hands from 1 (royal flush) to 7462 (7 5 4 3 2)
+const KEV_WORST_HAND: u32 = 7462;
+
+fn kev_family(kev_rank: u32) -> RankEnum {
+    match kev_rank {
+        1..=10 => RankEnum::StraightFlush,
+        11..=166 => RankEnum::FourOfAKind,
+        167..=322 => RankEnum::FullHouse,
+        323..=1599 => RankEnum::Flush,
+        1600..=1609 => RankEnum::Straight,
+        1610..=2467 => RankEnum::ThreeOfAKind,
+        2468..=3325 => RankEnum::TwoPair,
+        3326..=6185 => RankEnum::OnePair,
+        _ => RankEnum::HighCard,
+    }
+}
+
+impl HandEvaluator for KevEvaluator {
+    fn name(&self) -> &'static str {
+        EvaluatorBackend::Kev.name()
+    }
+
+    fn evaluate(&self, cards: &[Card]) -> HandValue {
+        let c = cards.iter().map(|c| c.index as usize).collect_vec();
+        let kev_rank = match c.len() {
+            5 => eval_5cards(c[0], c[1], c[2], c[3], c[4]),
+            6 => eval_6cards(c[0], c[1], c[2], c[3], c[4], c[5]),
+            7 => eval_7cards(c[0], c[1], c[2], c[3], c[4], c[5], c[6]),
+            n => panic!("Kev evaluates 5 to 7 cards, not {}", n),
+        } as u32;
+        HandValue {
+            family: kev_family(kev_rank),
+            strength: KEV_WORST_HAND + 1 - kev_rank,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct OldRankEvaluator;
+
+impl HandEvaluator for OldRankEvaluator {
+    fn name(&self) -> &'static str {
+        EvaluatorBackend::OldRank.name()
+    }
+
+    fn evaluate(&self, cards: &[Card]) -> HandValue {
+        let old_rank = rank_cards(cards.iter());
+        let (family, value) = match old_rank {
+            OldRank::HighCard(v) => (RankEnum::HighCard, v),
+            OldRank::OnePair(v) => (RankEnum::OnePair, v),
+            OldRank::TwoPair(v) => (RankEnum::TwoPair, v),
+            OldRank::ThreeOfAKind(v) => (RankEnum::ThreeOfAKind, v),
+            OldRank::Straight(v) => (RankEnum::Straight, v),
+            OldRank::Flush(v) => (RankEnum::Flush, v),
+            OldRank::FullHouse(v) => (RankEnum::FullHouse, v),
+            OldRank::FourOfAKind(v) => (RankEnum::FourOfAKind, v),
+            OldRank::StraightFlush(v) => (RankEnum::StraightFlush, v),
+        };
+        //The values are card value bit sets shifted by at most 13, so they fit under the family
+        HandValue {
+            family,
+            strength: ((family as u32) << 26) | value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluatorBackend {
+    Fast,
+    Kev,
+    OldRank,
+}
+
+impl EvaluatorBackend {
+    pub fn all() -> [EvaluatorBackend; 3] {
+        [
+            EvaluatorBackend::Fast,
+            EvaluatorBackend::Kev,
+            EvaluatorBackend::OldRank,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvaluatorBackend::Fast => "fast",
+            EvaluatorBackend::Kev => "kev",
+            EvaluatorBackend::OldRank => "old_rank",
+        }
+    }
+
+    pub fn evaluator(&self) -> Box<dyn HandEvaluator> {
+        match self {
+            EvaluatorBackend::Fast => Box::<FastEvaluator>::default(),
+            EvaluatorBackend::Kev => Box::new(KevEvaluator),
+            EvaluatorBackend::OldRank => Box::new(OldRankEvaluator),
+        }
+    }
+}
+
+impl FromStr for EvaluatorBackend {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EvaluatorBackend::all()
+            .into_iter()
+            .find(|b| b.name() == s)
+            .ok_or(PokerError::from_string(format!(
+                "Unknown evaluator {}, expected fast, kev or old_rank",
+                s
+            )))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossValidationMode {
+    //Every hand of the number of cards, 2,598,960 for 5 cards but 133,784,560 for 7
+    Exhaustive,
+    Random { num_hands: usize, seed: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct EvaluatorMismatch {
+    pub cards: Vec<Card>,
+    pub reference: HandValue,
+    pub other: HandValue,
+    pub reason: String,
+}
+
+//Only the first ones are kept, the rest are just counted
+pub const MAX_REPORTED_MISMATCHES: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct CrossValidationReport {
+    pub reference_name: &'static str,
+    pub other_name: &'static str,
+    pub num_cards: usize,
+    pub num_hands: usize,
+    pub num_mismatches: usize,
+    pub mismatches: Vec<EvaluatorMismatch>,
+}
+
+impl CrossValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.num_mismatches == 0
+    }
+
+    fn add_mismatch(&mut self, mismatch: EvaluatorMismatch) {
+        self.num_mismatches += 1;
+        if self.mismatches.len() < MAX_REPORTED_MISMATCHES {
+            self.mismatches.push(mismatch);
+        }
+    }
+}
+
+impl fmt::Display for CrossValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} vs {} on {} {} card hands: {} mismatches",
+            self.reference_name,
+            self.other_name,
+            self.num_hands,
+            self.num_cards,
+            self.num_mismatches
+        )?;
+        for m in self.mismatches.iter() {
+            writeln!(
+                f,
+                "  [{}] {}: {} {:?} vs {} {:?}",
+                m.cards.iter().join(" "),
+                m.reason,
+                self.reference_name,
+                m.reference,
+                self.other_name,
+                m.other
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/*
+Checks other against reference, so they can be swapped in for each other.
+
+Both must agree on the family, and order hands the same way, so hands with the same reference strength
+have the same other strength, and a higher reference strength means a higher other strength
+*/
+pub fn cross_validate(
+    reference: &dyn HandEvaluator,
+    other: &dyn HandEvaluator,
+    num_cards: usize,
+    mode: CrossValidationMode,
+) -> Result<CrossValidationReport, PokerError> {
+    if !(5..=7).contains(&num_cards) {
+        return Err(PokerError::from_string(format!(
+            "Can only cross validate 5 to 7 card hands, not {}",
+            num_cards
+        )));
+    }
+
+    let mut report = CrossValidationReport {
+        reference_name: reference.name(),
+        other_name: other.name(),
+        num_cards,
+        num_hands: 0,
+        num_mismatches: 0,
+        mismatches: Vec::new(),
+    };
+
+    //reference strength => the first hand with it
+    let mut seen: HashMap<u32, (HandValue, HandValue, Vec<Card>)> = HashMap::new();
+
+    let mut check_hand = |cards: &[Card]| {
+        let reference_value = reference.evaluate(cards);
+        let other_value = other.evaluate(cards);
+        report.num_hands += 1;
+
+        if reference_value.family != other_value.family {
+            report.add_mismatch(EvaluatorMismatch {
+                cards: cards.to_vec(),
+                reference: reference_value,
+                other: other_value,
+                reason: "Different family".to_string(),
+            });
+            return;
+        }
+
+        let (_, first_other_value, first_cards) = seen
+            .entry(reference_value.strength)
+            .or_insert_with(|| (reference_value, other_value, cards.to_vec()));
+        if first_other_value.strength != other_value.strength {
+            let reason = format!(
+                "Same {} strength as [{}] but different {} strength",
+                report.reference_name,
+                first_cards.iter().join(" "),
+                report.other_name
+            );
+            report.add_mismatch(EvaluatorMismatch {
+                cards: cards.to_vec(),
+                reference: reference_value,
+                other: other_value,
+                reason,
+            });
+        }
+    };
+
+    match mode {
+        CrossValidationMode::Exhaustive => {
+            for cards in ALL_CARDS.iter().copied().combinations(num_cards) {
+                check_hand(&cards);
+            }
+        }
+        CrossValidationMode::Random { num_hands, seed } => {
+            let mut deck = Deck::new_with_rng(StdRng::seed_from_u64(seed));
+            let mut cards = Vec::with_capacity(num_cards);
+            for _ in 0..num_hands {
+                deck.reset();
+                cards.clear();
+                for _ in 0..num_cards {
+                    cards.push(deck.get_unused_card()?);
+                }
+                check_hand(&cards);
+            }
+        }
+    }
+
+    //Now the order, the other strengths must go up with the reference strengths
+    for (lower, higher) in seen
+        .values()
+        .sorted_by_key(|(reference_value, _, _)| reference_value.strength)
+        .tuple_windows()
+    {
+        if higher.1.strength <= lower.1.strength {
+            let reason = format!(
+                "Beats [{}] for {} but not for {}",
+                lower.2.iter().join(" "),
+                report.reference_name,
+                report.other_name
+            );
+            report.add_mismatch(EvaluatorMismatch {
+                cards: higher.2.clone(),
+                reference: higher.0,
+                other: higher.1,
+                reason,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Board;
+
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Board::try_from(s).unwrap().as_slice_card().to_vec()
+    }
+
+    #[test]
+    fn test_evaluators_agree() {
+        let evaluators = EvaluatorBackend::all().map(|b| b.evaluator());
+
+        for evaluator in evaluators.iter() {
+            let eval = |s: &str| evaluator.evaluate(&cards(s));
+
+            assert_eq!(eval("2d 5s Qd 3h Kh 4s Ac").family, RankEnum::Straight);
+            assert!(eval("2d 5s Qd 3h Kh 4s Ac") < eval("2d 5s 6d 3h Kh 4s"));
+            assert_eq!(eval("Ah Kh Qh Jh Th").family, RankEnum::StraightFlush);
+            assert_eq!(
+                eval("Ah Kh Qh Jh Th 2c").strength,
+                eval("As Ks Qs Js Ts").strength
+            );
+            assert!(eval("Kd Kc 2h 2s 7c").strength > eval("Qd Qc Jh Js Ac").strength);
+            assert_eq!(
+                evaluator.name().parse::<EvaluatorBackend>().unwrap().name(),
+                evaluator.name()
+            );
+        }
+
+        let reference = FastEvaluator::default();
+        for num_cards in 5..=7 {
+            for other in [&KevEvaluator as &dyn HandEvaluator, &OldRankEvaluator] {
+                let report = cross_validate(
+                    &reference,
+                    other,
+                    num_cards,
+                    CrossValidationMode::Random {
+                        num_hands: 10_000,
+                        seed: 42,
+                    },
+                )
+                .unwrap();
+                assert_eq!(report.num_hands, 10_000);
+                assert!(report.is_ok(), "{}", report);
+            }
+        }
+
+        assert!("cactus".parse::<EvaluatorBackend>().is_err());
+    }
+
+    //Pairs are ranked below high cards
+    struct BrokenEvaluator;
+
+    impl HandEvaluator for BrokenEvaluator {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+
+        fn evaluate(&self, cards: &[Card]) -> HandValue {
+            let value = KevEvaluator.evaluate(cards);
+            match value.family {
+                RankEnum::OnePair => HandValue {
+                    family: value.family,
+                    strength: value.strength % 100,
+                },
+                _ => value,
+            }
+        }
+    }
+
+    #[test]
+    fn test_cross_validate_mismatches() {
+        let report = cross_validate(
+            &KevEvaluator,
+            &BrokenEvaluator,
+            5,
+            CrossValidationMode::Random {
+                num_hands: 5_000,
+                seed: 1,
+            },
+        )
+        .unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.mismatches.len(), MAX_REPORTED_MISMATCHES);
+        assert!(report.num_mismatches > MAX_REPORTED_MISMATCHES);
+        assert!(report
+            .to_string()
+            .starts_with("kev vs broken on 5000 5 card hands"));
+
+        assert!(cross_validate(
+            &KevEvaluator,
+            &BrokenEvaluator,
+            4,
+            CrossValidationMode::Exhaustive
+        )
+        .is_err());
+    }
+}
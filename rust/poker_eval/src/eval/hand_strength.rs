@@ -0,0 +1,276 @@
+use boomphf::Mphf;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    deal_cards_used, pre_calc::fast_eval::fast_hand_eval, range_hole_cards_left, BoolRange, Card,
+    CardUsedType, HoleCards, PokerError, ALL_CARDS,
+};
+
+/*
+The hand strength metrics from Billings et al, "The challenge of poker"
+
+All against one opponent holding a hand from a range, ties count as half a win
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct HandStrength {
+    //HS, the fraction of the range we beat right now
+    pub hand_strength: f64,
+    //PPot, chance of getting ahead when we are behind (or tied) now
+    pub positive_potential: f64,
+    //NPot, chance of falling behind when we are ahead (or tied) now
+    pub negative_potential: f64,
+    //EHS = HS * (1 - NPot) + (1 - HS) * PPot
+    pub effective_hand_strength: f64,
+    //EHS², the average of the squared hand strength after the cards to come
+    //which unlike EHS rewards hands that will be either very strong or very weak (draws)
+    pub effective_hand_strength_sq: f64,
+}
+
+const AHEAD: usize = 0;
+const TIED: usize = 1;
+const BEHIND: usize = 2;
+
+fn compare_index(hero_rank: u16, villian_rank: u16) -> usize {
+    match hero_rank.cmp(&villian_rank) {
+        std::cmp::Ordering::Greater => AHEAD,
+        std::cmp::Ordering::Equal => TIED,
+        std::cmp::Ordering::Less => BEHIND,
+    }
+}
+
+//ahead counts 1 and a tie 1/2
+fn strength_from_counts(counts: &[f64; 3]) -> f64 {
+    let total = counts.iter().sum::<f64>();
+    if total == 0.0 {
+        return 0.0;
+    }
+    (counts[AHEAD] + counts[TIED] / 2.0) / total
+}
+
+fn ratio_or_zero(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+/*
+HS, PPot, NPot, EHS and EHS² for hole cards on a flop, turn or river, vs one opponent with any hand in range.
+
+num_cards_to_come is how many more board cards to look at for the potentials, 1 for the next card
+or up to 5 - board.len() to go to the river.  Every runout is enumerated.
+*/
+pub fn calc_hand_strength(
+    hole_cards: &HoleCards,
+    board: &[Card],
+    range: &BoolRange,
+    num_cards_to_come: usize,
+    hash_func: &Mphf<u32>,
+) -> Result<HandStrength, PokerError> {
+    if board.len() < 3 || board.len() > 5 {
+        return Err(PokerError::from_string(format!(
+            "Need a flop, turn or river, not {} board cards",
+            board.len()
+        )));
+    }
+    if board.len() + num_cards_to_come > 5 {
+        return Err(PokerError::from_string(format!(
+            "Only {} cards to come after {} board cards",
+            5 - board.len(),
+            board.len()
+        )));
+    }
+
+    let cards_used = deal_cards_used(board, hole_cards)?;
+    let villian_hole_cards = range_hole_cards_left(range, &cards_used)?;
+
+    let eval = |hc: &HoleCards, runout: &[Card]| {
+        fast_hand_eval(
+            board
+                .iter()
+                .chain(hc.as_slice().iter())
+                .chain(runout.iter()),
+            hash_func,
+        )
+        .raw_rank
+    };
+
+    //Where we are now vs each of the villian's hands
+    let hero_rank = eval(hole_cards, &[]);
+    let mut hs_counts = [0.0; 3];
+    let current_index = villian_hole_cards
+        .iter()
+        .map(|hc| {
+            let index = compare_index(hero_rank, eval(hc, &[]));
+            hs_counts[index] += 1.0;
+            index
+        })
+        .collect_vec();
+    let hand_strength = strength_from_counts(&hs_counts);
+
+    if num_cards_to_come == 0 {
+        return Ok(HandStrength {
+            hand_strength,
+            positive_potential: 0.0,
+            negative_potential: 0.0,
+            effective_hand_strength: hand_strength,
+            effective_hand_strength_sq: hand_strength * hand_strength,
+        });
+    }
+
+    //hand_potential[now][after the runout]
+    let mut hand_potential = [[0.0; 3]; 3];
+    let mut hs_sq_sum = 0.0;
+    let mut num_runouts = 0;
+
+    let remaining_cards = ALL_CARDS
+        .iter()
+        .filter(|c| !cards_used[c.index as usize])
+        .copied()
+        .collect_vec();
+
+    for runout in remaining_cards.into_iter().combinations(num_cards_to_come) {
+        let mut runout_used = CardUsedType::default();
+        for c in runout.iter() {
+            runout_used.set(c.index as usize, true);
+        }
+
+        let hero_final_rank = eval(hole_cards, &runout);
+        let mut runout_counts = [0.0; 3];
+
+        for (villian_index, hc) in villian_hole_cards.iter().enumerate() {
+            if runout_used[hc.get_hi_card().index as usize]
+                || runout_used[hc.get_lo_card().index as usize]
+            {
+                continue;
+            }
+            let final_index = compare_index(hero_final_rank, eval(hc, &runout));
+            hand_potential[current_index[villian_index]][final_index] += 1.0;
+            runout_counts[final_index] += 1.0;
+        }
+
+        if runout_counts.iter().sum::<f64>() > 0.0 {
+            let runout_strength = strength_from_counts(&runout_counts);
+            hs_sq_sum += runout_strength * runout_strength;
+            num_runouts += 1;
+        }
+    }
+
+    let totals = hand_potential.map(|row| row.iter().sum::<f64>());
+
+    let positive_potential = ratio_or_zero(
+        hand_potential[BEHIND][AHEAD]
+            + hand_potential[BEHIND][TIED] / 2.0
+            + hand_potential[TIED][AHEAD] / 2.0,
+        totals[BEHIND] + totals[TIED] / 2.0,
+    );
+    let negative_potential = ratio_or_zero(
+        hand_potential[AHEAD][BEHIND]
+            + hand_potential[TIED][BEHIND] / 2.0
+            + hand_potential[AHEAD][TIED] / 2.0,
+        totals[AHEAD] + totals[TIED] / 2.0,
+    );
+
+    Ok(HandStrength {
+        hand_strength,
+        positive_potential,
+        negative_potential,
+        effective_hand_strength: hand_strength * (1.0 - negative_potential)
+            + (1.0 - hand_strength) * positive_potential,
+        effective_hand_strength_sq: ratio_or_zero(hs_sq_sum, num_runouts as f64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval_test_deal, pre_calc::perfect_hash::load_boomperfect_hash, Board};
+
+    use super::*;
+
+    fn calc(hole_cards: &str, board: &str, range: &str, num_cards_to_come: usize) -> HandStrength {
+        eval_test_deal(hole_cards, board, |hole_cards, board, hash_func| {
+            calc_hand_strength(
+                hole_cards,
+                board,
+                &range.parse().unwrap(),
+                num_cards_to_come,
+                hash_func,
+            )
+        })
+    }
+
+    #[test]
+    fn test_hand_strength() {
+        //River, nothing to come
+        let hs = calc(
+            "AsAh",
+            "Ks Kd 2c 7h 9d",
+            "22+,A2+,K2+,Q2+,J2+,T2+,92+,82+,72+,62+,52+,42+,32",
+            0,
+        );
+        assert!(hs.hand_strength > 0.8 && hs.hand_strength < 1.0);
+        assert_eq!(hs.positive_potential, 0.0);
+        assert_eq!(hs.effective_hand_strength, hs.hand_strength);
+        assert_eq!(
+            hs.effective_hand_strength_sq,
+            hs.hand_strength * hs.hand_strength
+        );
+
+        //Royal flush on the board, always a tie
+        let hs = calc("2c3d", "As Ks Qs Js Ts", "AA,KK", 0);
+        assert_eq!(hs.hand_strength, 0.5);
+
+        //A big draw vs overpairs, behind now but lots of outs
+        let next_card = calc("9h8h", "Th 7h 2c", "AA,KK", 1);
+        assert_eq!(next_card.hand_strength, 0.0);
+        assert_eq!(next_card.negative_potential, 0.0);
+        //9 flush outs and 6 more straight outs out of 45 unseen cards, less the blocked ones
+        assert!(
+            next_card.positive_potential > 0.3 && next_card.positive_potential < 15.0 / 45.0,
+            "{:?}",
+            next_card
+        );
+        assert_eq!(
+            next_card.effective_hand_strength,
+            next_card.positive_potential
+        );
+
+        let to_river = calc("9h8h", "Th 7h 2c", "AA,KK", 2);
+        assert!(to_river.positive_potential > next_card.positive_potential);
+
+        //Now the overpair vs the draw
+        let hs = calc("AsAd", "Th 7h 2c", "9h8h,6h5h", 1);
+        assert_eq!(hs.hand_strength, 1.0);
+        assert!(hs.negative_potential > 0.2, "{:?}", hs);
+        assert!(
+            (hs.effective_hand_strength - (1.0 - hs.negative_potential)).abs() < 1e-12,
+            "{:?}",
+            hs
+        );
+        assert!(hs.effective_hand_strength_sq <= hs.effective_hand_strength);
+
+        let hash_func = load_boomperfect_hash();
+        let board: Board = "Th 7h 2c".parse().unwrap();
+        let hole_cards: HoleCards = "AsAd".parse().unwrap();
+        //Turn + river is all that's left
+        assert!(calc_hand_strength(
+            &hole_cards,
+            board.as_slice_card(),
+            &"KK".parse().unwrap(),
+            3,
+            &hash_func
+        )
+        .is_err());
+        //Only AsAd is in the range, and we have it
+        assert!(calc_hand_strength(
+            &hole_cards,
+            board.as_slice_card(),
+            &"AsAd".parse().unwrap(),
+            1,
+            &hash_func
+        )
+        .is_err());
+    }
+}
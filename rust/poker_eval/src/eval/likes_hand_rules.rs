@@ -0,0 +1,582 @@
+use std::{
+    cmp::{max, min},
+    collections::HashSet,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    likes_hands::{LikesHandLevel, LikesHandResponse},
+    pre_calc::rank::RankEnum,
+    Board, BoardTexture, CardValue, FlushDrawType, HoleCards, PairInfo, PartialRankContainer,
+    PokerError, Round, StraightDrawType,
+};
+
+/*
+likes_hand driven by a rule file instead of code, so a bot can be tuned without recompiling.
+
+The file is JSON, a list of rules applied in order, each one either raising the level to at least
+raise_to or capping it at cap_at when all its conditions hold (a missing condition always holds).
+
+A rule with stop skips the rest of the rules in its group when it fires, like an early return
+in likes_hand. It may have no level at all and only stop.
+
+{
+  "rules": [
+    { "name": "pair_heads_up", "group": "pairs", "when": { "paired_hole_card": true, "max_players": 2 }, "raise_to": "LargeBet", "stop": true },
+    { "name": "top_pair", "group": "pairs", "when": { "hi_pair_max_above": 0, "min_hi_card": "Eight" }, "raise_to": "LargeBet" },
+    { "name": "flush_board", "when": { "min_board_suited": 3, "max_rank_family": "Straight" }, "cap_at": "LargeBet" }
+  ]
+}
+
+likes_hand_rules.json is a port of the hard coded likes_hand
+*/
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LikesHandRules {
+    pub rules: Vec<LikesHandRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LikesHandRule {
+    pub name: String,
+    //Added to the name in the comments
+    pub comment: Option<String>,
+
+    //Exactly one of these, or at most one with stop
+    pub raise_to: Option<LikesHandLevel>,
+    pub cap_at: Option<LikesHandLevel>,
+
+    //Needed for stop
+    pub group: Option<String>,
+    #[serde(default)]
+    pub stop: bool,
+
+    //All must hold, no conditions always fires
+    #[serde(default)]
+    pub when: RuleConditions,
+}
+
+//Each is ignored when None
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConditions {
+    pub streets: Option<Vec<Round>>,
+    pub min_players: Option<u8>,
+    pub max_players: Option<u8>,
+
+    //Of our best 5 card hand
+    pub min_rank_family: Option<RankEnum>,
+    pub max_rank_family: Option<RankEnum>,
+
+    //Our hole cards
+    pub min_hi_card: Option<CardValue>,
+    pub max_hi_card: Option<CardValue>,
+    pub min_lo_card: Option<CardValue>,
+    pub max_lo_card: Option<CardValue>,
+    pub min_overcards: Option<u8>,
+
+    //What our hole cards made, from the partial rank
+    pub made_set: Option<bool>,
+    pub made_two_pair: Option<bool>,
+    pub top_pair: Option<bool>,
+    //Either hole card paired the board
+    pub paired_hole_card: Option<bool>,
+    //The pair exists with at least / at most this many higher board cards
+    pub hi_pair_min_above: Option<u8>,
+    pub hi_pair_max_above: Option<u8>,
+    pub lo_pair_min_above: Option<u8>,
+    pub lo_pair_max_above: Option<u8>,
+    pub pocket_pair_max_above: Option<u8>,
+    pub pocket_pair_min_below: Option<u8>,
+    pub min_made_flush_card: Option<CardValue>,
+    pub max_made_flush_card: Option<CardValue>,
+
+    //Draws, flush_draw is 4 to a flush, straight_draw is open ended or a double gut shot
+    pub flush_draw: Option<bool>,
+    pub nut_flush_draw: Option<bool>,
+    pub min_flush_draw_card: Option<CardValue>,
+    pub straight_draw: Option<bool>,
+    pub gut_shot: Option<bool>,
+    pub combo_draw: Option<bool>,
+    pub pair_and_draw: Option<bool>,
+
+    //The board texture
+    pub board_paired: Option<bool>,
+    pub board_has_trips: Option<bool>,
+    pub board_has_straight: Option<bool>,
+    pub board_has_full_house: Option<bool>,
+    pub board_has_quads: Option<bool>,
+    pub min_board_suited: Option<u8>,
+    pub max_board_suited: Option<u8>,
+    //Number of other hole cards making a straight
+    pub min_others_with_str8: Option<usize>,
+}
+
+//Everything a rule can look at
+pub struct RuleInput<'a> {
+    pub prc: &'a PartialRankContainer,
+    pub ft: &'a BoardTexture,
+    pub rank_family: RankEnum,
+    pub board: &'a Board,
+    pub hc: &'a HoleCards,
+    pub num_in_pot: u8,
+}
+
+//min <= value <= max for the limits that are set
+fn in_range<T: PartialOrd>(value: T, min_value: Option<T>, max_value: Option<T>) -> bool {
+    min_value.is_none_or(|m| value >= m) && max_value.is_none_or(|m| value <= m)
+}
+
+//No limits, or the pair exists and its number above is within them
+fn pair_in_range(pair: Option<PairInfo>, min_above: Option<u8>, max_above: Option<u8>) -> bool {
+    if min_above.is_none() && max_above.is_none() {
+        return true;
+    }
+    pair.is_some_and(|p| in_range(p.number_above, min_above, max_above))
+}
+
+fn matches_flag(expected: Option<bool>, actual: bool) -> bool {
+    expected.is_none_or(|e| e == actual)
+}
+
+fn check_range<T: PartialOrd + std::fmt::Debug>(
+    rule_name: &str,
+    field: &str,
+    min_value: Option<T>,
+    max_value: Option<T>,
+) -> Result<(), PokerError> {
+    if let (Some(min_value), Some(max_value)) = (min_value, max_value) {
+        if min_value > max_value {
+            return Err(PokerError::from_string(format!(
+                "Rule {}: min_{} {:?} is more than max_{} {:?}",
+                rule_name, field, min_value, field, max_value
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl RuleConditions {
+    pub fn matches(&self, input: &RuleInput) -> Result<bool, PokerError> {
+        let prc = input.prc;
+        let ft = input.ft;
+        let round = input.board.get_round()?;
+
+        let has_flush_draw = prc
+            .flush_draw
+            .is_some_and(|fd| fd.flush_draw_type == FlushDrawType::FlushDraw);
+        let has_gut_shot = prc
+            .straight_draw
+            .is_some_and(|sd| matches!(sd.straight_draw_type, StraightDrawType::GutShot(_)));
+
+        Ok(self.streets.as_ref().is_none_or(|s| s.contains(&round))
+            && in_range(input.num_in_pot, self.min_players, self.max_players)
+            && in_range(
+                input.rank_family,
+                self.min_rank_family,
+                self.max_rank_family,
+            )
+            && in_range(
+                input.hc.get_hi_card().value,
+                self.min_hi_card,
+                self.max_hi_card,
+            )
+            && in_range(
+                input.hc.get_lo_card().value,
+                self.min_lo_card,
+                self.max_lo_card,
+            )
+            && in_range(prc.get_num_overcards(), self.min_overcards, None)
+            && matches_flag(self.made_set, prc.made_a_set().is_some())
+            && matches_flag(self.made_two_pair, prc.made_two_pair().is_some())
+            && matches_flag(self.top_pair, prc.has_top_pair())
+            && matches_flag(
+                self.paired_hole_card,
+                prc.hi_pair.is_some() || prc.lo_pair.is_some(),
+            )
+            && pair_in_range(prc.hi_pair, self.hi_pair_min_above, self.hi_pair_max_above)
+            && pair_in_range(prc.lo_pair, self.lo_pair_min_above, self.lo_pair_max_above)
+            && self
+                .pocket_pair_max_above
+                .is_none_or(|m| prc.pocket_pair.is_some_and(|p| p.number_above <= m))
+            && self
+                .pocket_pair_min_below
+                .is_none_or(|m| prc.pocket_pair.is_some_and(|p| p.number_below >= m))
+            && (self.min_made_flush_card.is_none() && self.max_made_flush_card.is_none()
+                || prc.made_flush.is_some_and(|v| {
+                    in_range(v, self.min_made_flush_card, self.max_made_flush_card)
+                }))
+            && matches_flag(self.flush_draw, has_flush_draw)
+            && matches_flag(self.nut_flush_draw, prc.has_nut_flush_draw())
+            && self.min_flush_draw_card.is_none_or(|m| {
+                has_flush_draw && prc.flush_draw.is_some_and(|fd| fd.hole_card_value >= m)
+            })
+            && matches_flag(self.straight_draw, prc.has_straight_draw())
+            && matches_flag(self.gut_shot, has_gut_shot)
+            && matches_flag(self.combo_draw, prc.has_combo_draw())
+            && matches_flag(self.pair_and_draw, prc.has_pair_and_draw())
+            && matches_flag(
+                self.board_paired,
+                ft.has_pair || ft.has_two_pair || ft.has_trips,
+            )
+            && matches_flag(self.board_has_trips, ft.has_trips)
+            && matches_flag(self.board_has_straight, ft.has_straight)
+            && matches_flag(self.board_has_full_house, ft.has_fh)
+            && matches_flag(self.board_has_quads, ft.has_quads)
+            && in_range(
+                ft.same_suited_max_count,
+                self.min_board_suited,
+                self.max_board_suited,
+            )
+            && in_range(ft.others_with_str8.len(), self.min_others_with_str8, None))
+    }
+
+    fn validate(&self, rule_name: &str) -> Result<(), PokerError> {
+        check_range(rule_name, "players", self.min_players, self.max_players)?;
+        check_range(
+            rule_name,
+            "rank_family",
+            self.min_rank_family,
+            self.max_rank_family,
+        )?;
+        check_range(rule_name, "hi_card", self.min_hi_card, self.max_hi_card)?;
+        check_range(
+            rule_name,
+            "hi_pair_above",
+            self.hi_pair_min_above,
+            self.hi_pair_max_above,
+        )?;
+        check_range(
+            rule_name,
+            "lo_pair_above",
+            self.lo_pair_min_above,
+            self.lo_pair_max_above,
+        )?;
+        check_range(rule_name, "lo_card", self.min_lo_card, self.max_lo_card)?;
+        check_range(
+            rule_name,
+            "made_flush_card",
+            self.min_made_flush_card,
+            self.max_made_flush_card,
+        )?;
+        check_range(
+            rule_name,
+            "board_suited",
+            self.min_board_suited,
+            self.max_board_suited,
+        )?;
+
+        for num_players in [self.min_players, self.max_players].iter().flatten() {
+            if !(2..=10).contains(num_players) {
+                return Err(PokerError::from_string(format!(
+                    "Rule {}: number of players {} must be between 2 and 10",
+                    rule_name, num_players
+                )));
+            }
+        }
+        if let Some(streets) = self.streets.as_ref() {
+            if streets.is_empty() || streets.contains(&Round::Preflop) {
+                return Err(PokerError::from_string(format!(
+                    "Rule {}: streets must be some of Flop, Turn and River",
+                    rule_name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LikesHandRules {
+    //Parses and validates
+    pub fn from_json(json: &str) -> Result<Self, PokerError> {
+        let rules: LikesHandRules = serde_json::from_str(json)
+            .map_err(|e| PokerError::from_string(format!("Invalid likes hand rules: {}", e)))?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> Result<Self, PokerError> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            PokerError::from_string(format!("Unable to read {}: {}", path.display(), e))
+        })?;
+        Self::from_json(&json)
+    }
+
+    //The hard coded likes_hand as rules
+    pub fn default_rules() -> Self {
+        Self::from_json(include_str!("likes_hand_rules.json")).unwrap()
+    }
+
+    fn validate(&self) -> Result<(), PokerError> {
+        if self.rules.is_empty() {
+            return Err(PokerError::from_str("No likes hand rules"));
+        }
+
+        let mut names = HashSet::new();
+        for rule in self.rules.iter() {
+            if rule.name.is_empty() {
+                return Err(PokerError::from_str("Rule without a name"));
+            }
+            if !names.insert(rule.name.as_str()) {
+                return Err(PokerError::from_string(format!(
+                    "Rule {} is defined twice",
+                    rule.name
+                )));
+            }
+            if rule.raise_to.is_some() && rule.cap_at.is_some() {
+                return Err(PokerError::from_string(format!(
+                    "Rule {} can't have both raise_to and cap_at",
+                    rule.name
+                )));
+            }
+            if rule.raise_to.is_none() && rule.cap_at.is_none() && !rule.stop {
+                return Err(PokerError::from_string(format!(
+                    "Rule {} needs one of raise_to, cap_at or stop",
+                    rule.name
+                )));
+            }
+            if rule.stop && rule.group.is_none() {
+                return Err(PokerError::from_string(format!(
+                    "Rule {} needs a group to stop",
+                    rule.name
+                )));
+            }
+            rule.when.validate(&rule.name)?;
+        }
+        Ok(())
+    }
+
+    /*
+    Same parameters and response as likes_hand.
+
+    Each rule that fires is reported by name, in likes_hand_comments when it raises the level
+    and in not_like_hand_comments when it caps it or only stops
+    */
+    pub fn likes_hand(
+        &self,
+        prc: &PartialRankContainer,
+        ft: &BoardTexture,
+        rank_family: RankEnum,
+        board: &Board,
+        hc: &HoleCards,
+        num_in_pot: u8,
+    ) -> Result<LikesHandResponse, PokerError> {
+        let input = RuleInput {
+            prc,
+            ft,
+            rank_family,
+            board,
+            hc,
+            num_in_pot,
+        };
+
+        let mut likes_hand = LikesHandLevel::None;
+        let mut likes_hand_comments: Vec<String> = Vec::new();
+        let mut not_like_hand_comments: Vec<String> = Vec::new();
+
+        let mut stopped_groups: HashSet<&str> = HashSet::new();
+
+        for rule in self.rules.iter() {
+            if rule
+                .group
+                .as_deref()
+                .is_some_and(|g| stopped_groups.contains(g))
+            {
+                continue;
+            }
+            if !rule.when.matches(&input)? {
+                continue;
+            }
+            if rule.stop {
+                //validate checked there is a group
+                stopped_groups.extend(rule.group.as_deref());
+            }
+
+            let comment = match rule.comment.as_ref() {
+                Some(c) => format!("Rule {}: {}", rule.name, c),
+                None => format!("Rule {}", rule.name),
+            };
+
+            if let Some(level) = rule.raise_to {
+                likes_hand = max(likes_hand, level);
+                likes_hand_comments.push(comment);
+            } else if let Some(level) = rule.cap_at {
+                likes_hand = min(likes_hand, level);
+                not_like_hand_comments.push(comment);
+            } else {
+                not_like_hand_comments.push(comment);
+            }
+        }
+
+        Ok(LikesHandResponse {
+            likes_hand,
+            likes_hand_comments,
+            not_like_hand_comments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calc_board_texture, likes_hands::likes_hand, partial_rank_cards,
+        pre_calc::fast_eval::fast_hand_eval, pre_calc::perfect_hash::load_boomperfect_hash, Deck,
+    };
+
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn compare(rules: &LikesHandRules, hc: &str, board: &str, num_in_pot: u8) -> LikesHandResponse {
+        let hc: HoleCards = hc.parse().unwrap();
+        let board: Board = board.parse().unwrap();
+        let prc = partial_rank_cards(&hc, board.as_slice_card());
+        let ft = calc_board_texture(board.as_slice_card());
+        let rank_family = fast_hand_eval(
+            board.get_iter().chain(hc.get_iter()),
+            &load_boomperfect_hash(),
+        )
+        .get_rank_enum();
+
+        let from_rules = rules
+            .likes_hand(&prc, &ft, rank_family, &board, &hc, num_in_pot)
+            .unwrap();
+        let hard_coded = likes_hand(&prc, &ft, rank_family, &board, &hc, num_in_pot).unwrap();
+        assert_eq!(
+            from_rules.likes_hand, hard_coded.likes_hand,
+            "{} {} {:?} vs {:?}",
+            hc, board, from_rules, hard_coded
+        );
+        from_rules
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = LikesHandRules::default_rules();
+
+        let r = compare(&rules, "7c 7d", "7h 2s Kd", 3);
+        assert_eq!(r.likes_hand, LikesHandLevel::AllIn);
+        assert!(r
+            .likes_hand_comments
+            .iter()
+            .any(|c| c.starts_with("Rule made_set")));
+
+        let r = compare(&rules, "Kc Jd", "Kh 8s 2d", 3);
+        assert_eq!(r.likes_hand, LikesHandLevel::LargeBet);
+        assert!(r
+            .likes_hand_comments
+            .iter()
+            .any(|c| c.starts_with("Rule top_pair")));
+
+        compare(&rules, "Ah 5h", "3h 9d Th", 3);
+        compare(&rules, "9c 8d", "Tc 7h 2s", 2);
+        compare(&rules, "Qc Qd", "Jh 8s 2d", 4);
+        compare(&rules, "4c 3d", "Ah Ks 2d", 4);
+
+        //Top pair on a 3 flush board is capped
+        let r = compare(&rules, "Kc Jd", "Kh 8h 2h", 3);
+        assert_eq!(r.likes_hand, LikesHandLevel::LargeBet);
+        assert!(r
+            .not_like_hand_comments
+            .iter()
+            .any(|c| c.starts_with("Rule worried_about_flush")));
+
+        //2 good overcards stop the other hi card rules, like the else if in likes_hand
+        let r = compare(&rules, "As Qh", "9d 9h 7s 4h 9s", 3);
+        assert_eq!(r.likes_hand, LikesHandLevel::CallSmallBet);
+        assert!(!r
+            .likes_hand_comments
+            .iter()
+            .any(|c| c.starts_with("Rule ace_overcard_trips_on_board")));
+
+        //Straight draws don't count with 4 of a suit on the board multiway
+        let r = compare(&rules, "6h 4d", "Qs 5s 8s 3s", 4);
+        assert_eq!(r.likes_hand, LikesHandLevel::None);
+        assert!(r
+            .not_like_hand_comments
+            .iter()
+            .any(|c| c.starts_with("Rule no_straight_draws_with_4_suited_multiway")));
+    }
+
+    #[test]
+    fn test_default_rules_match_likes_hand() {
+        let rules = LikesHandRules::default_rules();
+        let hash_func = load_boomperfect_hash();
+        let mut deck = Deck::new_with_seed(1);
+
+        for i in 0..10_000 {
+            deck.reset();
+            let hc = HoleCards::new(
+                deck.get_unused_card().unwrap(),
+                deck.get_unused_card().unwrap(),
+            )
+            .unwrap();
+            let board_cards = (0..3 + i % 3)
+                .map(|_| deck.get_unused_card().unwrap())
+                .collect_vec();
+            let board = Board::new_from_cards(&board_cards);
+            let num_in_pot = 2 + (i % 4) as u8;
+
+            let prc = partial_rank_cards(&hc, board.as_slice_card());
+            let ft = calc_board_texture(board.as_slice_card());
+            let rank_family =
+                fast_hand_eval(board.get_iter().chain(hc.get_iter()), &hash_func).get_rank_enum();
+
+            let from_rules = rules
+                .likes_hand(&prc, &ft, rank_family, &board, &hc, num_in_pot)
+                .unwrap();
+            let hard_coded = likes_hand(&prc, &ft, rank_family, &board, &hc, num_in_pot).unwrap();
+            assert_eq!(
+                from_rules.likes_hand, hard_coded.likes_hand,
+                "{} {} {} players {:?} vs {:?}",
+                hc, board, num_in_pot, from_rules, hard_coded
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let parse = |json: &str| LikesHandRules::from_json(json);
+
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn", "when": {"made_set": true}}]}"#
+        )
+        .is_ok());
+
+        //Typo in a condition
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn", "when": {"made_sett": true}}]}"#
+        )
+        .is_err());
+        //Unknown level
+        assert!(parse(r#"{"rules": [{"name": "a", "raise_to": "Huge"}]}"#).is_err());
+        //Needs one effect
+        assert!(parse(r#"{"rules": [{"name": "a", "when": {"made_set": true}}]}"#).is_err());
+        assert!(
+            parse(r#"{"rules": [{"name": "a", "raise_to": "AllIn", "cap_at": "None"}]}"#).is_err()
+        );
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn"}, {"name": "a", "cap_at": "None"}]}"#
+        )
+        .is_err());
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn", "when": {"min_players": 4, "max_players": 3}}]}"#
+        )
+        .is_err());
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn", "when": {"max_players": 11}}]}"#
+        )
+        .is_err());
+        assert!(parse(
+            r#"{"rules": [{"name": "a", "raise_to": "AllIn", "when": {"streets": ["Preflop"]}}]}"#
+        )
+        .is_err());
+        assert!(parse(r#"{"rules": []}"#).is_err());
+        //stop needs a group, but no level
+        assert!(parse(r#"{"rules": [{"name": "a", "stop": true}]}"#).is_err());
+        assert!(parse(r#"{"rules": [{"name": "a", "group": "g", "stop": true}]}"#).is_ok());
+    }
+}
@@ -4,13 +4,14 @@ use std::{
     mem,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    pre_calc::rank::{Rank, RankEnum},
-    Board, BoardTexture, CardValue, FlushDrawType, HoleCards, MadeWith, PartialRankContainer,
-    PokerError, Round, StraightDrawType,
+    pre_calc::rank::RankEnum, Board, BoardTexture, CardValue, FlushDrawType, HoleCards, MadeWith,
+    PartialRankContainer, PokerError, Round, StraightDrawType,
 };
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum LikesHandLevel {
     None = 0, //might even fold instead of checking
@@ -54,7 +55,7 @@ pub struct LikesHandResponse {
 pub fn likes_hand(
     prc: &PartialRankContainer,
     ft: &BoardTexture,
-    rank: &Rank,
+    rank_family: RankEnum,
     board: &Board,
     hc: &HoleCards,
     //Idea is to tweak responses based on 2 vs more players
@@ -75,7 +76,7 @@ pub fn likes_hand(
     handle_set_and_two_pair(
         prc,
         ft,
-        rank,
+        rank_family,
         board,
         hc,
         &mut likes_hand,
@@ -97,7 +98,7 @@ pub fn likes_hand(
     handle_pocket_pair(
         prc,
         ft,
-        rank,
+        rank_family,
         board,
         hc,
         &mut likes_hand,
@@ -128,7 +129,7 @@ pub fn likes_hand(
     );
 
     likes_made_flushes_and_straights(
-        rank,
+        rank_family,
         ft,
         prc,
         &mut likes_hand,
@@ -139,7 +140,7 @@ pub fn likes_hand(
 
     worried_about_straights(
         ft,
-        rank,
+        rank_family,
         &mut likes_hand,
         &mut not_like_hand_comments,
         num_in_pot,
@@ -148,7 +149,7 @@ pub fn likes_hand(
     worried_about_flushes(
         ft,
         prc,
-        rank,
+        rank_family,
         &mut likes_hand,
         &mut not_like_hand_comments,
         num_in_pot,
@@ -164,7 +165,7 @@ pub fn likes_hand(
 fn handle_set_and_two_pair(
     prc: &PartialRankContainer,
     ft: &BoardTexture,
-    rank: &Rank,
+    rank_family: RankEnum,
     board: &Board,
     hc: &HoleCards,
     likes_hand: &mut LikesHandLevel,
@@ -196,7 +197,7 @@ fn handle_set_and_two_pair(
             }
         }
     }
-    if !ft.has_quads && rank.get_rank_enum() >= RankEnum::FourOfAKind {
+    if !ft.has_quads && rank_family >= RankEnum::FourOfAKind {
         likes_hand_comments.push(format!("Made Quads or better"));
         *likes_hand = max(*likes_hand, LikesHandLevel::AllIn);
     }
@@ -367,7 +368,7 @@ fn handle_hi_and_lo_pair(
 fn handle_pocket_pair(
     prc: &PartialRankContainer,
     ft: &BoardTexture,
-    rank: &Rank,
+    rank_family: RankEnum,
     _board: &Board,
     hc: &HoleCards,
     likes_hand: &mut LikesHandLevel,
@@ -401,7 +402,7 @@ fn handle_pocket_pair(
         }
     }
 
-    if !ft.has_quads && !ft.has_fh && rank.get_rank_enum() >= RankEnum::FullHouse {
+    if !ft.has_quads && !ft.has_fh && rank_family >= RankEnum::FullHouse {
         likes_hand_comments.push(format!(
             "Pocket Pair FH or better {}",
             hc.get_hi_card().value
@@ -499,12 +500,12 @@ fn likes_draws(
 fn worried_about_flushes(
     ft: &BoardTexture,
     prc: &PartialRankContainer,
-    rank: &Rank,
+    rank_family: RankEnum,
     likes_hand: &mut LikesHandLevel,
     not_like_hand_comments: &mut Vec<String>,
     num_in_pot: u8,
 ) {
-    if rank.get_rank_enum() >= RankEnum::Flush {
+    if rank_family >= RankEnum::Flush {
         return;
     }
 
@@ -541,13 +542,13 @@ fn worried_about_flushes(
 
 fn worried_about_straights(
     ft: &BoardTexture,
-    rank: &Rank,
+    rank_family: RankEnum,
     likes_hand: &mut LikesHandLevel,
     not_like_hand_comments: &mut Vec<String>,
     num_in_pot: u8,
 ) {
     //Maybe needed to worry about better straights?
-    if rank.get_rank_enum() >= RankEnum::Straight {
+    if rank_family >= RankEnum::Straight {
         return;
     }
 
@@ -567,7 +568,7 @@ fn worried_about_straights(
 }
 
 fn likes_made_flushes_and_straights(
-    rank: &Rank,
+    rank_family: RankEnum,
     ft: &BoardTexture,
     prc: &PartialRankContainer,
     likes_hand: &mut LikesHandLevel,
@@ -575,7 +576,7 @@ fn likes_made_flushes_and_straights(
     not_like_hand_comments: &mut Vec<String>,
     _num_in_pot: u8,
 ) {
-    if RankEnum::Straight == rank.get_rank_enum() {
+    if RankEnum::Straight == rank_family {
         if ft.has_straight {
             likes_hand_comments.push(format!("Straight on board"));
         } else {
@@ -587,7 +588,7 @@ fn likes_made_flushes_and_straights(
         }
     }
 
-    if RankEnum::Flush == rank.get_rank_enum() {
+    if RankEnum::Flush == rank_family {
         if let Some(made_flush) = prc.made_flush {
             assert!(ft.same_suited_max_count >= 3);
             if ft.same_suited_max_count >= 4 {
@@ -626,8 +627,8 @@ mod test {
     use log::{debug, info};
 
     use crate::{
-        board_eval_cache_redb::{EvalCacheReDb, ProduceFlopTexture},
-        board_hc_eval_cache_redb::{EvalCacheWithHcReDb, ProducePartialRankCards},
+        board_eval_cache_redb::{EvalCache, ProduceFlopTexture},
+        board_hc_eval_cache_redb::{EvalCacheWithHc, ProducePartialRankCards},
         calc_board_texture, init_test_logger,
         monte_carlo_equity::calc_equity,
         partial_rank_cards,
@@ -664,7 +665,7 @@ mod test {
         let likes_hand_response = likes_hand(
             &prc,
             &board_texture,
-            &rank,
+            rank.get_rank_enum(),
             &board.borrow(),
             &hc,
             num_in_pot,
@@ -931,10 +932,10 @@ mod test {
          */
         init_test_logger();
 
-        let mut partial_rank_db: EvalCacheWithHcReDb<ProducePartialRankCards> =
-            EvalCacheWithHcReDb::new().unwrap();
+        let mut partial_rank_db: EvalCacheWithHc<ProducePartialRankCards> =
+            EvalCacheWithHc::new_in_memory();
 
-        let mut flop_texture_db: EvalCacheReDb<ProduceFlopTexture> = EvalCacheReDb::new().unwrap();
+        let mut flop_texture_db: EvalCache<ProduceFlopTexture> = EvalCache::new_in_memory();
 
         let mut ranges: Vec<BoolRange> = vec![
             //We'll replace this one with the hole cards
@@ -1003,7 +1004,8 @@ mod test {
                                 fast_hand_eval(board.get_iter().chain(hc.get_iter()), &hash_func);
 
                             let likes_hand_res =
-                                likes_hand(&prc, &ft, &rank, &board, &hc, 4).unwrap();
+                                likes_hand(&prc, &ft, rank.get_rank_enum(), &board, &hc, 4)
+                                    .unwrap();
 
                             //Get equity
                             ranges[0].data.fill(false);
@@ -0,0 +1,280 @@
+use std::borrow::Borrow;
+
+use itertools::Itertools;
+
+use crate::{Card, CardValue};
+
+/*
+A low hand, for the low half of hi/lo split pots (Omaha Hi/Lo, Stud Hi/Lo) and lowball games.
+
+Like Rank a higher LowRank is a better hand, so the winner is still the max.
+Only compare low ranks from the same evaluator.
+*/
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct LowRank {
+    pub raw_rank: u32,
+}
+
+//Hand categories, the lower the better for a low hand
+const NO_PAIR: u32 = 0;
+const ONE_PAIR: u32 = 1;
+const TWO_PAIR: u32 = 2;
+const THREE_OF_A_KIND: u32 = 3;
+const STRAIGHT: u32 = 4;
+const FLUSH: u32 = 5;
+const FULL_HOUSE: u32 = 6;
+const FOUR_OF_A_KIND: u32 = 7;
+const STRAIGHT_FLUSH: u32 = 8;
+
+//Values take 4 bits each, most significant first, the category is above them
+const VALUE_BITS: u32 = 4;
+const CATEGORY_SHIFT: u32 = 5 * VALUE_BITS;
+const MAX_BADNESS: u32 = (STRAIGHT_FLUSH + 1) << CATEGORY_SHIFT;
+
+impl LowRank {
+    fn from_badness(badness: u32) -> Self {
+        LowRank {
+            raw_rank: MAX_BADNESS - badness,
+        }
+    }
+
+    fn badness(&self) -> u32 {
+        MAX_BADNESS - self.raw_rank
+    }
+
+    //True for 5 unpaired cards, straights and flushes don't count against an ace to five low
+    pub fn is_no_pair(&self) -> bool {
+        self.badness() >> CATEGORY_SHIFT == NO_PAIR
+    }
+
+    //The highest card of the low, 1 for an ace up to 13 for a king (ace to five)
+    //or 2 for a deuce up to 14 for an ace (deuce to seven)
+    pub fn get_high_value(&self) -> u32 {
+        //+1 so the values read like the cards
+        ((self.badness() >> (4 * VALUE_BITS)) & 0xF) + 1
+    }
+}
+
+/*
+values are 0 for the lowest card of the game
+sorted so the most cards of a value come first, then the highest value,
+ex: 7 7 5 3 2 is a pair of 7s, then 5 3 2
+*/
+fn sort_by_count_then_value(values: &mut [u32; 5]) {
+    let mut counts = [0u8; 13];
+    for v in values.iter() {
+        counts[*v as usize] += 1;
+    }
+    values.sort_by(|a, b| counts[*b as usize].cmp(&counts[*a as usize]).then(b.cmp(a)));
+}
+
+fn pair_category(values: &[u32; 5]) -> u32 {
+    let mut counts = [0u8; 13];
+    for v in values.iter() {
+        counts[*v as usize] += 1;
+    }
+    let max_count = *counts.iter().max().unwrap();
+    let num_pairs = counts.iter().filter(|c| **c == 2).count();
+
+    match (max_count, num_pairs) {
+        (4, _) => FOUR_OF_A_KIND,
+        (3, 1) => FULL_HOUSE,
+        (3, _) => THREE_OF_A_KIND,
+        (2, 2) => TWO_PAIR,
+        (2, _) => ONE_PAIR,
+        _ => NO_PAIR,
+    }
+}
+
+fn values_badness(category: u32, values: &[u32; 5]) -> u32 {
+    values
+        .iter()
+        .fold(category, |acc, v| (acc << VALUE_BITS) | v)
+}
+
+//Ace is the lowest card, straights and flushes don't count
+fn ace_to_five_badness(cards: &[Card]) -> u32 {
+    let mut values = [0u32; 5];
+    for (i, c) in cards.iter().enumerate() {
+        values[i] = if c.value == CardValue::Ace {
+            0
+        } else {
+            c.value as u32 + 1
+        };
+    }
+    sort_by_count_then_value(&mut values);
+
+    values_badness(pair_category(&values), &values)
+}
+
+//Ace is the highest card, straights and flushes count against you, so this is just a high hand reversed
+fn deuce_to_seven_badness(cards: &[Card]) -> u32 {
+    let mut values = [0u32; 5];
+    for (i, c) in cards.iter().enumerate() {
+        values[i] = c.value as u32;
+    }
+    sort_by_count_then_value(&mut values);
+
+    let is_flush = cards.iter().all(|c| c.suit == cards[0].suit);
+    //The ace is only high, so A5432 is not a straight
+    let is_straight = values.iter().tuple_windows().all(|(a, b)| *a == b + 1);
+
+    let category = match (is_straight, is_flush) {
+        (true, true) => STRAIGHT_FLUSH,
+        (true, false) => STRAIGHT,
+        (false, true) => FLUSH,
+        _ => pair_category(&values),
+    };
+
+    values_badness(category, &values)
+}
+
+//The best (lowest badness) 5 cards out of 5 to 7 cards
+fn best_low<I, B, F>(cards: I, badness: F) -> LowRank
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+    F: Fn(&[Card]) -> u32,
+{
+    let cards = cards.map(|c| *c.borrow()).collect_vec();
+    assert!(cards.len() >= 5 && cards.len() <= 7);
+
+    let best_badness = cards
+        .into_iter()
+        .combinations(5)
+        .map(|five_cards| badness(&five_cards))
+        .min()
+        .unwrap();
+
+    LowRank::from_badness(best_badness)
+}
+
+/*
+Ace to five low (razz, the low half of hi/lo), the best hand is A2345.
+
+Straights and flushes are ignored, pairs count against the hand.
+*/
+pub fn ace_to_five_low_eval<I, B>(cards: I) -> LowRank
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+{
+    best_low(cards, ace_to_five_badness)
+}
+
+/*
+The low half of a hi/lo split pot, an ace to five low with 5 unpaired cards 8 or lower.
+
+None if the hand does not qualify
+*/
+pub fn eight_or_better_low_eval<I, B>(cards: I) -> Option<LowRank>
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+{
+    let low_rank = ace_to_five_low_eval(cards);
+
+    if low_rank.is_no_pair() && low_rank.get_high_value() <= 8 {
+        Some(low_rank)
+    } else {
+        None
+    }
+}
+
+/*
+Deuce to seven low (Kansas City lowball), the best hand is 75432 of at least 2 suits.
+
+Aces are high, straights and flushes count against the hand.
+*/
+pub fn deuce_to_seven_low_eval<I, B>(cards: I) -> LowRank
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+{
+    best_low(cards, deuce_to_seven_badness)
+}
+
+/*
+Omaha Hi/Lo, the low also needs exactly 2 hole cards and 3 board cards, see fast_omaha_eval.
+
+None if no combination qualifies for an 8 or better low.
+*/
+pub fn omaha_eight_or_better_low_eval(hole_cards: &[Card], board: &[Card]) -> Option<LowRank> {
+    hole_cards
+        .iter()
+        .combinations(2)
+        .cartesian_product(board.iter().combinations(3).collect_vec())
+        .filter_map(|(hole_pair, board_triple)| {
+            eight_or_better_low_eval(hole_pair.into_iter().chain(board_triple))
+        })
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Board;
+
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Board::try_from(s).unwrap().as_slice_card().to_vec()
+    }
+
+    #[test]
+    fn test_ace_to_five_low() {
+        let low = |s: &str| ace_to_five_low_eval(cards(s).iter());
+
+        let wheel = low("Ac 2c 3c 4c 5c");
+        assert!(wheel > low("Ac 2d 3h 4s 6c"));
+        assert!(low("Ac 2d 3h 4s 6c") > low("Ac 2d 3h 5s 6c"));
+        assert!(low("8c 7d 6h 5s Ac 2c") > low("8c 7d 6h 5s 4c"));
+        assert!(low("8c 7d 6h 5s 4c") > low("9c 2d 3h 4s 5c"));
+        assert_eq!(wheel, low("Ac 2d 3h 4s 5c Kd Kh"));
+
+        //Any pair is worse than no pair
+        assert!(low("Kc Qd Jh Ts 9c") > low("Ac Ad 2h 3s 4c"));
+        assert!(low("Ac Ad 2h 3s 4c") > low("2c 2d 3h 3s 4c"));
+        assert!(low("2c 2d 2h 3s 3c") < low("2c 2d 3h 3s 4c"));
+
+        assert_eq!(
+            Some(wheel),
+            eight_or_better_low_eval(cards("Ac 2c 3c 4c 5c").iter())
+        );
+        assert!(eight_or_better_low_eval(cards("Ac 2d 3h 4s 8c").iter()).is_some());
+        assert!(eight_or_better_low_eval(cards("Ac 2d 3h 4s 9c").iter()).is_none());
+        assert!(eight_or_better_low_eval(cards("Ac 2d 3h 4s 4c Kd 8d").iter()).is_some());
+        assert!(eight_or_better_low_eval(cards("Ac 2d 3h 4s 4c Kd 9d").iter()).is_none());
+    }
+
+    #[test]
+    fn test_deuce_to_seven_low() {
+        let low = |s: &str| deuce_to_seven_low_eval(cards(s).iter());
+
+        let best = low("7c 5d 4h 3s 2c");
+        assert!(best > low("7c 6d 4h 3s 2c"));
+        assert!(best > low("8c 5d 4h 3s 2c"));
+        //Flushes and straights are bad
+        assert!(low("8c 6d 4h 3s 2c") > low("7c 5c 4c 3c 2c"));
+        assert!(low("Kc Qd Jh Ts 8c") > low("6c 5d 4h 3s 2c"));
+        //The ace is high
+        assert!(low("Kc Qd Jh Ts 8c") > low("Ac 5d 4h 3s 2c"));
+        assert!(low("Ac 5d 4h 3s 2c") > low("2c 2d 4h 3s 5c"));
+        assert_eq!(best, low("7c 5d 4h 3s 2c Kd Kh"));
+    }
+
+    #[test]
+    fn test_omaha_low() {
+        let omaha_low = |hole_cards: &str, board: &str| {
+            omaha_eight_or_better_low_eval(&cards(hole_cards), &cards(board))
+        };
+
+        //Needs 2 low hole cards
+        assert!(omaha_low("Ac Kd Qh Js", "2c 3d 4h 5s 6c").is_none());
+        assert_eq!(
+            omaha_low("Ac 2d Qh Js", "3c 4d 5h Ks Kc"),
+            eight_or_better_low_eval(cards("Ac 2d 3c 4d 5h").iter())
+        );
+        //Only 3 board cards, so 2 + 3 + 4 from the hand is not counted
+        assert!(omaha_low("Ac 2d 3h 4s", "Kc Qd 5h 6s 9c").is_none());
+    }
+}
@@ -1,11 +1,19 @@
 mod board_texture;
+mod hand_strength;
+mod low_rank;
+mod outs;
 mod partial_rank;
 mod rank;
+mod relative_strength;
 mod value_set_iterator;
 
 pub use board_texture::*;
+pub use hand_strength::*;
+pub use low_rank::*;
+pub use outs::*;
 pub use partial_rank::*;
 pub use rank::*;
+pub use relative_strength::*;
 pub use value_set_iterator::*;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -14,20 +22,39 @@ mod combinatorial_index;
 #[cfg(not(target_arch = "wasm32"))]
 pub use combinatorial_index::*;
 
-#[cfg(not(target_arch = "wasm32"))]
+mod evaluator;
+pub use evaluator::*;
+
+mod eval_cache_backend;
+pub use eval_cache_backend::*;
+
 pub mod board_eval_cache_redb;
 
-#[cfg(not(target_arch = "wasm32"))]
 pub mod board_hc_eval_cache_redb;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod batched_eval_cache_redb;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use batched_eval_cache_redb::*;
+
+mod shared_eval_cache;
+pub use shared_eval_cache::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod eval_cache_maintenance;
+
 pub mod narrow_range;
 
 pub mod likes_hands;
 
+pub mod likes_hand_rules;
+
 pub mod monte_carlo_equity;
 
+pub mod equity_distribution;
+
 #[allow(dead_code)]
-#[cfg(not(target_arch = "wasm32"))]
 mod kev;
 
 //#[cfg(not(target_arch = "wasm32"))]
@@ -6,12 +6,22 @@ use std::{cmp::Ordering, iter::once};
 
 use itertools::Itertools;
 use log::trace;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{derive_seed, split_iterations};
 use crate::{
+    eight_or_better_low_eval, now_ms, omaha_eight_or_better_low_eval,
     pre_calc::{
-        fast_eval::fast_hand_eval, perfect_hash::load_boomperfect_hash, rank::Rank, NUMBER_OF_SUITS,
+        fast_eval::{fast_hand_eval, fast_omaha_eval},
+        perfect_hash::load_boomperfect_hash,
+        rank::Rank,
+        NUMBER_OF_SUITS,
     },
-    Board, BoolRange, Card, Deck, HoleCards, PokerError, Suit, ALL_CARDS, ALL_HOLE_CARDS,
+    set_used_card, std_error_of_mean, Board, BoolRange, Card, CardUsedType, Deck, DeckVariant,
+    FastEvaluator, HandEvaluator, HandValue, HoleCards, HoleCardsSampler, LowRank, OmahaRange,
+    PokerError, StoppingRule, Suit, WeightedRange, ALL_CARDS, ALL_HOLE_CARDS, DEFAULT_SEED,
+    Z_SCORE_95,
 };
 
 /*
@@ -29,104 +39,976 @@ pub fn calc_equity(
     ranges: &Vec<BoolRange>,
     num_simulations: usize,
 ) -> Result<Vec<f64>, PokerError> {
-    let hash_func = load_boomperfect_hash();
+    calc_equity_with_rng(
+        board,
+        ranges,
+        num_simulations,
+        StdRng::seed_from_u64(DEFAULT_SEED),
+    )
+}
 
-    let mut deck = Deck::new();
+//Same as calc_equity, the rng deals the hole cards and runouts
+pub fn calc_equity_with_rng(
+    board: &Board,
+    ranges: &[BoolRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<Vec<f64>, PokerError> {
+    let weighted_ranges = ranges.iter().map(WeightedRange::from).collect_vec();
 
-    let mut out = vec![0.0; ranges.len()];
+    calc_equity_weighted_with_rng(board, &weighted_ranges, num_simulations, rng)
+}
 
-    //Store the player index because we want to pick the most restrictive hole cards first
-    let possible_hole_cards: Vec<(usize, Vec<HoleCards>)> = {
-        let mut pv = ranges
-            .iter()
+/*
+Same as calc_equity, but hole cards are sampled in proportion to their weight in the range
+*/
+pub fn calc_equity_weighted(
+    board: &Board,
+    //The player ranges we are calculating equity for
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+) -> Result<Vec<f64>, PokerError> {
+    calc_equity_weighted_with_rng(
+        board,
+        ranges,
+        num_simulations,
+        StdRng::seed_from_u64(DEFAULT_SEED),
+    )
+}
+
+pub fn calc_equity_weighted_with_rng(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<Vec<f64>, PokerError> {
+    Ok(calc_equity_with_method(board, ranges, num_simulations, rng)?.equities)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquityMethod {
+    MonteCarlo,
+    Enumeration,
+}
+
+#[derive(Debug, Clone)]
+pub struct EquityResult {
+    pub equities: Vec<f64>,
+    //Standard error of each equity, 0 when enumerated
+    pub std_errors: Vec<f64>,
+    pub method: EquityMethod,
+    //Number of simulations, or number of (hole cards, runout) combinations enumerated
+    pub num_evaluations: usize,
+}
+
+impl EquityResult {
+    //95% confidence interval of the player's equity
+    pub fn confidence_interval(&self, player_index: usize) -> (f64, f64) {
+        let margin = Z_SCORE_95 * self.std_errors[player_index];
+        (
+            (self.equities[player_index] - margin).max(0.0),
+            (self.equities[player_index] + margin).min(1.0),
+        )
+    }
+}
+
+/*
+Picks exact enumeration when the estimated number of evaluations is no more than
+the simulations we would run anyway (typically on the turn / river or with narrow ranges),
+otherwise Monte Carlo
+
+rng is only used by Monte Carlo, pass the same seed to get the same results
+*/
+pub fn calc_equity_with_method(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<EquityResult, PokerError> {
+    calc_equity_for_variant(board, ranges, num_simulations, rng, DeckVariant::Standard)
+}
+
+/*
+Same as calc_equity_with_method, but the board and hole cards come from the given deck,
+ex: the 36 card short deck where A6789 is a straight and a flush beats a full house
+*/
+pub fn calc_equity_for_variant(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+    deck_variant: DeckVariant,
+) -> Result<EquityResult, PokerError> {
+    calc_equity_with_variant_evaluator(
+        board,
+        ranges,
+        num_simulations,
+        rng,
+        deck_variant,
+        &FastEvaluator::new_for_variant(deck_variant),
+    )
+}
+
+/*
+Same as calc_equity_with_method with any hand evaluator, to check the results don't depend on the backend.
+
+calc_equity and the others use the FastEvaluator
+*/
+pub fn calc_equity_with_evaluator(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+    evaluator: &dyn HandEvaluator,
+) -> Result<EquityResult, PokerError> {
+    calc_equity_with_variant_evaluator(
+        board,
+        ranges,
+        num_simulations,
+        rng,
+        DeckVariant::Standard,
+        evaluator,
+    )
+}
+
+//The evaluator must rank hands of the deck variant
+fn calc_equity_with_variant_evaluator(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+    deck_variant: DeckVariant,
+    evaluator: &dyn HandEvaluator,
+) -> Result<EquityResult, PokerError> {
+    if let Some(c) = board
+        .as_slice_card()
+        .iter()
+        .find(|c| !deck_variant.contains(**c))
+    {
+        return Err(PokerError::from_string(format!(
+            "Board card {} is not in the deck",
+            c
+        )));
+    }
+
+    let work_size = estimate_variant_work_size(board, ranges, deck_variant)?;
+
+    trace!(
+        "Enumeration work size {} vs {} simulations",
+        work_size,
+        num_simulations
+    );
+
+    if work_size <= num_simulations as f64 {
+        calc_variant_equity_exact(board, ranges, deck_variant, evaluator)
+    } else {
+        calc_equity_monte_carlo(board, ranges, num_simulations, rng, deck_variant, evaluator)
+    }
+}
+
+/*
+Same as calc_equity_with_method, but Monte Carlo simulations are split across num_threads threads,
+each with its own rng seeded from seed.  The results are the same for the same seed and number of threads.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+pub fn calc_equity_parallel(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    seed: u64,
+    num_threads: usize,
+) -> Result<EquityResult, PokerError> {
+    if estimate_enumeration_work_size(board, ranges)? <= num_simulations as f64 {
+        return calc_equity_exact(board, ranges);
+    }
+
+    let evaluator = FastEvaluator::default();
+    let thread_results: Vec<Result<EquityAccumulator, PokerError>> = std::thread::scope(|s| {
+        let handles = split_iterations(num_simulations, num_threads)
+            .into_iter()
             .enumerate()
-            //These are irrespective of used cards, we try later on which hole cards are still valid
-            .map(|(player_index, r)| (player_index, r.get_all_enabled_holecards()))
+            .filter(|(_, thread_simulations)| *thread_simulations > 0)
+            .map(|(thread_index, thread_simulations)| {
+                let rng = StdRng::seed_from_u64(derive_seed(seed, thread_index as u64));
+                let evaluator = &evaluator;
+                s.spawn(move || {
+                    let mut monte_carlo = MonteCarloEquity::new(
+                        board,
+                        ranges,
+                        rng,
+                        DeckVariant::Standard,
+                        evaluator,
+                    )?;
+                    monte_carlo.run(thread_simulations)?;
+                    Ok(monte_carlo.accumulator)
+                })
+            })
             .collect_vec();
 
-        pv.sort_by(|a, b| a.1.len().cmp(&b.1.len()));
-        pv
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .unwrap_or_else(|_| Err(PokerError::from_str("Equity thread panicked")))
+            })
+            .collect_vec()
+    });
+
+    let mut accumulator = EquityAccumulator::new(ranges.len());
+    for thread_accumulator in thread_results {
+        accumulator.merge(&thread_accumulator?);
+    }
+
+    Ok(accumulator.to_result())
+}
+
+/*
+Instead of a fixed number of simulations, runs batches until the stopping rule is met,
+i.e. the standard error of every player's equity is small enough or the time budget is used up.
+
+Enumerates instead if that takes no more than the max iterations of the stopping rule
+*/
+pub fn calc_equity_until_converged(
+    board: &Board,
+    ranges: &[WeightedRange],
+    stopping_rule: &StoppingRule,
+    rng: StdRng,
+) -> Result<EquityResult, PokerError> {
+    if estimate_enumeration_work_size(board, ranges)? <= stopping_rule.max_iterations as f64 {
+        return calc_equity_exact(board, ranges);
+    }
+
+    let start_ms = now_ms();
+    let evaluator = FastEvaluator::default();
+    let mut monte_carlo =
+        MonteCarloEquity::new(board, ranges, rng, DeckVariant::Standard, &evaluator)?;
+
+    loop {
+        let batch_size = stopping_rule.next_batch_size(monte_carlo.accumulator.num_simulations);
+        monte_carlo.run(batch_size)?;
+
+        if stopping_rule.is_done(
+            monte_carlo.accumulator.num_simulations,
+            monte_carlo.accumulator.max_std_error(),
+            start_ms,
+        ) {
+            break;
+        }
+    }
+
+    trace!(
+        "Converged after {} simulations",
+        monte_carlo.accumulator.num_simulations
+    );
+
+    Ok(monte_carlo.accumulator.to_result())
+}
+
+/*
+An upper bound on the number of evaluations enumeration needs,
+the product of each player's possible hole cards (ignoring conflicts between players) times the number of runouts
+*/
+pub fn estimate_enumeration_work_size(
+    board: &Board,
+    ranges: &[WeightedRange],
+) -> Result<f64, PokerError> {
+    estimate_variant_work_size(board, ranges, DeckVariant::Standard)
+}
+
+fn estimate_variant_work_size(
+    board: &Board,
+    ranges: &[WeightedRange],
+    deck_variant: DeckVariant,
+) -> Result<f64, PokerError> {
+    let mut work_size = 1.0;
+
+    for r in ranges.iter() {
+        let num_hole_cards = ALL_HOLE_CARDS
+            .iter()
+            .zip(r.data.iter())
+            .filter(|(hc, w)| {
+                **w > 0.0 && !board.intersects_holecards(hc) && is_in_deck(hc, deck_variant)
+            })
+            .count();
+        work_size *= num_hole_cards as f64;
+    }
+
+    let num_cards_to_deal = 5usize.checked_sub(board.get_num_cards()).ok_or_else(|| {
+        PokerError::from_string(format!(
+            "Board has {} cards, at most 5",
+            board.get_num_cards()
+        ))
+    })?;
+    let num_unknown_cards = deck_variant
+        .num_cards()
+        .checked_sub(board.get_num_cards() + 2 * ranges.len())
+        .filter(|n| *n >= num_cards_to_deal)
+        .ok_or_else(|| {
+            PokerError::from_string(format!(
+                "Not enough cards in the deck for {} players and a {} card board",
+                ranges.len(),
+                board.get_num_cards()
+            ))
+        })?;
+    for i in 0..num_cards_to_deal {
+        work_size *= (num_unknown_cards - i) as f64 / (i + 1) as f64;
+    }
+
+    Ok(work_size)
+}
+
+/*
+Exact equity, enumerates every combination of hole cards that doesn't share cards, weighted by the product of the
+range weights, and every runout of the remaining board cards
+*/
+pub fn calc_equity_exact(
+    board: &Board,
+    ranges: &[WeightedRange],
+) -> Result<EquityResult, PokerError> {
+    calc_variant_equity_exact(
+        board,
+        ranges,
+        DeckVariant::Standard,
+        &FastEvaluator::default(),
+    )
+}
+
+fn is_in_deck(hole_cards: &HoleCards, deck_variant: DeckVariant) -> bool {
+    deck_variant.contains(hole_cards.get_lo_card())
+}
+
+fn calc_variant_equity_exact(
+    board: &Board,
+    ranges: &[WeightedRange],
+    deck_variant: DeckVariant,
+    evaluator: &dyn HandEvaluator,
+) -> Result<EquityResult, PokerError> {
+    //Cards not in the deck are never dealt
+    let mut used_cards = CardUsedType::default();
+    for c in board
+        .as_slice_card()
+        .iter()
+        .chain(deck_variant.removed_cards().iter())
+    {
+        used_cards.set((*c).into(), true);
+    }
+
+    //Enumerate the most restrictive range first
+    let mut player_combos: Vec<(usize, Vec<(HoleCards, f64)>)> = ranges
+        .iter()
+        .enumerate()
+        .map(|(player_index, r)| {
+            let combos = ALL_HOLE_CARDS
+                .iter()
+                .zip(r.data.iter())
+                .filter(|(hc, w)| {
+                    **w > 0.0 && !board.intersects_holecards(hc) && is_in_deck(hc, deck_variant)
+                })
+                .map(|(hc, w)| (*hc, *w as f64))
+                .collect_vec();
+            (player_index, combos)
+        })
+        .collect_vec();
+    player_combos.sort_by_key(|(_, combos)| combos.len());
+
+    let mut board_cards = board.as_slice_card().to_vec();
+    board_cards.resize(5, ALL_CARDS[0]);
+
+    let mut enumerator = Enumerator {
+        evaluator,
+        player_combos: &player_combos,
+        num_board_cards: board.get_num_cards(),
+        used_cards,
+        board_cards,
+        player_hole_cards: vec![ALL_HOLE_CARDS[0]; ranges.len()],
+        player_values: vec![HandValue::default(); ranges.len()],
+        out: vec![0.0; ranges.len()],
+        total_weight: 0.0,
+        num_evaluations: 0,
     };
-    //trace!("Get possible hole cards done ");
 
-    let mut player_hole_cards = vec![ALL_HOLE_CARDS[0]; ranges.len()];
+    enumerator.enumerate_hole_cards(0, 1.0)?;
 
-    let mut player_ranks: Vec<Rank> = vec![Rank::lowest_rank(); ranges.len()];
+    if enumerator.total_weight <= 0.0 {
+        return Err(PokerError::from_str(
+            "No possible hole cards for the players given the board",
+        ));
+    }
 
-    let mut board_cards = board.as_slice_card().iter().map(|c| *c).collect_vec();
+    let total_weight = enumerator.total_weight;
 
-    while board_cards.len() < 5 {
-        //just a place holder
-        board_cards.push(ALL_CARDS[0]);
+    Ok(EquityResult {
+        equities: enumerator
+            .out
+            .iter()
+            .map(|e| e / total_weight)
+            .collect_vec(),
+        std_errors: vec![0.0; ranges.len()],
+        method: EquityMethod::Enumeration,
+        num_evaluations: enumerator.num_evaluations,
+    })
+}
+
+struct Enumerator<'a> {
+    evaluator: &'a dyn HandEvaluator,
+    player_combos: &'a [(usize, Vec<(HoleCards, f64)>)],
+    num_board_cards: usize,
+    used_cards: CardUsedType,
+    board_cards: Vec<Card>,
+    player_hole_cards: Vec<HoleCards>,
+    player_values: Vec<HandValue>,
+    out: Vec<f64>,
+    total_weight: f64,
+    num_evaluations: usize,
+}
+
+impl<'a> Enumerator<'a> {
+    fn enumerate_hole_cards(&mut self, depth: usize, weight: f64) -> Result<(), PokerError> {
+        if depth == self.player_combos.len() {
+            self.enumerate_runouts(weight);
+            return Ok(());
+        }
+
+        let (player_index, combos) = &self.player_combos[depth];
+
+        for (hole_cards, hc_weight) in combos.iter() {
+            let hi: usize = hole_cards.get_hi_card().into();
+            let lo: usize = hole_cards.get_lo_card().into();
+            if self.used_cards[hi] || self.used_cards[lo] {
+                continue;
+            }
+            self.used_cards.set(hi, true);
+            self.used_cards.set(lo, true);
+            self.player_hole_cards[*player_index] = *hole_cards;
+
+            self.enumerate_hole_cards(depth + 1, weight * hc_weight)?;
+
+            self.used_cards.set(hi, false);
+            self.used_cards.set(lo, false);
+        }
+
+        Ok(())
     }
 
-    for it in 0..num_simulations {
-        if it % 10_000 == 0 && it > 0 {
-            trace!("it {}", it);
+    //Every runout is equally likely once the hole cards are fixed
+    fn enumerate_runouts(&mut self, weight: f64) {
+        let available_cards = ALL_CARDS
+            .iter()
+            .filter(|c| !self.used_cards[Into::<usize>::into(**c)])
+            .cloned()
+            .collect_vec();
+
+        for runout in available_cards
+            .into_iter()
+            .combinations(5 - self.num_board_cards)
+        {
+            for (board_index, card) in runout.into_iter().enumerate() {
+                self.board_cards[self.num_board_cards + board_index] = card;
+            }
+
+            add_showdown_result(
+                &self.board_cards,
+                &self.player_hole_cards,
+                self.evaluator,
+                &mut self.player_values,
+                &mut self.out,
+                weight,
+            );
+
+            self.total_weight += weight;
+            self.num_evaluations += 1;
         }
-        deck.reset();
+    }
+}
 
-        for c in board.as_slice_card().iter() {
-            deck.set_used_card(*c);
+//Adds each player's share of the pot, a win is weight, a 2 way tie is weight / 2, etc.
+fn add_showdown_result(
+    board_cards: &[Card],
+    player_hole_cards: &[HoleCards],
+    evaluator: &dyn HandEvaluator,
+    player_values: &mut [HandValue],
+    out: &mut [f64],
+    weight: f64,
+) {
+    let num_board_cards = board_cards.len();
+    let mut cards = [ALL_CARDS[0]; 7];
+    cards[..num_board_cards].copy_from_slice(board_cards);
+
+    for (player_index, hole_cards) in player_hole_cards.iter().enumerate() {
+        cards[num_board_cards] = hole_cards.get_hi_card();
+        cards[num_board_cards + 1] = hole_cards.get_lo_card();
+
+        player_values[player_index] = evaluator.evaluate(&cards[..num_board_cards + 2]);
+    }
+
+    let max_strength = player_values.iter().map(|v| v.strength).max().unwrap();
+    let count_at_max = player_values
+        .iter()
+        .filter(|v| v.strength == max_strength)
+        .count();
+
+    for (player_index, value) in player_values.iter().enumerate() {
+        if value.strength == max_strength {
+            out[player_index] += weight / count_at_max as f64;
         }
+    }
+}
 
-        //We need to deal hole cards to each player
-        for (player_index, player_possible_hold_cards) in possible_hole_cards.iter() {
-            // trace!(
-            //     "Choosing range for {} with {} possibilities",
-            //     p,
-            //     possible_hole_cards[p].len()
-            // );
+fn calc_equity_monte_carlo(
+    board: &Board,
+    ranges: &[WeightedRange],
+    num_simulations: usize,
+    rng: StdRng,
+    deck_variant: DeckVariant,
+    evaluator: &dyn HandEvaluator,
+) -> Result<EquityResult, PokerError> {
+    let mut monte_carlo = MonteCarloEquity::new(board, ranges, rng, deck_variant, evaluator)?;
+    monte_carlo.run(num_simulations)?;
+    Ok(monte_carlo.accumulator.to_result())
+}
+
+//Sum and sum of squares of each player's share of the pot, to get the equity and its standard error
+#[derive(Debug, Clone)]
+struct EquityAccumulator {
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    num_simulations: usize,
+}
 
-            //This takes into account the used cards
-            player_hole_cards[*player_index] =
-                deck.choose_available_in_range(player_possible_hold_cards)?;
+impl EquityAccumulator {
+    fn new(num_players: usize) -> Self {
+        Self {
+            sum: vec![0.0; num_players],
+            sum_sq: vec![0.0; num_players],
+            num_simulations: 0,
         }
+    }
 
-        for board_index in board.get_num_cards()..5 {
-            let card = deck.get_unused_card().unwrap();
-            board_cards[board_index] = card;
+    fn add_simulation(&mut self, shares: &[f64]) {
+        for (i, share) in shares.iter().enumerate() {
+            self.sum[i] += share;
+            self.sum_sq[i] += share * share;
         }
+        self.num_simulations += 1;
+    }
 
-        assert_eq!(
-            2 * player_hole_cards.len() + 5,
-            deck.get_number_of_used_cards()
-        );
+    #[cfg(not(target_arch = "wasm32"))]
+    fn merge(&mut self, other: &EquityAccumulator) {
+        for i in 0..self.sum.len() {
+            self.sum[i] += other.sum[i];
+            self.sum_sq[i] += other.sum_sq[i];
+        }
+        self.num_simulations += other.num_simulations;
+    }
 
-        //do eval
+    fn std_errors(&self) -> Vec<f64> {
+        self.sum
+            .iter()
+            .zip(self.sum_sq.iter())
+            .map(|(sum, sum_sq)| std_error_of_mean(*sum, *sum_sq, self.num_simulations))
+            .collect_vec()
+    }
 
-        let mut max_rank: Option<Rank> = None;
-        let mut count_at_max = 0;
+    fn max_std_error(&self) -> f64 {
+        self.std_errors().into_iter().fold(0.0, f64::max)
+    }
 
-        for player_index in 0..player_hole_cards.len() {
-            let hole_cards = &player_hole_cards[player_index];
+    fn to_result(&self) -> EquityResult {
+        EquityResult {
+            equities: self
+                .sum
+                .iter()
+                .map(|s| s / self.num_simulations as f64)
+                .collect_vec(),
+            std_errors: self.std_errors(),
+            method: EquityMethod::MonteCarlo,
+            num_evaluations: self.num_simulations,
+        }
+    }
+}
 
-            let h1 = once(hole_cards.get_hi_card()).chain(once(hole_cards.get_lo_card()));
-            let c_it = board_cards.iter().map(|c| *c).chain(h1);
+//The state of a Monte Carlo simulation, so it can be run in batches
+pub(crate) struct MonteCarloEquity<'a> {
+    board: &'a Board,
+    evaluator: &'a dyn HandEvaluator,
+    deck: Deck,
+    //Deals all the hole cards jointly, so overlapping ranges don't bias the results
+    sampler: HoleCardsSampler,
+    player_hole_cards: Vec<HoleCards>,
+    player_values: Vec<HandValue>,
+    board_cards: Vec<Card>,
+    shares: Vec<f64>,
+    accumulator: EquityAccumulator,
+}
 
-            let rank = fast_hand_eval(c_it, &hash_func);
-            player_ranks[player_index] = rank;
+impl<'a> MonteCarloEquity<'a> {
+    pub(crate) fn new(
+        board: &'a Board,
+        ranges: &[WeightedRange],
+        rng: StdRng,
+        deck_variant: DeckVariant,
+        evaluator: &'a dyn HandEvaluator,
+    ) -> Result<Self, PokerError> {
+        //Cards not in the deck are dead, so they are removed from the ranges
+        let mut dead_cards = board.as_slice_card().to_vec();
+        dead_cards.extend_from_slice(deck_variant.removed_cards());
+        let sampler = HoleCardsSampler::new(&ranges.iter().map(Some).collect_vec(), &dead_cards)?;
+
+        let mut board_cards = board.as_slice_card().to_vec();
+        //just a place holder
+        board_cards.resize(5, ALL_CARDS[0]);
+
+        Ok(Self {
+            board,
+            evaluator,
+            deck: Deck::new_with_variant(rng, deck_variant),
+            sampler,
+            player_hole_cards: vec![ALL_HOLE_CARDS[0]; ranges.len()],
+            player_values: vec![HandValue::default(); ranges.len()],
+            board_cards,
+            shares: vec![0.0; ranges.len()],
+            accumulator: EquityAccumulator::new(ranges.len()),
+        })
+    }
 
-            if max_rank.is_none() || &rank > max_rank.as_ref().unwrap() {
-                max_rank = Some(rank);
-                count_at_max = 1;
-            } else if &rank == max_rank.as_ref().unwrap() {
-                count_at_max += 1;
+    fn run(&mut self, num_simulations: usize) -> Result<(), PokerError> {
+        self.run_with(num_simulations, |_, _| {})
+    }
+
+    //Also passes the hole cards dealt and each player's share of the pot of every simulation
+    pub(crate) fn run_with<F>(
+        &mut self,
+        num_simulations: usize,
+        mut on_simulation: F,
+    ) -> Result<(), PokerError>
+    where
+        F: FnMut(&[HoleCards], &[f64]),
+    {
+        for it in 0..num_simulations {
+            if it % 10_000 == 0 && it > 0 {
+                trace!("it {}", it);
+            }
+            self.deck.reset();
+
+            for c in self.board.as_slice_card().iter() {
+                self.deck.set_used_card(*c);
             }
+
+            //We need to deal hole cards to each player
+            self.sampler
+                .sample(&mut self.deck, &mut self.player_hole_cards)?;
+
+            for board_index in self.board.get_num_cards()..5 {
+                self.board_cards[board_index] = self.deck.get_unused_card()?;
+            }
+
+            assert_eq!(
+                2 * self.player_hole_cards.len() + 5,
+                self.deck.get_number_of_used_cards()
+            );
+
+            //do eval
+            self.shares.fill(0.0);
+            add_showdown_result(
+                &self.board_cards,
+                &self.player_hole_cards,
+                self.evaluator,
+                &mut self.player_values,
+                &mut self.shares,
+                1.0,
+            );
+            self.accumulator.add_simulation(&self.shares);
+            on_simulation(&self.player_hole_cards, &self.shares);
         }
 
-        for player_index in 0..player_hole_cards.len() {
-            if &player_ranks[player_index] == max_rank.as_ref().unwrap() {
-                out[player_index] += 1.0 / count_at_max as f64;
+        Ok(())
+    }
+}
+
+//Only used when the ranges are wide, so almost all deals are accepted
+const MAX_OMAHA_DEAL_ATTEMPTS: usize = 100_000;
+
+/*
+Same as calc_equity_with_method but for Omaha (PLO4 or PLO5), each player must use exactly 2 of their
+hole cards and 3 board cards.
+
+Hands are chosen uniformly from each range, and the whole deal is rejected if any cards are shared
+so overlapping ranges are not biased.  Players with any hand (xxxx) are dealt random cards after the others.
+*/
+pub fn calc_omaha_equity(
+    board: &Board,
+    ranges: &[OmahaRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<EquityResult, PokerError> {
+    simulate_omaha(board, ranges, num_simulations, rng, false).map(|r| r.equity)
+}
+
+/*
+Omaha Hi/Lo (Omaha 8), half the pot goes to the best high hand and half to the best 8 or better low,
+both using exactly 2 hole cards.  If nobody has a low the high hand gets the whole pot.
+*/
+pub fn calc_omaha_hi_lo_equity(
+    board: &Board,
+    ranges: &[OmahaRange],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<HiLoEquityResult, PokerError> {
+    simulate_omaha(board, ranges, num_simulations, rng, true)
+}
+
+fn simulate_omaha(
+    board: &Board,
+    ranges: &[OmahaRange],
+    num_simulations: usize,
+    rng: StdRng,
+    hi_lo: bool,
+) -> Result<HiLoEquityResult, PokerError> {
+    let variant = ranges
+        .first()
+        .ok_or(PokerError::from_str("Need at least 1 range"))?
+        .variant;
+    if ranges.iter().any(|r| r.variant != variant) {
+        return Err(PokerError::from_str(
+            "All ranges must have the same number of hole cards",
+        ));
+    }
+    let num_hole_cards = variant.num_hole_cards();
+    if 5 + num_hole_cards * ranges.len() > 52 {
+        return Err(PokerError::from_str("Not enough cards for all the players"));
+    }
+
+    //None for players with any hand
+    let player_hands = ranges
+        .iter()
+        .enumerate()
+        .map(|(player_index, r)| {
+            if r.is_any() {
+                return Ok(None);
+            }
+            let hands = r.get_hole_cards(board.as_slice_card());
+            if hands.is_empty() {
+                return Err(PokerError::from_string(format!(
+                    "Player {} has no possible hole cards given the board",
+                    player_index
+                )));
+            }
+            Ok(Some(hands))
+        })
+        .collect::<Result<Vec<_>, PokerError>>()?;
+
+    let hash_func = load_boomperfect_hash();
+    let mut deck = Deck::new_with_rng(rng);
+    let mut player_hole_cards: Vec<Vec<Card>> = vec![Vec::with_capacity(5); ranges.len()];
+    let mut player_ranks = vec![Rank::lowest_rank(); ranges.len()];
+    let mut player_lows: Vec<Option<LowRank>> = vec![None; ranges.len()];
+    let mut board_cards = board.as_slice_card().to_vec();
+    //just a place holder
+    board_cards.resize(5, ALL_CARDS[0]);
+    let mut shares = vec![0.0; ranges.len()];
+    let mut accumulator = HiLoAccumulator::new(ranges.len());
+
+    for _ in 0..num_simulations {
+        let mut attempts = 0;
+        'attempt: loop {
+            attempts += 1;
+            if attempts > MAX_OMAHA_DEAL_ATTEMPTS {
+                return Err(PokerError::from_string(format!(
+                    "Unable to find hole cards without sharing cards after {} attempts",
+                    MAX_OMAHA_DEAL_ATTEMPTS
+                )));
+            }
+
+            deck.reset();
+            for c in board.as_slice_card().iter() {
+                deck.set_used_card(*c);
+            }
+
+            for (player_index, hands) in player_hands.iter().enumerate() {
+                if let Some(hands) = hands {
+                    let hole_cards = &hands[deck.rng.gen_range(0..hands.len())];
+                    if hole_cards.intersects(&deck.used_cards) {
+                        continue 'attempt;
+                    }
+                    player_hole_cards[player_index].clear();
+                    for c in hole_cards.as_slice().iter() {
+                        deck.set_used_card(*c);
+                        player_hole_cards[player_index].push(*c);
+                    }
+                }
+            }
+            break;
+        }
+
+        for (player_index, hands) in player_hands.iter().enumerate() {
+            if hands.is_none() {
+                player_hole_cards[player_index].clear();
+                for _ in 0..num_hole_cards {
+                    player_hole_cards[player_index].push(deck.get_unused_card()?);
+                }
             }
         }
+
+        for board_card in board_cards[board.get_num_cards()..].iter_mut() {
+            *board_card = deck.get_unused_card()?;
+        }
+
+        for (player_index, hole_cards) in player_hole_cards.iter().enumerate() {
+            player_ranks[player_index] = fast_omaha_eval(hole_cards, &board_cards, &hash_func);
+            if hi_lo {
+                player_lows[player_index] =
+                    omaha_eight_or_better_low_eval(hole_cards, &board_cards);
+            }
+        }
+
+        split_pot_shares(&player_ranks, &player_lows, &mut shares);
+        accumulator.add_simulation(&shares, player_lows.iter().any(|l| l.is_some()));
+    }
+
+    Ok(accumulator.to_result())
+}
+
+/*
+Seven card stud Hi/Lo (Stud 8), player_cards are the cards each player has so far (up to 7)
+and dead_cards are any folded or otherwise exposed cards.
+
+The rest of each player's 7 cards are dealt randomly.
+*/
+pub fn calc_stud_hi_lo_equity(
+    player_cards: &[Vec<Card>],
+    dead_cards: &[Card],
+    num_simulations: usize,
+    rng: StdRng,
+) -> Result<HiLoEquityResult, PokerError> {
+    if player_cards.len() < 2 {
+        return Err(PokerError::from_str("Need at least 2 players"));
+    }
+    if player_cards.iter().any(|cards| cards.len() > 7) {
+        return Err(PokerError::from_str("Stud players have at most 7 cards"));
+    }
+    if dead_cards.len() + 7 * player_cards.len() > 52 {
+        return Err(PokerError::from_str("Not enough cards for all the players"));
     }
 
-    for i in 0..out.len() {
-        out[i] /= num_simulations as f64;
+    let mut known_cards_used = CardUsedType::default();
+    for c in player_cards.iter().flatten().chain(dead_cards.iter()) {
+        set_used_card(c.index as usize, &mut known_cards_used)?;
     }
 
-    Ok(out)
+    let hash_func = load_boomperfect_hash();
+    let mut deck = Deck::new_with_rng(rng);
+    let mut hands = player_cards.to_vec();
+    let mut player_ranks = vec![Rank::lowest_rank(); player_cards.len()];
+    let mut player_lows: Vec<Option<LowRank>> = vec![None; player_cards.len()];
+    let mut shares = vec![0.0; player_cards.len()];
+    let mut accumulator = HiLoAccumulator::new(player_cards.len());
+
+    for _ in 0..num_simulations {
+        deck.used_cards = known_cards_used;
+
+        for (player_index, hand) in hands.iter_mut().enumerate() {
+            hand.truncate(player_cards[player_index].len());
+            while hand.len() < 7 {
+                hand.push(deck.get_unused_card()?);
+            }
+
+            player_ranks[player_index] = fast_hand_eval(hand.iter(), &hash_func);
+            player_lows[player_index] = eight_or_better_low_eval(hand.iter());
+        }
+
+        split_pot_shares(&player_ranks, &player_lows, &mut shares);
+        accumulator.add_simulation(&shares, player_lows.iter().any(|l| l.is_some()));
+    }
+
+    Ok(accumulator.to_result())
+}
+
+/*
+Each player's share of a hi/lo pot, half to the best high hand and half to the best low, each half
+split between ties.  With no qualifying low the high hand takes it all.
+
+A share of 1.0 is a scoop, 0.25 is quartered
+*/
+pub fn split_pot_shares(
+    player_ranks: &[Rank],
+    player_lows: &[Option<LowRank>],
+    shares: &mut [f64],
+) {
+    let best_low = player_lows.iter().flatten().max();
+    let high_pot = if best_low.is_some() { 0.5 } else { 1.0 };
+
+    let max_rank = *player_ranks.iter().max().unwrap();
+    let count_at_max = player_ranks.iter().filter(|r| **r == max_rank).count();
+    for (player_index, rank) in player_ranks.iter().enumerate() {
+        shares[player_index] = if *rank == max_rank {
+            high_pot / count_at_max as f64
+        } else {
+            0.0
+        };
+    }
+
+    if let Some(best_low) = best_low {
+        let count_at_best = player_lows
+            .iter()
+            .filter(|l| l.as_ref() == Some(best_low))
+            .count();
+        for (player_index, low) in player_lows.iter().enumerate() {
+            if low.as_ref() == Some(best_low) {
+                shares[player_index] += 0.5 / count_at_best as f64;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HiLoEquityResult {
+    //Equity counts a scoop as 1, winning half the pot as 0.5, etc.
+    pub equity: EquityResult,
+    //How often each player won the whole pot
+    pub scoop_frequencies: Vec<f64>,
+    //How often anyone had a qualifying low
+    pub low_frequency: f64,
+}
+
+struct HiLoAccumulator {
+    equity: EquityAccumulator,
+    num_scoops: Vec<usize>,
+    num_with_low: usize,
+}
+
+impl HiLoAccumulator {
+    fn new(num_players: usize) -> Self {
+        Self {
+            equity: EquityAccumulator::new(num_players),
+            num_scoops: vec![0; num_players],
+            num_with_low: 0,
+        }
+    }
+
+    fn add_simulation(&mut self, shares: &[f64], has_low: bool) {
+        self.equity.add_simulation(shares);
+        for (player_index, share) in shares.iter().enumerate() {
+            if *share == 1.0 {
+                self.num_scoops[player_index] += 1;
+            }
+        }
+        if has_low {
+            self.num_with_low += 1;
+        }
+    }
+
+    fn to_result(&self) -> HiLoEquityResult {
+        let num_simulations = self.equity.num_simulations.max(1) as f64;
+        HiLoEquityResult {
+            equity: self.equity.to_result(),
+            scoop_frequencies: self
+                .num_scoops
+                .iter()
+                .map(|n| *n as f64 / num_simulations)
+                .collect_vec(),
+            low_frequency: self.num_with_low as f64 / num_simulations,
+        }
+    }
 }
 
 pub fn calc_equity_vs_random(
@@ -156,6 +1038,12 @@ pub fn calc_equity_vs_random(
 
     player_hole_cards[0] = hole_cards.clone();
 
+    //The other players have random hands
+    let mut dead_cards = board.as_slice_card().to_vec();
+    dead_cards.push(hole_cards.get_hi_card());
+    dead_cards.push(hole_cards.get_lo_card());
+    let sampler = HoleCardsSampler::new(&vec![None; num_players - 1], &dead_cards)?;
+
     for it in 0..num_simulations {
         if it % 10_000 == 0 && it > 0 {
             trace!("it {}", it);
@@ -170,18 +1058,7 @@ pub fn calc_equity_vs_random(
         deck.set_used_card(player_hole_cards[0].get_lo_card());
 
         //We need to deal hole cards to each player
-        for player_index in 1..num_players {
-            // trace!(
-            //     "Choosing range for {} with {} possibilities",
-            //     p,
-            //     possible_hole_cards[p].len()
-            // );
-
-            //This takes into account the used cards
-            let card1 = deck.get_unused_card().unwrap();
-            let card2 = deck.get_unused_card().unwrap();
-            player_hole_cards[player_index] = HoleCards::new(card1, card2)?;
-        }
+        sampler.sample(&mut deck, &mut player_hole_cards[1..])?;
 
         for board_index in board.get_num_cards()..5 {
             let card = deck.get_unused_card().unwrap();
@@ -226,6 +1103,7 @@ pub fn calc_equity_vs_random(
 }
 
 //If we are doing hole cards + board vs pure random ranges we can reduce the search space
+//Used for the eval cache keys, so the mapping must not change.  See canonical_board for boards and ranges
 pub fn get_equivalent_hole_board(hole_cards: &HoleCards, board: &[Card]) -> (HoleCards, Board) {
     //Mapping
 
@@ -321,7 +1199,7 @@ mod tests {
 
     use log::info;
 
-    use crate::init_test_logger;
+    use crate::{init_test_logger, EvaluatorBackend};
 
     use super::*;
 
@@ -341,7 +1219,7 @@ mod tests {
             "33+,A2s+,K3s+,Q6s+,J8s+,T9s,A2o+,K6o+,Q8o+,JTo".parse().unwrap(),
         ];
 
-        //let rank_db: EvalCacheReDb<ProduceRank> = EvalCacheReDb::new().unwrap();
+        //let rank_db: EvalCache<ProduceRank> = EvalCache::new_redb().unwrap();
 
         //let shared = Rc::new(RefCell::new(rank_db));
 
@@ -366,7 +1244,7 @@ mod tests {
             "As7s".parse().unwrap(),
         ];
 
-        //let rank_db: EvalCacheReDb<ProduceRank> = EvalCacheReDb::new().unwrap();
+        //let rank_db: EvalCache<ProduceRank> = EvalCache::new_redb().unwrap();
 
         //let shared = Rc::new(RefCell::new(rank_db));
 
@@ -382,6 +1260,197 @@ mod tests {
         assert!(results[1] > results[0]);
     }
 
+    #[test]
+    fn test_weighted_ranges() {
+        let board: Board = "Ks Qs 2h".parse().unwrap();
+
+        let hero: BoolRange = "7c7d".parse().unwrap();
+        let villain: BoolRange = "AcAh,4c3c".parse().unwrap();
+
+        let results = calc_equity(&board, &vec![hero.clone(), villain], 10_000).unwrap();
+
+        //AA is rarely chosen, so hero does a lot better
+        let weighted_villain: WeightedRange = "AcAh:0.1,4c3c".parse().unwrap();
+        let weighted_results = calc_equity_weighted(
+            &board,
+            &[WeightedRange::from(&hero), weighted_villain],
+            10_000,
+        )
+        .unwrap();
+
+        assert!(weighted_results[0] > results[0] + 0.2);
+        assert!((weighted_results[0] + weighted_results[1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_exact_equity() {
+        //Villain only wins with one of the 2 remaining kings out of 44 river cards
+        let board: Board = "2c 7d 9h Jc".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["AhAd".parse().unwrap(), "KcKs".parse().unwrap()];
+
+        let result =
+            calc_equity_with_method(&board, &ranges, 1_000, StdRng::seed_from_u64(DEFAULT_SEED))
+                .unwrap();
+        assert_eq!(result.method, EquityMethod::Enumeration);
+        assert_eq!(result.num_evaluations, 44);
+        assert!((result.equities[1] - 2.0 / 44.0).abs() < 1e-9);
+        assert!((result.equities[0] - 42.0 / 44.0).abs() < 1e-9);
+
+        //Card removal, villain can't have AhKh, and AcKc is twice as likely as KsKd
+        let ranges: Vec<WeightedRange> = vec![
+            "AhAd".parse().unwrap(),
+            "AhKh,AcKc,KsKd:0.5".parse().unwrap(),
+        ];
+        let result = calc_equity_exact(&board, &ranges).unwrap();
+        //AcKc wins with the 9 remaining clubs, KsKd with the Kc, Kh
+        let expected_villain = (2.0 / 3.0) * (9.0 / 44.0) + (1.0 / 3.0) * (2.0 / 44.0);
+        assert!((result.equities[1] - expected_villain).abs() < 1e-9);
+
+        //Too much work to enumerate on the flop with wide ranges
+        let board: Board = "9d 8h 9c".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+        let result =
+            calc_equity_with_method(&board, &ranges, 1_000, StdRng::seed_from_u64(DEFAULT_SEED))
+                .unwrap();
+        assert_eq!(result.method, EquityMethod::MonteCarlo);
+        assert_eq!(result.num_evaluations, 1_000);
+
+        //3 + 2 * 25 > 52 cards, and 24 players leave 1 card for the turn and river
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(); 25];
+        assert!(estimate_enumeration_work_size(&board, &ranges).is_err());
+        assert!(estimate_enumeration_work_size(&board, &ranges[..24]).is_err());
+        assert!(estimate_enumeration_work_size(&board, &ranges[..23]).is_ok());
+    }
+
+    #[test]
+    fn test_exact_equity_matches_monte_carlo() {
+        let board: Board = "Ks Qs 2h".parse().unwrap();
+        let ranges: Vec<WeightedRange> =
+            vec!["7c7d".parse().unwrap(), "AsJs,QQ:0.5".parse().unwrap()];
+
+        let exact = calc_equity_exact(&board, &ranges).unwrap();
+        let monte_carlo = calc_equity_monte_carlo(
+            &board,
+            &ranges,
+            20_000,
+            StdRng::seed_from_u64(DEFAULT_SEED),
+            DeckVariant::Standard,
+            &FastEvaluator::default(),
+        )
+        .unwrap();
+
+        for i in 0..ranges.len() {
+            assert!((exact.equities[i] - monte_carlo.equities[i]).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_seeded_equity_repeats() {
+        let board: Board = "9d 8h 9c".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+
+        let run = |seed: u64| {
+            calc_equity_with_method(&board, &ranges, 1_000, StdRng::seed_from_u64(seed))
+                .unwrap()
+                .equities
+        };
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+
+        let bool_ranges: Vec<BoolRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+        let run_bool = |seed: u64| {
+            calc_equity_with_rng(&board, &bool_ranges, 1_000, StdRng::seed_from_u64(seed)).unwrap()
+        };
+        assert_eq!(run_bool(7), run(7));
+        assert_ne!(run_bool(7), run_bool(8));
+        assert_eq!(
+            calc_equity(&board, &bool_ranges, 1_000).unwrap(),
+            run_bool(DEFAULT_SEED)
+        );
+        assert_eq!(
+            calc_equity_weighted_with_rng(&board, &ranges, 1_000, StdRng::seed_from_u64(7))
+                .unwrap(),
+            run(7)
+        );
+    }
+
+    #[test]
+    fn test_equity_with_evaluator() {
+        let board: Board = "9d 8h 9c".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+
+        let expected = calc_equity_weighted(&board, &ranges, 20_000).unwrap();
+
+        for backend in EvaluatorBackend::all() {
+            let result = calc_equity_with_evaluator(
+                &board,
+                &ranges,
+                20_000,
+                StdRng::seed_from_u64(3),
+                backend.evaluator().as_ref(),
+            )
+            .unwrap();
+            assert_eq!(result.num_evaluations, 20_000);
+            for (equity, expected) in result.equities.iter().zip(expected.iter()) {
+                assert!(
+                    (equity - expected).abs() < 0.02,
+                    "{} {}",
+                    backend.name(),
+                    equity
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_equity() {
+        let board: Board = "9d 8h 9c".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+
+        let result = calc_equity_parallel(&board, &ranges, 20_000, 7, 4).unwrap();
+        assert_eq!(result.method, EquityMethod::MonteCarlo);
+        assert_eq!(
+            result.equities,
+            calc_equity_parallel(&board, &ranges, 20_000, 7, 4)
+                .unwrap()
+                .equities
+        );
+
+        let single_thread = calc_equity_parallel(&board, &ranges, 20_000, 7, 1).unwrap();
+        for i in 0..ranges.len() {
+            assert!((result.equities[i] - single_thread.equities[i]).abs() < 0.02);
+        }
+        assert!((result.equities[0] + result.equities[1] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_equity_until_converged() {
+        let board: Board = "9d 8h 9c 2s".parse().unwrap();
+        let ranges: Vec<WeightedRange> = vec!["22+".parse().unwrap(), "A2s+".parse().unwrap()];
+
+        let exact = calc_equity_exact(&board, &ranges).unwrap();
+
+        //max iterations is lower than the enumeration work size so it simulates
+        let stopping_rule = StoppingRule::with_target_std_error(0.005, 100_000);
+        let result = calc_equity_until_converged(
+            &board,
+            &ranges,
+            &stopping_rule,
+            StdRng::seed_from_u64(DEFAULT_SEED),
+        )
+        .unwrap();
+
+        assert_eq!(result.method, EquityMethod::MonteCarlo);
+        assert!(result.num_evaluations < 100_000);
+        for i in 0..ranges.len() {
+            assert!(result.std_errors[i] <= 0.005);
+            let (lo, hi) = result.confidence_interval(i);
+            assert!(lo < result.equities[i] && result.equities[i] < hi);
+            assert!((result.equities[i] - exact.equities[i]).abs() < 3.0 * result.std_errors[i]);
+        }
+    }
+
     fn compare_expected_actual(
         hole_cards: &HoleCards,
         board: &Board,
@@ -404,6 +1473,154 @@ mod tests {
         assert_eq!(expected_board.as_slice_card(), actual_board.as_slice_card());
     }
 
+    #[test]
+    fn test_short_deck_equity() {
+        let rng = || StdRng::seed_from_u64(DEFAULT_SEED);
+        let short_deck_equity = |board: &str, ranges: &[&str], num_simulations: usize| {
+            let board: Board = board.parse().unwrap();
+            let ranges: Vec<WeightedRange> = ranges.iter().map(|r| r.parse().unwrap()).collect();
+            calc_equity_for_variant(
+                &board,
+                &ranges,
+                num_simulations,
+                rng(),
+                DeckVariant::ShortDeck,
+            )
+        };
+
+        //A6789 is a straight
+        let result = short_deck_equity("Ac 6d 7h Kd Ks", &["9c8c", "AhQh"], 100).unwrap();
+        assert_eq!(result.method, EquityMethod::Enumeration);
+        assert_eq!(result.equities, vec![1.0, 0.0]);
+
+        //Flush beats a full house
+        let result = short_deck_equity("Ks Kh 9s 7s 6d", &["AsTs", "9h9d"], 100).unwrap();
+        assert_eq!(result.equities, vec![1.0, 0.0]);
+        let result = calc_equity_exact(
+            &"Ks Kh 9s 7s 6d".parse().unwrap(),
+            &["AsTs".parse().unwrap(), "9h9d".parse().unwrap()],
+        )
+        .unwrap();
+        assert_eq!(result.equities, vec![0.0, 1.0]);
+
+        //Fewer cards make the underdog's outs more likely than in a full deck
+        let result = short_deck_equity("", &["AA", "KK"], 5_000).unwrap();
+        assert_eq!(result.method, EquityMethod::MonteCarlo);
+        let full_deck_result = calc_equity_with_method(
+            &Board::new(),
+            &["AA".parse().unwrap(), "KK".parse().unwrap()],
+            5_000,
+            rng(),
+        )
+        .unwrap();
+        assert!(result.equities[0] > 0.7);
+        assert!(result.equities[0] < full_deck_result.equities[0]);
+
+        //Only the 66+ part of the range can be dealt
+        let result = short_deck_equity("Kc Qd 8h 7d", &["22+", "AKs"], 100_000).unwrap();
+        let pairs_result = short_deck_equity("Kc Qd 8h 7d", &["66+", "AKs"], 100_000).unwrap();
+        assert_eq!(result.equities, pairs_result.equities);
+
+        assert!(short_deck_equity("", &["55-22", "AA"], 100).is_err());
+        assert!(short_deck_equity("2c 6d 7h", &["AA", "KK"], 100).is_err());
+
+        //3 + 2 * 17 > 36 cards
+        assert!(short_deck_equity("Ac 6d 7h", &["66+"; 17], 100).is_err());
+    }
+
+    #[test]
+    fn test_omaha_equity() {
+        let rng = || StdRng::seed_from_u64(DEFAULT_SEED);
+
+        //Only player 2 can use 2 hearts
+        let board: Board = "2h 5h 8h Th 3c".parse().unwrap();
+        let ranges: Vec<OmahaRange> =
+            vec!["AhKcQdJs".parse().unwrap(), "7h6h2c2d".parse().unwrap()];
+        let result = calc_omaha_equity(&board, &ranges, 100, rng()).unwrap();
+        assert_eq!(result.equities, vec![0.0, 1.0]);
+
+        let board = Board::new();
+        let ranges: Vec<OmahaRange> = vec!["xxxx".parse().unwrap(); 3];
+        let result = calc_omaha_equity(&board, &ranges, 2_000, rng()).unwrap();
+        for player_index in 0..3 {
+            assert!(
+                (result.equities[player_index] - 1.0 / 3.0).abs()
+                    < 4.0 * result.std_errors[player_index]
+            );
+        }
+
+        let ranges: Vec<OmahaRange> = vec!["AAxxds".parse().unwrap(), "xxxx".parse().unwrap()];
+        let result = calc_omaha_equity(&board, &ranges, 2_000, rng()).unwrap();
+        assert!(result.equities[0] > 0.6 && result.equities[0] < 0.8);
+
+        //Overlapping ranges are symmetric
+        let board: Board = "Qs 7d 2c".parse().unwrap();
+        let ranges: Vec<OmahaRange> = vec!["AAxx".parse().unwrap(); 2];
+        let result = calc_omaha_equity(&board, &ranges, 2_000, rng()).unwrap();
+        assert!((result.equities[0] - 0.5).abs() < 4.0 * result.std_errors[0]);
+
+        let ranges: Vec<OmahaRange> = vec!["xxxxx".parse().unwrap(); 2];
+        let result = calc_omaha_equity(&board, &ranges, 2_000, rng()).unwrap();
+        assert!((result.equities[0] - 0.5).abs() < 4.0 * result.std_errors[0]);
+
+        let ranges: Vec<OmahaRange> = vec!["xxxx".parse().unwrap(), "xxxxx".parse().unwrap()];
+        assert!(calc_omaha_equity(&board, &ranges, 100, rng()).is_err());
+    }
+
+    #[test]
+    fn test_hi_lo_equity() {
+        let rng = || StdRng::seed_from_u64(DEFAULT_SEED);
+        let high = Rank { raw_rank: 1000 };
+        let low_high = Rank { raw_rank: 10 };
+        let best_low = LowRank { raw_rank: 100 };
+        let worse_low = LowRank { raw_rank: 50 };
+        let mut shares = vec![0.0; 3];
+
+        split_pot_shares(
+            &[high, low_high, low_high],
+            &[Some(best_low), Some(worse_low), None],
+            &mut shares,
+        );
+        assert_eq!(shares, vec![1.0, 0.0, 0.0]);
+
+        //Quartered, 2 players split the low
+        split_pot_shares(
+            &[low_high, low_high, high],
+            &[Some(best_low), Some(best_low), None],
+            &mut shares,
+        );
+        assert_eq!(shares, vec![0.25, 0.25, 0.5]);
+
+        //No low, the high hands split the whole pot
+        split_pot_shares(&[high, high, low_high], &[None, None, None], &mut shares);
+        assert_eq!(shares, vec![0.5, 0.5, 0.0]);
+
+        //Player 1 has the A-2-3-4-7 low, player 2 has trip kings
+        let board: Board = "2c 3d 7h Kc Qd".parse().unwrap();
+        let ranges: Vec<OmahaRange> =
+            vec!["AhAs4c5d".parse().unwrap(), "KhKs9c9d".parse().unwrap()];
+        let result = calc_omaha_hi_lo_equity(&board, &ranges, 100, rng()).unwrap();
+        assert_eq!(result.equity.equities, vec![0.5, 0.5]);
+        assert_eq!(result.scoop_frequencies, vec![0.0, 0.0]);
+        assert_eq!(result.low_frequency, 1.0);
+
+        //Same hands as high only
+        let result = calc_omaha_equity(&board, &ranges, 100, rng()).unwrap();
+        assert_eq!(result.equities, vec![0.0, 1.0]);
+
+        let cards = |s: &str| Board::try_from(s).unwrap().as_slice_card().to_vec();
+        let player_cards = vec![cards("Ac 2c 3c"), cards("Kd Kh Ks")];
+        let result = calc_stud_hi_lo_equity(&player_cards, &cards("Qc Jd"), 2_000, rng()).unwrap();
+        assert!((result.equity.equities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        //The kings usually win the high, the A23 makes a low a bit over half the time
+        assert!(result.equity.equities[1] > result.equity.equities[0]);
+        assert!(result.equity.equities[0] > 0.25);
+        assert!(result.low_frequency > 0.5 && result.low_frequency < 1.0);
+        assert!(result.scoop_frequencies[1] > 0.0 && result.scoop_frequencies[1] < 1.0);
+
+        assert!(calc_stud_hi_lo_equity(&player_cards[..1], &[], 100, rng()).is_err());
+    }
+
     #[test]
     fn test_get_equivalent_hole_board() {
         //cargo test --lib test_get_equivalent_hole_board
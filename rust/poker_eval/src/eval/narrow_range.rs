@@ -47,22 +47,42 @@ the board  (5 cards, maybe 4)
 
 Maybe have this take a decision profile, but we'll start with what
 seems reasonable and the most 'fishy'
+
+narrow_range_by_line does this over a whole action line, each street keeps the
+hands that would have continued vs. that street's action.  The hands that continue
+only depend on the board so far, the action and the number of players, so
+RangeNarrowingCache stores those and any starting range is narrowed by intersecting them.
 */
 
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::derive_seed;
 use crate::{
     calc_board_texture,
     core::BoolRange,
     likes_hands::{likes_hand, LikesHandLevel},
-    monte_carlo_equity::calc_equity,
+    monte_carlo_equity::calc_equity_with_method,
     partial_rank_cards,
     pre_calc::fast_eval::fast_hand_eval,
-    Board, PokerError, ALL_HOLE_CARDS,
+    Board, Card, ChipType, PokerError, WeightedRange, ALL_HOLE_CARDS, DEFAULT_SEED,
 };
 
 use boomphf::Mphf;
 use itertools::Itertools;
 use log::{debug, trace};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
+/*
+Keeps the hole cards of range_to_narrow with at least min_equity vs. the opponent ranges.
+
+Like calc_equity, each hole card is simulated with an rng seeded from DEFAULT_SEED
+*/
 pub fn narrow_range_by_equity(
     range_to_narrow: &BoolRange,
     opponent_ranges: &[BoolRange],
@@ -73,48 +93,138 @@ pub fn narrow_range_by_equity(
     let mut narrowed_range = BoolRange::default();
 
     //we'll calc equity on every hole card against the opponent ranges
+    let mut all_ranges = hero_and_opponent_ranges(opponent_ranges);
+
+    for hci in possible_hole_card_indexes(range_to_narrow, board) {
+        let rng = StdRng::seed_from_u64(DEFAULT_SEED);
+        if has_min_equity(
+            hci,
+            &mut all_ranges,
+            min_equity,
+            board,
+            num_simulations,
+            rng,
+        ) {
+            narrowed_range.data.set(hci, true);
+        }
+    }
 
-    let mut all_ranges: Vec<BoolRange> = Vec::with_capacity(opponent_ranges.len() + 1);
-    all_ranges.push(BoolRange::default());
-    all_ranges.extend(opponent_ranges.iter().cloned());
+    narrowed_range
+}
 
-    let hole_card_indexes = range_to_narrow.data.iter_ones().collect_vec();
+/*
+Same as narrow_range_by_equity, but the hole cards are split across num_threads threads.
 
-    for hci in hole_card_indexes.iter() {
-        if *hci >= ALL_HOLE_CARDS.len() {
-            break;
-        }
+Each hole card gets its own rng seeded from seed and its range index,
+so the narrowed range only depends on the seed.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+pub fn narrow_range_by_equity_parallel(
+    range_to_narrow: &BoolRange,
+    opponent_ranges: &[BoolRange],
+    min_equity: f64,
+    board: &Board,
+    num_simulations: usize,
+    seed: u64,
+    num_threads: usize,
+) -> Result<BoolRange, PokerError> {
+    let num_threads = num_threads.max(1);
+
+    let hole_card_indexes = possible_hole_card_indexes(range_to_narrow, board);
+
+    let thread_ranges: Vec<Result<BoolRange, PokerError>> = std::thread::scope(|s| {
+        let handles = (0..num_threads)
+            .map(|thread_index| {
+                let hole_card_indexes = &hole_card_indexes;
+                s.spawn(move || {
+                    let mut narrowed_range = BoolRange::default();
+                    let mut all_ranges = hero_and_opponent_ranges(opponent_ranges);
+
+                    for hci in hole_card_indexes
+                        .iter()
+                        .skip(thread_index)
+                        .step_by(num_threads)
+                    {
+                        let rng = StdRng::seed_from_u64(derive_seed(seed, *hci as u64));
+                        if has_min_equity(
+                            *hci,
+                            &mut all_ranges,
+                            min_equity,
+                            board,
+                            num_simulations,
+                            rng,
+                        ) {
+                            narrowed_range.data.set(*hci, true);
+                        }
+                    }
+
+                    narrowed_range
+                })
+            })
+            .collect_vec();
+
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .map_err(|_| PokerError::from_str("Narrowing thread panicked"))
+            })
+            .collect_vec()
+    });
 
-        //if these hole cards are impossible given the board, skip
-        if board.intersects_holecards(&ALL_HOLE_CARDS[*hci]) {
-            continue;
-        }
+    let mut narrowed_range = BoolRange::default();
+    for thread_range in thread_ranges {
+        narrowed_range = narrowed_range.union(&thread_range?);
+    }
+    Ok(narrowed_range)
+}
 
-        all_ranges[0].data.fill(false);
-        all_ranges[0].data.set(*hci, true);
-        let results = calc_equity(board, &all_ranges, num_simulations);
-
-        match results {
-            Err(e) => {
-                let hc = ALL_HOLE_CARDS[*hci];
-                debug!("Unable to calculate {}, error: {}", &hc, e);
-                continue;
-            }
-            Ok(results) => {
-                trace!(
-                    "Equity was {:.2} for {} in board {}",
-                    results[0],
-                    ALL_HOLE_CARDS[*hci],
-                    &board
-                );
-                if results[0] >= min_equity {
-                    narrowed_range.data.set(*hci, true);
-                }
-            }
+//The hole cards of the range, skipping those that are impossible given the board
+fn possible_hole_card_indexes(range_to_narrow: &BoolRange, board: &Board) -> Vec<usize> {
+    range_to_narrow
+        .data
+        .iter_ones()
+        .filter(|hci| *hci < ALL_HOLE_CARDS.len())
+        .filter(|hci| !board.intersects_holecards(&ALL_HOLE_CARDS[*hci]))
+        .collect_vec()
+}
+
+//The first range is a place holder for the hole cards being checked
+fn hero_and_opponent_ranges(opponent_ranges: &[BoolRange]) -> Vec<WeightedRange> {
+    let mut all_ranges: Vec<WeightedRange> = Vec::with_capacity(opponent_ranges.len() + 1);
+    all_ranges.push(WeightedRange::default());
+    all_ranges.extend(opponent_ranges.iter().map(WeightedRange::from));
+    all_ranges
+}
+
+//Puts the hole cards in the first range, an equity error counts as not enough equity
+fn has_min_equity(
+    hci: usize,
+    all_ranges: &mut [WeightedRange],
+    min_equity: f64,
+    board: &Board,
+    num_simulations: usize,
+    rng: StdRng,
+) -> bool {
+    all_ranges[0].data.fill(0.0);
+    all_ranges[0].data[hci] = 1.0;
+
+    match calc_equity_with_method(board, all_ranges, num_simulations, rng) {
+        Err(e) => {
+            let hc = ALL_HOLE_CARDS[hci];
+            debug!("Unable to calculate {}, error: {}", &hc, e);
+            false
+        }
+        Ok(results) => {
+            trace!(
+                "Equity was {:.2} for {} in board {}",
+                results.equities[0],
+                ALL_HOLE_CARDS[hci],
+                &board
+            );
+            results.equities[0] >= min_equity
         }
     }
-
-    narrowed_range
 }
 
 pub fn narrow_range_by_pref(
@@ -146,7 +256,14 @@ pub fn narrow_range_by_pref(
 
         let rank = fast_hand_eval(board.get_iter().chain(hc.get_iter()), hash_func);
 
-        let likes_hand_response = likes_hand(&prc, &board_texture, &rank, board, &hc, num_players)?;
+        let likes_hand_response = likes_hand(
+            &prc,
+            &board_texture,
+            rank.get_rank_enum(),
+            board,
+            &hc,
+            num_players,
+        )?;
 
         if likes_hand_response.likes_hand >= min_likes_hand_level {
             narrowed_range.data.set(*hci, true);
@@ -156,10 +273,203 @@ pub fn narrow_range_by_pref(
     Ok(narrowed_range)
 }
 
+//What the range faced on one street after the flop, ordered by bet size
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum StreetAction {
+    NoBet = 0,
+    SmallBet = 1,
+    LargeBet = 2,
+}
+
+impl StreetAction {
+    //Up to half pot is a small bet
+    pub fn from_bet(bet: ChipType, pot: ChipType) -> Self {
+        if bet == 0 {
+            StreetAction::NoBet
+        } else if 2 * bet as u32 <= pot as u32 {
+            StreetAction::SmallBet
+        } else {
+            StreetAction::LargeBet
+        }
+    }
+
+    //None means every hand continues
+    pub fn min_likes_hand_level(&self) -> Option<LikesHandLevel> {
+        match self {
+            StreetAction::NoBet => None,
+            StreetAction::SmallBet => Some(LikesHandLevel::CallSmallBet),
+            StreetAction::LargeBet => Some(LikesHandLevel::LargeBet),
+        }
+    }
+}
+
+impl Display for StreetAction {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let s = match self {
+            StreetAction::NoBet => "No bet",
+            StreetAction::SmallBet => "Small bet",
+            StreetAction::LargeBet => "Large bet",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl TryFrom<u8> for StreetAction {
+    type Error = PokerError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(StreetAction::NoBet),
+            1 => Ok(StreetAction::SmallBet),
+            2 => Ok(StreetAction::LargeBet),
+            _ => Err(PokerError::from_string(format!(
+                "Invalid street action {}",
+                value
+            ))),
+        }
+    }
+}
+
+//x / s / l, so a line can be written as "s,l,x"
+impl FromStr for StreetAction {
+    type Err = PokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "x" | "n" | "none" | "check" => Ok(StreetAction::NoBet),
+            "s" | "small" => Ok(StreetAction::SmallBet),
+            "l" | "large" => Ok(StreetAction::LargeBet),
+            _ => Err(PokerError::from_string(format!(
+                "Invalid street action {}",
+                s
+            ))),
+        }
+    }
+}
+
+pub fn parse_action_line(s: &str) -> Result<Vec<StreetAction>, PokerError> {
+    s.split(',').map(|a| a.parse()).collect()
+}
+
+//board index (so also the street), action, num players
+type NarrowingKey = (u32, StreetAction, u8);
+
+/*
+Hands that continue on a board vs. an action, kept by the flop_analyzer between calls
+and shared by the agents (see AgentHandEval::set_range_narrowing_cache)
+*/
+#[derive(Default)]
+pub struct RangeNarrowingCache {
+    continuing_ranges: HashMap<NarrowingKey, BoolRange>,
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+}
+
+impl RangeNarrowingCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.continuing_ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.continuing_ranges.is_empty()
+    }
+
+    pub fn get_put(
+        &mut self,
+        board: &mut Board,
+        action: StreetAction,
+        num_players: u8,
+        hash_func: &Mphf<u32>,
+    ) -> Result<&BoolRange, PokerError> {
+        let key = (board.get_index(), action, num_players);
+
+        if self.continuing_ranges.contains_key(&key) {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+            let all_hands = BoolRange::new().complement();
+            let continuing_range = match action.min_likes_hand_level() {
+                None => all_hands.without_cards(board.as_slice_card()),
+                Some(level) => {
+                    narrow_range_by_pref(&all_hands, level, board, num_players, hash_func)?
+                }
+            };
+            self.continuing_ranges.insert(key, continuing_range);
+        }
+
+        Ok(&self.continuing_ranges[&key])
+    }
+}
+
+/*
+Narrows the range street by street, line[0] is the flop action, line[1] the turn, line[2] the river.
+
+board is the runout, it needs at least the cards up to the last street in the line.
+
+Returns the narrowed range after each street in the line
+*/
+pub fn narrow_range_by_line(
+    range_to_narrow: &BoolRange,
+    board: &[Card],
+    line: &[StreetAction],
+    num_players: u8,
+    hash_func: &Mphf<u32>,
+    cache: &mut RangeNarrowingCache,
+) -> Result<Vec<BoolRange>, PokerError> {
+    if line.is_empty() || line.len() > 3 {
+        return Err(PokerError::from_string(format!(
+            "Action line should have 1 to 3 streets, not {}",
+            line.len()
+        )));
+    }
+    if board.len() < 2 + line.len() || board.len() > 5 {
+        return Err(PokerError::from_string(format!(
+            "Board {} is too short for {} streets",
+            board.iter().join(" "),
+            line.len()
+        )));
+    }
+    if !board.iter().all_unique() {
+        return Err(PokerError::from_string(format!(
+            "Board {} has duplicate cards",
+            board.iter().join(" ")
+        )));
+    }
+
+    let mut narrowed_ranges = Vec::with_capacity(line.len());
+    let mut narrowed_range = range_to_narrow.clone();
+
+    for (street_index, action) in line.iter().enumerate() {
+        let mut street_board = Board::new_from_cards(&board[..3 + street_index]);
+        let continuing_range = cache.get_put(&mut street_board, *action, num_players, hash_func)?;
+
+        narrowed_range = narrowed_range.intersection(continuing_range);
+
+        debug!(
+            "{} on {} leaves {} combos",
+            action,
+            street_board,
+            narrowed_range.get_num_combos()
+        );
+
+        narrowed_ranges.push(narrowed_range.clone());
+    }
+
+    Ok(narrowed_ranges)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{init_test_logger, Board, BoolRange};
+    use crate::{
+        init_test_logger, pre_calc::perfect_hash::load_boomperfect_hash, Board, BoolRange,
+        HoleCards,
+    };
 
     #[test]
     fn test_narrow_range() {
@@ -170,9 +480,10 @@ mod tests {
         init_test_logger();
 
         //let hero_range: BoolRange = "Jd9s".parse().unwrap();
-        let hero_range = "22+,A2+,K2+,Q3s+,Q5o+,J7s+,J8o+,T7s+,T8o+,97s+,98o,87s,76s,65s,54s"
-            .parse()
-            .unwrap();
+        let hero_range: BoolRange =
+            "22+,A2+,K2+,Q3s+,Q5o+,J7s+,J8o+,T7s+,T8o+,97s+,98o,87s,76s,65s,54s"
+                .parse()
+                .unwrap();
         let other_guy: BoolRange = "22+,A2+,K2+,Q2s+,Q3o+,J3s+,J6o+,T5s+,T7o+,97s+,98o,87s"
             .parse()
             .unwrap();
@@ -180,11 +491,43 @@ mod tests {
             .parse()
             .unwrap();
 
-        let narrowed_range =
-            narrow_range_by_equity(&to_narrow, &[hero_range, other_guy], 0.25, &board, 1);
+        let narrowed_range = narrow_range_by_equity(
+            &to_narrow,
+            &[hero_range.clone(), other_guy.clone()],
+            0.25,
+            &board,
+            1,
+        );
 
         println!("Narrowed range:\n{}", narrowed_range.to_string());
 
+        let parallel_range = narrow_range_by_equity_parallel(
+            &to_narrow,
+            &[hero_range.clone(), other_guy.clone()],
+            0.25,
+            &board,
+            100,
+            7,
+            4,
+        )
+        .unwrap();
+        //Each hole card has its own seed, so the thread count doesn't matter
+        assert_eq!(
+            parallel_range,
+            narrow_range_by_equity_parallel(
+                &to_narrow,
+                &[hero_range, other_guy],
+                0.25,
+                &board,
+                100,
+                7,
+                1
+            )
+            .unwrap()
+        );
+        assert!(parallel_range.get_num_combos() > 0);
+        assert!(parallel_range.get_num_combos() < to_narrow.get_num_combos());
+
         // let board: Board = "Jh 6h 5d".parse().unwrap();
         // let to_narrow : BoolRange = "22+,A2+,K2+,Q2+,J2+,T2s+,T5o+,95s+,96o+,85s+,87o,76s".parse().unwrap();
         // let op1 : BoolRange = "22+,A2+,K2+,Q2+,J2+,T2+,92+,82+,72+,62+,52+,42+,32".parse().unwrap();
@@ -194,4 +537,91 @@ mod tests {
 
         // println!("Narrowed range:\n{}", narrowed_range.to_string());
     }
+
+    #[test]
+    fn test_narrow_range_by_line() {
+        let hash_func = load_boomperfect_hash();
+        let mut cache = RangeNarrowingCache::new();
+
+        let board = Board::try_from("Ah 7d 2c Ks 9h").unwrap();
+        let to_narrow: BoolRange = "22+,A2+,K2+,Q2+,J7+,T7+,97+,87,76".parse().unwrap();
+
+        let line = parse_action_line("s,l,x").unwrap();
+        assert_eq!(
+            line,
+            vec![
+                StreetAction::SmallBet,
+                StreetAction::LargeBet,
+                StreetAction::NoBet
+            ]
+        );
+
+        let narrowed = narrow_range_by_line(
+            &to_narrow,
+            board.as_slice_card(),
+            &line,
+            3,
+            &hash_func,
+            &mut cache,
+        )
+        .unwrap();
+        assert_eq!(narrowed.len(), 3);
+        assert_eq!(cache.cache_misses, 3);
+
+        //Each street only narrows further
+        assert!(narrowed[0].get_num_combos() < to_narrow.get_num_combos());
+        assert!(narrowed[1].get_num_combos() < narrowed[0].get_num_combos());
+        assert_eq!(narrowed[1].difference(&narrowed[0]).get_num_combos(), 0);
+        //No bet only removes the river card
+        assert_eq!(
+            narrowed[2],
+            narrowed[1].without_cards(&[board.as_slice_card()[4]])
+        );
+
+        //Same as narrowing one street at a time
+        let flop = Board::try_from("Ah 7d 2c").unwrap();
+        assert_eq!(
+            narrowed[0],
+            narrow_range_by_pref(
+                &to_narrow,
+                LikesHandLevel::CallSmallBet,
+                &flop,
+                3,
+                &hash_func
+            )
+            .unwrap()
+        );
+
+        //Top pair or better likes a large bet
+        let ak: HoleCards = "AsKd".parse().unwrap();
+        assert!(narrowed[1].data[ak.to_range_index()]);
+        let t8: HoleCards = "Tc8c".parse().unwrap();
+        assert!(!narrowed[0].data[t8.to_range_index()]);
+
+        //A different starting range on the same runout only needs the cache
+        let tight: BoolRange = "TT+,AJ+,KQ".parse().unwrap();
+        let narrowed_tight = narrow_range_by_line(
+            &tight,
+            board.as_slice_card(),
+            &line[..2],
+            3,
+            &hash_func,
+            &mut cache,
+        )
+        .unwrap();
+        assert_eq!(cache.cache_misses, 3);
+        assert_eq!(cache.cache_hits, 2);
+        assert_eq!(narrowed_tight[1], tight.intersection(&narrowed[1]));
+
+        assert!(narrow_range_by_line(
+            &tight,
+            &board.as_slice_card()[..4],
+            &line,
+            3,
+            &hash_func,
+            &mut cache
+        )
+        .is_err());
+        assert!(parse_action_line("s,b").is_err());
+    }
 }
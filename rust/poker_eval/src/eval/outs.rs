@@ -0,0 +1,281 @@
+use boomphf::Mphf;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    deal_cards_used, pre_calc::fast_eval::fast_hand_eval, weighted_range_hole_cards_left, Card,
+    CardUsedType, HoleCards, PokerError, WeightedRange, ALL_CARDS,
+};
+
+//After a clean out we beat at least this much of the range
+pub const CLEAN_OUT_HAND_STRENGTH: f64 = 0.8;
+//How much the hand strength must go up for a dirty out
+pub const MIN_OUT_IMPROVEMENT: f64 = 0.2;
+//How much the equity must go up for a redraw, less since there is still a card to come
+pub const MIN_REDRAW_IMPROVEMENT: f64 = 0.1;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutType {
+    //We now beat almost all of the range
+    Clean,
+    //We improve a lot, but still lose to a good part of the range
+    Dirty,
+    //Not ahead yet, but we pick up a draw for the river so our equity goes up a lot
+    Redraw,
+    //Doesn't help us, it may even help the villian
+    Blank,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Out {
+    pub card: Card,
+    pub out_type: OutType,
+    //How much of the range we beat once the card is out, ties count half
+    pub hand_strength: f64,
+    //Our equity at showdown once the card is out, the same as hand_strength on the river
+    pub equity: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OutsResult {
+    //Before the next card
+    pub hand_strength: f64,
+    pub equity: f64,
+    //Every unseen card that can still come given the range, in card order
+    pub cards: Vec<Out>,
+}
+
+impl OutsResult {
+    pub fn get_cards(&self, out_type: OutType) -> Vec<Card> {
+        self.cards
+            .iter()
+            .filter(|o| o.out_type == out_type)
+            .map(|o| o.card)
+            .collect_vec()
+    }
+
+    //Clean + dirty outs
+    pub fn num_outs(&self) -> usize {
+        self.cards
+            .iter()
+            .filter(|o| o.out_type == OutType::Clean || o.out_type == OutType::Dirty)
+            .count()
+    }
+}
+
+struct OutsCalculator<'a> {
+    hole_cards: &'a HoleCards,
+    //With their weight in the range
+    villian_hole_cards: Vec<(HoleCards, f32)>,
+    hash_func: &'a Mphf<u32>,
+}
+
+impl<'a> OutsCalculator<'a> {
+    fn rank(&self, hole_cards: &HoleCards, board: &[Card]) -> u16 {
+        fast_hand_eval(
+            board.iter().chain(hole_cards.as_slice().iter()),
+            self.hash_func,
+        )
+        .raw_rank
+    }
+
+    //Our share vs each villian hand not using a used card, averaged by weight, None if the board blocks them all
+    fn hand_strength(&self, board: &[Card], cards_used: &CardUsedType) -> Option<f64> {
+        let hero_rank = self.rank(self.hole_cards, board);
+        let mut total = 0.0;
+        let mut total_weight = 0.0;
+        for (hc, weight) in self.villian_hole_cards.iter() {
+            if cards_used[hc.get_hi_card().index as usize]
+                || cards_used[hc.get_lo_card().index as usize]
+            {
+                continue;
+            }
+            let villian_rank = self.rank(hc, board);
+            let share = match hero_rank.cmp(&villian_rank) {
+                std::cmp::Ordering::Greater => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Less => 0.0,
+            };
+            total += share * *weight as f64;
+            total_weight += *weight as f64;
+        }
+        if total_weight == 0.0 {
+            None
+        } else {
+            Some(total / total_weight)
+        }
+    }
+
+    //Enumerates every river, or just the hand strength if board is already the river
+    fn equity(&self, board: &mut Vec<Card>, cards_used: &mut CardUsedType) -> Option<f64> {
+        if board.len() == 5 {
+            return self.hand_strength(board, cards_used);
+        }
+
+        let mut total = 0.0;
+        let mut count = 0;
+        for river in self.unseen_cards(cards_used) {
+            board.push(river);
+            cards_used.set(river.index as usize, true);
+            if let Some(hand_strength) = self.hand_strength(board, cards_used) {
+                total += hand_strength;
+                count += 1;
+            }
+            cards_used.set(river.index as usize, false);
+            board.pop();
+        }
+        if count == 0 {
+            None
+        } else {
+            Some(total / count as f64)
+        }
+    }
+
+    fn unseen_cards(&self, cards_used: &CardUsedType) -> Vec<Card> {
+        ALL_CARDS
+            .iter()
+            .filter(|c| !cards_used[c.index as usize])
+            .copied()
+            .collect_vec()
+    }
+}
+
+/*
+What each unseen card does for our hole cards on a flop or turn vs the villian's range,
+enumerating every card (and every river after it when on the flop).
+
+Each villian hand counts by its weight in the range
+*/
+pub fn calc_outs(
+    hole_cards: &HoleCards,
+    board: &[Card],
+    range: &WeightedRange,
+    hash_func: &Mphf<u32>,
+) -> Result<OutsResult, PokerError> {
+    if board.len() != 3 && board.len() != 4 {
+        return Err(PokerError::from_string(format!(
+            "Outs need a flop or turn, not {} board cards",
+            board.len()
+        )));
+    }
+
+    //Hole cards are in here too, neither they nor the board can come
+    let mut cards_used = deal_cards_used(board, hole_cards)?;
+    let villian_hole_cards = weighted_range_hole_cards_left(range, &cards_used)?;
+
+    let calculator = OutsCalculator {
+        hole_cards,
+        villian_hole_cards,
+        hash_func,
+    };
+
+    let mut board_cards = board.to_vec();
+    //The range has hands left, so these are never None
+    let hand_strength = calculator
+        .hand_strength(&board_cards, &cards_used)
+        .unwrap_or_default();
+    let equity = calculator
+        .equity(&mut board_cards, &mut cards_used)
+        .unwrap_or_default();
+
+    let mut cards = Vec::new();
+    for card in calculator.unseen_cards(&cards_used) {
+        board_cards.push(card);
+        cards_used.set(card.index as usize, true);
+
+        let card_hand_strength = calculator.hand_strength(&board_cards, &cards_used);
+        let card_equity = calculator.equity(&mut board_cards, &mut cards_used);
+        cards_used.set(card.index as usize, false);
+        board_cards.pop();
+
+        //Every hand in the range has this card, so it can't come
+        let (Some(card_hand_strength), Some(card_equity)) = (card_hand_strength, card_equity)
+        else {
+            continue;
+        };
+
+        let out_type = if card_hand_strength >= CLEAN_OUT_HAND_STRENGTH
+            && card_hand_strength > hand_strength
+        {
+            OutType::Clean
+        } else if card_hand_strength >= hand_strength + MIN_OUT_IMPROVEMENT {
+            OutType::Dirty
+        } else if card_equity >= equity + MIN_REDRAW_IMPROVEMENT {
+            OutType::Redraw
+        } else {
+            OutType::Blank
+        };
+
+        cards.push(Out {
+            card,
+            out_type,
+            hand_strength: card_hand_strength,
+            equity: card_equity,
+        });
+    }
+
+    Ok(OutsResult {
+        hand_strength,
+        equity,
+        cards,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval_test_deal, pre_calc::perfect_hash::load_boomperfect_hash, Board};
+
+    use super::*;
+
+    fn calc(hole_cards: &str, board: &str, range: &str) -> OutsResult {
+        eval_test_deal(hole_cards, board, |hole_cards, board, hash_func| {
+            calc_outs(hole_cards, board, &range.parse().unwrap(), hash_func)
+        })
+    }
+
+    #[test]
+    fn test_outs() {
+        //Flush + open ended straight draw vs an overpair on the turn
+        let result = calc("9h8h", "Th 7h 2c Kd", "AsAd");
+        assert_eq!(result.hand_strength, 0.0);
+        //The aces can't come
+        assert_eq!(result.cards.len(), 52 - 4 - 2 - 2);
+        //9 hearts + 3 J + 3 6
+        assert_eq!(result.num_outs(), 15);
+        assert_eq!(result.get_cards(OutType::Clean).len(), 15);
+        assert!((result.equity - 15.0 / 44.0).abs() < 1e-9);
+        assert!(result.get_cards(OutType::Redraw).is_empty());
+        let jack: Card = "Jc".parse().unwrap();
+        assert!(result.get_cards(OutType::Clean).contains(&jack));
+
+        //A 9 or 8 gives a pair, but the aces are still ahead
+        let result = calc("9c8c", "Th 7h 2c Kd", "AsAd,3s3d");
+        let nine: Card = "9d".parse().unwrap();
+        assert!(result.get_cards(OutType::Dirty).contains(&nine));
+        let out = result.cards.iter().find(|o| o.card == nine).unwrap();
+        assert_eq!(out.hand_strength, 0.5);
+
+        //Same with the 33 only half in the range, it now counts for a third
+        let result = calc("9c8c", "Th 7h 2c Kd", "AsAd,3s3d:0.5");
+        let out = result.cards.iter().find(|o| o.card == nine).unwrap();
+        assert!((out.hand_strength - 1.0 / 3.0).abs() < 1e-9);
+
+        //On the flop a heart gives a flush draw but no pair
+        let result = calc("AhQc", "Kh 7h 2c", "KsKd");
+        let redraws = result.get_cards(OutType::Redraw);
+        let heart: Card = "3h".parse().unwrap();
+        assert!(redraws.contains(&heart), "{:?}", redraws);
+        assert!(result
+            .cards
+            .iter()
+            .all(|o| o.out_type != OutType::Clean || o.hand_strength >= CLEAN_OUT_HAND_STRENGTH));
+
+        assert!(calc_outs(
+            &"9h8h".parse().unwrap(),
+            Board::try_from("Th 7h 2c Kd 3s").unwrap().as_slice_card(),
+            &"AsAd".parse().unwrap(),
+            &load_boomperfect_hash()
+        )
+        .is_err());
+    }
+}
@@ -60,6 +60,15 @@ impl Display for StraightDrawType {
 pub struct FlushDraw {
     pub hole_card_value: CardValue,
     pub flush_draw_type: FlushDrawType,
+
+    //If we hit our flush, how many higher cards of the suit someone else could hold
+    pub number_above: u8,
+}
+
+impl FlushDraw {
+    pub fn is_nut(&self) -> bool {
+        self.number_above == 0
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize)]
@@ -68,6 +77,9 @@ pub struct StraightDraw {
 
     //If we hit our straight, how many better straight draws (of any type) exist on the board
     pub number_above: u8,
+
+    //3 or more of a suit on the board, so our straight could already be beaten by a flush
+    pub flush_possible: bool,
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize)]
@@ -81,11 +93,15 @@ pub struct PairInfo {
 
 //Tracking basically what our hole cards are doing
 //Meant to be combined with rank to make decisions
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PartialRankContainer {
     pub flush_draw: Option<FlushDraw>,
     pub straight_draw: Option<StraightDraw>,
 
+    //On the flop, a hole card is in a 5 card window needing 2 more cards (the turn and river)
+    //Only set without a straight draw, like the backdoor flush draw is only kept without a flush draw
+    pub backdoor_straight_draw: bool,
+
     //means that we have the flush suit in our hand, there could be 5 on the board though
     pub made_flush: Option<CardValue>,
 
@@ -112,6 +128,7 @@ impl Default for PartialRankContainer {
             made_flush: None,
             flush_draw: None,
             straight_draw: None,
+            backdoor_straight_draw: false,
             pocket_pair: None,
             hi_pair: None,
             lo_pair: None,
@@ -217,6 +234,32 @@ impl PartialRankContainer {
         false
     }
 
+    pub fn has_nut_flush_draw(&self) -> bool {
+        self.flush_draw
+            .is_some_and(|fd| fd.flush_draw_type == FlushDrawType::FlushDraw && fd.is_nut())
+    }
+
+    //A flush draw and a straight draw, gut shots included
+    pub fn has_combo_draw(&self) -> bool {
+        self.straight_draw.is_some()
+            && self
+                .flush_draw
+                .is_some_and(|fd| fd.flush_draw_type == FlushDrawType::FlushDraw)
+    }
+
+    //A pair using our hole cards (not a set) with a flush draw or an open ended / double gut shot draw
+    pub fn has_pair_and_draw(&self) -> bool {
+        let has_pair = [self.pocket_pair, self.hi_pair, self.lo_pair]
+            .iter()
+            .flatten()
+            .any(|p| !p.made_set && !p.made_quads);
+        let has_draw = self.has_straight_draw()
+            || self
+                .flush_draw
+                .is_some_and(|fd| fd.flush_draw_type == FlushDrawType::FlushDraw);
+        has_pair && has_draw
+    }
+
     pub fn has_top_pair(&self) -> bool {
         if let Some(hi_pair) = self.hi_pair {
             if hi_pair.number_above == 0 {
@@ -299,20 +342,25 @@ impl PartialRankContainer {
                 continue;
             }
 
+            //Unseen cards of the suit higher than our best one
+            let hole_card_value = hole_metrics.suit_value_sets[suit].last_one().unwrap();
+            let number_above = (hole_card_value + 1..13)
+                .filter(|v| !board_metrics.suit_value_sets[suit][*v])
+                .count() as u8;
+
             if hole_count + board_count == 4 {
-                let hole_card_value = hole_metrics.suit_value_sets[suit].last_one().unwrap();
                 self.flush_draw = Some(FlushDraw {
                     hole_card_value: CardValue::from(hole_card_value),
                     flush_draw_type: FlushDrawType::FlushDraw,
+                    number_above,
                 });
             } else if hole_count + board_count == 3 && board_length == 3
             //we need 2 more cards to go
             {
-                let hole_card_value = hole_metrics.suit_value_sets[suit].last_one().unwrap();
-
                 self.flush_draw = Some(FlushDraw {
                     hole_card_value: CardValue::from(hole_card_value),
                     flush_draw_type: FlushDrawType::BackdoorFlushDraw,
+                    number_above,
                 });
             }
         }
@@ -451,6 +499,11 @@ impl PartialRankContainer {
         let num_gut_shots = hero_card_needed.count_ones();
         assert!(num_gut_shots <= 2);
 
+        let flush_possible = board_metrics
+            .suit_value_sets
+            .iter()
+            .any(|s| s.count_ones() >= 3);
+
         //debug_print_value_set("Hero draw values", hero_draws);
 
         if num_gut_shots == 1 {
@@ -465,6 +518,7 @@ impl PartialRankContainer {
             self.straight_draw = Some(StraightDraw {
                 straight_draw_type: StraightDrawType::GutShot(card_needed.into()),
                 number_above: count_higher(all_draws, hi),
+                flush_possible,
             });
         } else if num_gut_shots == 2 {
             //if the diff is one, its an open ended draw, more than that it's a double gut shot
@@ -475,16 +529,47 @@ impl PartialRankContainer {
                 self.straight_draw = Some(StraightDraw {
                     straight_draw_type: StraightDrawType::OpenEnded,
                     number_above: count_higher(all_draws, hi),
+                    flush_possible,
                 });
             } else {
                 self.straight_draw = Some(StraightDraw {
                     straight_draw_type: StraightDrawType::DoubleGutShot,
                     number_above: count_higher(all_draws, hi),
+                    flush_possible,
                 });
             }
         }
     }
 
+    fn handle_backdoor_str8_draws(
+        &mut self,
+        hole_metrics: &BitSetCardsMetrics,
+        board_metrics: &BitSetCardsMetrics,
+        board_length: usize,
+    ) {
+        //Only the flop has 2 cards to come
+        if board_length != 3 || self.straight_draw.is_some() {
+            return;
+        }
+
+        let combined_value_set = board_metrics.value_set.bitor(hole_metrics.value_set);
+
+        //Already a straight
+        if rank_straight(combined_value_set.data[0]).is_some() {
+            return;
+        }
+
+        //Any window with 3 values where at least 1 is from our hole cards
+        self.backdoor_straight_draw =
+            value_set_iterator(board_metrics.value_set, 5, CardValue::Ace, CardValue::Ace)
+                .unwrap()
+                .zip(
+                    value_set_iterator(combined_value_set, 5, CardValue::Ace, CardValue::Ace)
+                        .unwrap(),
+                )
+                .any(|(vs_it, bh_it)| bh_it.value_count == 3 && vs_it.value_count < 3);
+    }
+
     fn get_pair_info_for_single_hole_card(
         &self,
         hole_card: CardValue,
@@ -547,6 +632,7 @@ impl PartialRankContainer {
         } else if let Some(other_flush_draw) = other.flush_draw {
             let fd = self.flush_draw.as_mut().unwrap();
             fd.flush_draw_type = max(fd.flush_draw_type, other_flush_draw.flush_draw_type);
+            fd.number_above = min(fd.number_above, other_flush_draw.number_above);
         }
 
         if self.straight_draw.is_none() {
@@ -557,6 +643,8 @@ impl PartialRankContainer {
             sd.number_above = max(sd.number_above, other_straight_draw.number_above);
         }
 
+        self.backdoor_straight_draw |= other.backdoor_straight_draw;
+
         if self.hi_pair.is_none() {
             //always take rhs
             self.hi_pair = other.hi_pair;
@@ -620,6 +708,7 @@ pub fn partial_rank_cards(hole_cards: &HoleCards, board: &[Card]) -> PartialRank
 
     //straight draws
     partial_ranks.handle_str8_draws(hole_cards.as_slice(), &hole_metrics, &board_metrics);
+    partial_ranks.handle_backdoor_str8_draws(&hole_metrics, &board_metrics, board.len());
 
     //flush draws
     partial_ranks.handle_flush_draws(
@@ -852,7 +941,8 @@ mod tests {
                 straight_draw_type: StraightDrawType::GutShot(CardValue::Six),
                 //T J Q [K] [A]
                 //[4] [5] 6 7 8
-                number_above: 2
+                number_above: 2,
+                flush_possible: false
             })
         );
         assert_eq!(
@@ -893,7 +983,8 @@ mod tests {
                 //Gutshot to 6 [7] 8 9 [T]
                 //Gutshot to [7] 8 9 [T] J
                 //Gutshot Draw to [T] J Q K [A]
-                number_above: 7
+                number_above: 7,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -935,7 +1026,8 @@ mod tests {
                 //other better gut shots made with 5 8, 6 8, 2 5, 2 6
                 //[4] 5 6 [7] 8
                 //3 4 5 6 7
-                number_above: 1
+                number_above: 1,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1001,7 +1093,8 @@ mod tests {
                 //8 9 [T] [J] [Q]
                 //7 8 9* [T] [J]
                 //Everything that beats us are made straights, not draws
-                number_above: 0
+                number_above: 0,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1035,7 +1128,8 @@ mod tests {
             Some(StraightDraw {
                 straight_draw_type: StraightDrawType::DoubleGutShot,
                 //there is one better gut shot, but we only count better open ended draws
-                number_above: 0
+                number_above: 0,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1070,7 +1164,8 @@ mod tests {
                 straight_draw_type: StraightDrawType::OpenEnded,
                 //we are drawing to 6 7 8 9 T
                 //but have an open ended draw 8 T that is better
-                number_above: 1
+                number_above: 1,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1104,7 +1199,8 @@ mod tests {
             Some(StraightDraw {
                 straight_draw_type: StraightDrawType::DoubleGutShot,
                 //we are drawing to 9 T J Q K and 7 8 9 T J
-                number_above: 1
+                number_above: 1,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1139,7 +1235,8 @@ mod tests {
                 straight_draw_type: StraightDrawType::DoubleGutShot,
                 //we are drawing to 6 [7] 8 9* [T] and [4] 5* 6 [7] 8
                 //better straight draws are [7] 8 9 [T] J
-                number_above: 1
+                number_above: 1,
+                flush_possible: false
             })
         );
         assert_eq!(prc.pocket_pair, None);
@@ -1174,7 +1271,8 @@ mod tests {
             prc.flush_draw,
             Some(FlushDraw {
                 hole_card_value: CardValue::Eight,
-                flush_draw_type: FlushDrawType::FlushDraw
+                flush_draw_type: FlushDrawType::FlushDraw,
+                number_above: 5
             })
         );
 
@@ -1199,7 +1297,8 @@ mod tests {
             prc.flush_draw,
             Some(FlushDraw {
                 hole_card_value: CardValue::Ace,
-                flush_draw_type: FlushDrawType::BackdoorFlushDraw
+                flush_draw_type: FlushDrawType::BackdoorFlushDraw,
+                number_above: 0
             })
         );
 
@@ -1214,7 +1313,8 @@ mod tests {
             prc.flush_draw,
             Some(FlushDraw {
                 hole_card_value: CardValue::Ace,
-                flush_draw_type: FlushDrawType::FlushDraw
+                flush_draw_type: FlushDrawType::FlushDraw,
+                number_above: 0
             })
         );
 
@@ -1228,6 +1328,62 @@ mod tests {
         assert_eq!(prc.flush_draw, None);
     }
 
+    #[test]
+    fn test_combo_and_backdoor_draws() {
+        let prc = |hole_cards: &str, board: &str| {
+            partial_rank_cards(
+                &hole_cards.parse().unwrap(),
+                Board::try_from(board).unwrap().as_slice_card(),
+            )
+        };
+
+        //Nut flush draw and open ended, a combo draw
+        let p = prc("Ah 5h", "3h 4d 6h");
+        assert!(p.has_nut_flush_draw());
+        assert!(p.has_combo_draw());
+        assert!(!p.has_pair_and_draw());
+        assert!(!p.backdoor_straight_draw);
+
+        //The ace is on the board, so the king is the nut flush draw
+        let p = prc("Kh Jc", "Ah 7h 2h Td");
+        assert!(p.has_nut_flush_draw());
+        //Needs a Q for the straight, but that could come with a flush
+        assert_eq!(
+            p.straight_draw,
+            Some(StraightDraw {
+                straight_draw_type: StraightDrawType::GutShot(CardValue::Queen),
+                number_above: 0,
+                flush_possible: true
+            })
+        );
+        assert!(p.has_combo_draw());
+
+        //Non nut flush draw with top pair
+        let p = prc("Qh Th", "Qd 7h 2h");
+        assert_eq!(p.flush_draw.unwrap().number_above, 2);
+        assert!(!p.has_nut_flush_draw());
+        assert!(p.has_pair_and_draw());
+        assert!(!p.has_combo_draw());
+
+        //Backdoor straight draw, 3 in J Q K A T
+        let p = prc("Kd Qs", "Jc 5h 2d");
+        assert_eq!(p.straight_draw, None);
+        assert!(p.backdoor_straight_draw);
+        assert!(!p.has_pair_and_draw());
+
+        //Only on the flop, and not when the board has the 3 cards
+        assert!(!prc("Kd Qs", "Jc 5h 2d 8s").backdoor_straight_draw);
+        assert!(!prc("2d 2s", "Jc Qh Kd").backdoor_straight_draw);
+
+        //An open ended draw is not also a backdoor draw
+        let p = prc("9d 8s", "Tc 7h 2d");
+        assert_eq!(
+            p.straight_draw.unwrap().straight_draw_type,
+            StraightDrawType::OpenEnded
+        );
+        assert!(!p.backdoor_straight_draw);
+    }
+
     #[test]
     fn test_overcards() {
         let hole_cards = "Kc 7s".parse().unwrap();
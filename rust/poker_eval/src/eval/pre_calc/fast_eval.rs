@@ -1,14 +1,16 @@
 use boomphf::Mphf;
+use itertools::Itertools;
 //use ph::fmph;
 use std::borrow::Borrow;
 
-use crate::Card;
+use crate::{Card, DeckVariant};
 
 use super::{
     lookup::{LOOKUP, LOOKUP_FLUSH},
     perfect_hash::get_value_bits_for_flush,
-    rank::Rank,
-    CARDS, GLOBAL_SUIT_SHIFT, INITIAL_SUIT_COUNT,
+    rank::{BestHand, Rank, RankEnum},
+    CARDS, CARD_VALUE_MASK, GLOBAL_SUIT_SHIFT, INITIAL_SUIT_COUNT, NUMBER_OF_RANKS,
+    NUMBER_OF_SUITS, RANK_FAMILY_OFFEST,
 };
 
 /*
@@ -78,6 +80,11 @@ where
 {
     let (lookup_key_sum, card_mask) = calc_lookup_key_and_mask(cards);
 
+    eval_lookup_key_and_mask(lookup_key_sum, card_mask, hash_func)
+}
+
+//The lookup key must include the initial suit count, as in calc_lookup_key_and_mask
+fn eval_lookup_key_and_mask(lookup_key_sum: u64, card_mask: u64, hash_func: &Mphf<u32>) -> Rank {
     let flush_lookup_key = get_value_bits_for_flush(lookup_key_sum, card_mask);
 
     let raw_rank = if let Some(flush_lookup) = flush_lookup_key {
@@ -93,13 +100,142 @@ where
     Rank::from(raw_rank)
 }
 
+/*
+The rank of 5 to 7 cards along with the 5 cards making it, so it can be described
+ex: Two Pair, Aces and Kings, Queen kicker
+
+If several 5 card hands have the best rank (the board plays) the first one found is used
+*/
+pub fn fast_best_hand(cards: &[Card], hash_func: &Mphf<u32>) -> BestHand {
+    let rank = fast_hand_eval(cards.iter(), hash_func);
+    let best_five = cards
+        .iter()
+        .combinations(5)
+        .find(|five| fast_hand_eval(five.iter().copied(), hash_func) == rank)
+        .unwrap();
+
+    BestHand::new(
+        rank,
+        [
+            *best_five[0],
+            *best_five[1],
+            *best_five[2],
+            *best_five[3],
+            *best_five[4],
+        ],
+    )
+}
+
+//A 6 7 8 9, the lowest straight in short deck
+const SHORT_DECK_WHEEL_VALUES: u64 = 0x10F0;
+
+/*
+Short deck (no 2s to 5s), the ace also plays low in A 6 7 8 9, which gets the same rank as the 5 high straight (flush)
+since that can't happen without 2s to 5s.
+
+Use Rank::strength to compare the results, since a flush beats a full house
+*/
+pub fn fast_short_deck_hand_eval<I, B>(cards: I, hash_func: &Mphf<u32>) -> Rank
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+{
+    let (lookup_key_sum, card_mask) = calc_lookup_key_and_mask(cards);
+
+    let rank = eval_lookup_key_and_mask(lookup_key_sum, card_mask, hash_func);
+
+    let suit_values: [u64; NUMBER_OF_SUITS] = std::array::from_fn(|suit| {
+        (card_mask >> (suit as u64 * NUMBER_OF_RANKS as u64)) & CARD_VALUE_MASK
+    });
+
+    let wheel_rank = if suit_values
+        .iter()
+        .any(|v| v & SHORT_DECK_WHEEL_VALUES == SHORT_DECK_WHEEL_VALUES)
+    {
+        Rank::from((RankEnum::StraightFlush as u16) << RANK_FAMILY_OFFEST)
+    } else if suit_values.iter().fold(0, |acc, v| acc | v) & SHORT_DECK_WHEEL_VALUES
+        == SHORT_DECK_WHEEL_VALUES
+    {
+        Rank::from((RankEnum::Straight as u16) << RANK_FAMILY_OFFEST)
+    } else {
+        return rank;
+    };
+
+    //Any higher straight or better is already in rank
+    rank.max(wheel_rank)
+}
+
+pub fn fast_hand_eval_for_variant<I, B>(
+    cards: I,
+    hash_func: &Mphf<u32>,
+    deck_variant: DeckVariant,
+) -> Rank
+where
+    I: Iterator<Item = B>,
+    B: Borrow<Card>,
+{
+    match deck_variant {
+        DeckVariant::Standard => fast_hand_eval(cards, hash_func),
+        DeckVariant::ShortDeck => fast_short_deck_hand_eval(cards, hash_func),
+    }
+}
+
+/*
+Omaha must use exactly 2 hole cards and 3 board cards, so this is the best rank of
+every 2 hole card + 3 board card combination.
+
+The lookup keys are sums, so we add up the pairs and the triples once and combine them.
+*/
+pub fn fast_omaha_eval(hole_cards: &[Card], board: &[Card], hash_func: &Mphf<u32>) -> Rank {
+    assert!(hole_cards.len() >= 2 && hole_cards.len() <= 5);
+    assert!(board.len() >= 3 && board.len() <= 5);
+
+    let card_key = |c: &Card| CARDS[c.index as usize];
+
+    let mut hole_pairs = [(0u64, 0u64); 10];
+    let mut num_hole_pairs = 0;
+    for i in 0..hole_cards.len() {
+        for j in i + 1..hole_cards.len() {
+            let (k1, m1) = card_key(&hole_cards[i]);
+            let (k2, m2) = card_key(&hole_cards[j]);
+            hole_pairs[num_hole_pairs] = (k1 + k2, m1 | m2);
+            num_hole_pairs += 1;
+        }
+    }
+
+    let mut board_triples = [(0u64, 0u64); 10];
+    let mut num_board_triples = 0;
+    for i in 0..board.len() {
+        for j in i + 1..board.len() {
+            for k in j + 1..board.len() {
+                let (k1, m1) = card_key(&board[i]);
+                let (k2, m2) = card_key(&board[j]);
+                let (k3, m3) = card_key(&board[k]);
+                board_triples[num_board_triples] = (
+                    (INITIAL_SUIT_COUNT << GLOBAL_SUIT_SHIFT) + k1 + k2 + k3,
+                    m1 | m2 | m3,
+                );
+                num_board_triples += 1;
+            }
+        }
+    }
+
+    let mut best_rank = Rank::lowest_rank();
+    for (hole_key, hole_mask) in hole_pairs[..num_hole_pairs].iter() {
+        for (board_key, board_mask) in board_triples[..num_board_triples].iter() {
+            let rank =
+                eval_lookup_key_and_mask(hole_key + board_key, hole_mask | board_mask, hash_func);
+            best_rank = best_rank.max(rank);
+        }
+    }
+
+    best_rank
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::{
-        eval::pre_calc::{perfect_hash::load_boomperfect_hash, rank::RankEnum},
-        Board,
-    };
+    use crate::{eval::pre_calc::perfect_hash::load_boomperfect_hash, Board, CardValue};
 
     use super::*;
 
@@ -183,6 +319,129 @@ mod tests {
         assert_eq!(rank.get_rank_enum(), RankEnum::Flush);
         assert_eq!(rank.get_kicker(), 0);
     }
+
+    #[test]
+    fn test_best_hand() {
+        let f = load_boomperfect_hash();
+
+        let best_hand = |cards: &str| {
+            let board = Board::try_from(cards).unwrap();
+            let best_hand = fast_best_hand(board.as_slice_card(), &f);
+            assert_eq!(
+                best_hand.rank,
+                fast_hand_eval(board.as_slice_card().iter(), &f)
+            );
+            best_hand
+        };
+
+        let bh = best_hand("Kd 2c Ah Qs 2d Ks Ac");
+        assert_eq!(bh.description(), "Two Pair, Aces and Kings, Queen kicker");
+        assert_eq!(
+            bh.to_string(),
+            "Two Pair, Aces and Kings, Queen kicker [Ah Ac Ks Kd Qs]"
+        );
+
+        assert_eq!(
+            best_hand("7d 5s 2h 3s Jc 9c Kh").description(),
+            "High Card, King, Jack Nine Seven Five kickers"
+        );
+        assert_eq!(
+            best_hand("6d 6s 2h 3s Jc 9c Kh").description(),
+            "One Pair, Sixes, King Jack Nine kickers"
+        );
+        assert_eq!(
+            best_hand("6d 6s 6h 3s Jc").description(),
+            "Trips, Sixes, Jack Three kickers"
+        );
+        let bh = best_hand("2d 5s Qd 3h Kh 4s Ac");
+        assert_eq!(bh.description(), "Straight, Five high");
+        assert_eq!(bh.cards[4].value, CardValue::Ace);
+        assert_eq!(
+            best_hand("Ad 9d Qd Jd 4h 4c Td").description(),
+            "Flush, Ace Queen Jack Ten Nine"
+        );
+        assert_eq!(
+            best_hand("3c 3d Qd 5d 4h 4c 4d").description(),
+            "Full House, Fours full of Threes"
+        );
+        assert_eq!(
+            best_hand("7c 7d 7h 7s Ah Kc Qd").description(),
+            "Quads, Sevens, Ace kicker"
+        );
+        assert_eq!(
+            best_hand("9h Th Jh Qh Kh Ah 2c").description(),
+            "Straight Flush, Ace high"
+        );
+    }
+
+    #[test]
+    fn test_short_deck_eval() {
+        let f = load_boomperfect_hash();
+
+        let short_deck_rank = |cards: &str| {
+            let board = Board::try_from(cards).unwrap();
+            fast_short_deck_hand_eval(board.as_slice_card().iter(), &f)
+        };
+
+        let wheel = short_deck_rank("Ad 6s 7h 8c 9c Kd Kh");
+        assert_eq!(wheel.get_rank_enum(), RankEnum::Straight);
+        assert_eq!(wheel.get_kicker(), 0);
+
+        let six_high = short_deck_rank("Ad 6s 7h 8c 9c Td Kh");
+        assert_eq!(six_high.get_rank_enum(), RankEnum::Straight);
+        assert!(six_high > wheel);
+
+        let straight_flush = short_deck_rank("Ac 6c 7c 8c 9c Td Kh");
+        assert_eq!(straight_flush.get_rank_enum(), RankEnum::StraightFlush);
+        assert_eq!(straight_flush.get_kicker(), 0);
+
+        let full_house = short_deck_rank("Ac Ad As 8c 8d Td Kh");
+        let flush = short_deck_rank("7c 6c Kc 8c 9d Tc Kh");
+        assert_eq!(full_house.get_rank_enum(), RankEnum::FullHouse);
+        assert_eq!(flush.get_rank_enum(), RankEnum::Flush);
+        assert!(full_house > flush);
+        assert!(
+            flush.strength(DeckVariant::ShortDeck) > full_house.strength(DeckVariant::ShortDeck)
+        );
+        assert!(full_house.strength(DeckVariant::Standard) > flush.strength(DeckVariant::Standard));
+        assert!(
+            straight_flush.strength(DeckVariant::ShortDeck)
+                > flush.strength(DeckVariant::ShortDeck)
+        );
+    }
+
+    #[test]
+    fn test_omaha_eval() {
+        let f = load_boomperfect_hash();
+
+        let omaha_rank = |hole_cards: &str, board: &str| {
+            let hole_cards = Board::try_from(hole_cards).unwrap();
+            let board = Board::try_from(board).unwrap();
+            fast_omaha_eval(hole_cards.as_slice_card(), board.as_slice_card(), &f)
+        };
+
+        //Only 1 heart in hand, no flush even with 4 on the board
+        let rank = omaha_rank("Ah Kc Qd Js", "2h 5h 8h Th 3c");
+        assert_eq!(rank.get_rank_enum(), RankEnum::HighCard);
+
+        //4 to a straight flush on the board is still nothing special with 1 card
+        let rank = omaha_rank("Ah Ac Kd Ks", "Qh Jh Th 9h 2c");
+        assert_eq!(rank.get_rank_enum(), RankEnum::Straight);
+
+        //Quads on the board only plays as trips + 2 hole cards
+        let rank = omaha_rank("Ah Kc 7d 2s", "9h 9c 9d 9s 3c");
+        assert_eq!(rank.get_rank_enum(), RankEnum::ThreeOfAKind);
+
+        let rank = omaha_rank("Ah Kh 7d 2s 3s", "9h 4h 5h 9s 3c");
+        assert_eq!(rank.get_rank_enum(), RankEnum::Flush);
+
+        //Same as hold'em when hold'em also uses 2 hole cards and 3 board cards
+        let rank = omaha_rank("Ah Kh 7d 2s", "Qh Jh Th");
+        assert_eq!(
+            rank,
+            fast_hand_eval(Board::try_from("Ah Kh Qh Jh Th").unwrap().get_iter(), &f)
+        );
+    }
 }
 
 /*
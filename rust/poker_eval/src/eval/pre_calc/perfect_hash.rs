@@ -1,4 +1,5 @@
 use boomphf::Mphf;
+use once_cell::sync::Lazy;
 //use log::info;
 // #[cfg(not(target_arch = "wasm32"))]
 // use ph::fmph;
@@ -146,6 +147,9 @@ pub fn load_boomperfect_hash() -> Mphf<u32> {
     deserialized_data
 }
 
+//Loading takes a while, so code that isn't passed a hash shares this one
+pub static BOOM_PERFECT_HASH: Lazy<Mphf<u32>> = Lazy::new(load_boomperfect_hash);
+
 // #[cfg(not(target_arch = "wasm32"))]
 // pub fn load_perfect_hash() -> fmph::Function {
 //     let path = get_perfect_hash_path();
@@ -1,5 +1,10 @@
 use std::fmt::{Display, Formatter};
 
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{Card, CardValue, DeckVariant};
+
 use super::RANK_FAMILY_OFFEST;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -25,7 +30,7 @@ impl Rank {
         }
     }
 
-    //The rest of the bits     
+    //The rest of the bits
     pub fn get_kicker(&self) -> u16 {
         let kicker_mask = (1 << RANK_FAMILY_OFFEST) - 1;
 
@@ -35,9 +40,22 @@ impl Rank {
     pub fn lowest_rank() -> Self {
         Self { raw_rank: 0 }
     }
+
+    //Compare these instead of the ranks to find the winner, in short deck a flush beats a full house
+    pub fn strength(&self, deck_variant: DeckVariant) -> u16 {
+        match (deck_variant, self.get_rank_enum()) {
+            (DeckVariant::ShortDeck, RankEnum::Flush) => {
+                ((RankEnum::FullHouse as u16) << RANK_FAMILY_OFFEST) | self.get_kicker()
+            }
+            (DeckVariant::ShortDeck, RankEnum::FullHouse) => {
+                ((RankEnum::Flush as u16) << RANK_FAMILY_OFFEST) | self.get_kicker()
+            }
+            _ => self.raw_rank,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum RankEnum {
     //0
@@ -75,3 +93,98 @@ impl Display for RankEnum {
         write!(f, "{}", s)
     }
 }
+
+/*
+The 5 cards making a Rank, from fast_best_hand.
+
+Cards are in order of importance, ex: the pair then the kickers highest first,
+and a 5 high straight is 5 4 3 2 A
+*/
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BestHand {
+    pub rank: Rank,
+    pub cards: [Card; 5],
+}
+
+fn value_name(value: CardValue) -> String {
+    format!("{:?}", value)
+}
+
+fn value_plural(value: CardValue) -> String {
+    match value {
+        CardValue::Six => "Sixes".to_string(),
+        _ => format!("{:?}s", value),
+    }
+}
+
+fn kickers(cards: &[Card]) -> String {
+    let names = cards.iter().map(|c| value_name(c.value)).join(" ");
+    match cards.len() {
+        0 => "".to_string(),
+        1 => format!(", {} kicker", names),
+        _ => format!(", {} kickers", names),
+    }
+}
+
+impl BestHand {
+    pub(crate) fn new(rank: Rank, mut cards: [Card; 5]) -> Self {
+        let count = |value: CardValue| cards.iter().filter(|c| c.value == value).count();
+        let counts = cards.map(|c| count(c.value));
+        let mut order = [0, 1, 2, 3, 4];
+        order.sort_by_key(|i| std::cmp::Reverse((counts[*i], cards[*i].value, cards[*i].suit)));
+        cards = order.map(|i| cards[i]);
+
+        //The ace plays low in the wheel
+        if matches!(
+            rank.get_rank_enum(),
+            RankEnum::Straight | RankEnum::StraightFlush
+        ) && cards[0].value == CardValue::Ace
+            && cards[1].value == CardValue::Five
+        {
+            cards.rotate_left(1);
+        }
+
+        BestHand { rank, cards }
+    }
+
+    //ex: Two Pair, Aces and Kings, Queen kicker
+    pub fn description(&self) -> String {
+        let c = &self.cards;
+        let rank_enum = self.rank.get_rank_enum();
+        let details = match rank_enum {
+            RankEnum::HighCard => format!("{}{}", value_name(c[0].value), kickers(&c[1..])),
+            RankEnum::OnePair => format!("{}{}", value_plural(c[0].value), kickers(&c[2..])),
+            RankEnum::TwoPair => format!(
+                "{} and {}{}",
+                value_plural(c[0].value),
+                value_plural(c[2].value),
+                kickers(&c[4..])
+            ),
+            RankEnum::ThreeOfAKind => {
+                format!("{}{}", value_plural(c[0].value), kickers(&c[3..]))
+            }
+            RankEnum::Straight | RankEnum::StraightFlush => {
+                format!("{} high", value_name(c[0].value))
+            }
+            RankEnum::Flush => c.iter().map(|c| value_name(c.value)).join(" "),
+            RankEnum::FullHouse => format!(
+                "{} full of {}",
+                value_plural(c[0].value),
+                value_plural(c[3].value)
+            ),
+            RankEnum::FourOfAKind => format!("{}{}", value_plural(c[0].value), kickers(&c[4..])),
+        };
+        format!("{}, {}", rank_enum, details)
+    }
+}
+
+impl Display for BestHand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}]",
+            self.description(),
+            self.cards.iter().map(|c| c.to_string()).join(" ")
+        )
+    }
+}
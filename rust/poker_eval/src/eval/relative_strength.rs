@@ -0,0 +1,184 @@
+use boomphf::Mphf;
+use itertools::Itertools;
+
+use crate::{
+    deal_cards_used,
+    pre_calc::{fast_eval::fast_hand_eval, rank::Rank},
+    BoolRange, Card, HoleCards, PokerError, ALL_HOLE_CARDS,
+};
+
+/*
+Where our made hand ranks among the other hands on the board, no cards to come.
+
+Unlike the rules in likes_hands (made_set_with_n_above and friends), this ranks every combo
+with fast_hand_eval so it is exact for any board
+*/
+#[derive(Debug, Clone)]
+pub struct RelativeHandStrength {
+    pub rank: Rank,
+
+    //Combos (in the range if one was given) that don't use a board card or our hole cards
+    pub better_combos: Vec<HoleCards>,
+    pub tied_combos: Vec<HoleCards>,
+    pub num_worse: usize,
+
+    //Best hand anyone could have on the board, ignoring the range, and every combo making it
+    //which includes our hole cards if we have the nuts
+    pub nut_rank: Rank,
+    pub nut_combos: Vec<HoleCards>,
+}
+
+impl RelativeHandStrength {
+    pub fn num_combos(&self) -> usize {
+        self.better_combos.len() + self.tied_combos.len() + self.num_worse
+    }
+
+    //Fraction of combos we beat, ties count half, so 1.0 is the nuts and 0.0 the worst hand
+    pub fn percentile(&self) -> f64 {
+        if self.num_combos() == 0 {
+            return 1.0;
+        }
+        (self.num_worse as f64 + self.tied_combos.len() as f64 / 2.0) / self.num_combos() as f64
+    }
+
+    pub fn is_nuts(&self) -> bool {
+        self.rank == self.nut_rank
+    }
+}
+
+/*
+Ranks the hole cards against all remaining combos, or only those in range when given.
+
+The board must have 3 to 5 cards
+*/
+pub fn calc_relative_strength(
+    hole_cards: &HoleCards,
+    board: &[Card],
+    range: Option<&BoolRange>,
+    hash_func: &Mphf<u32>,
+) -> Result<RelativeHandStrength, PokerError> {
+    if board.len() < 3 || board.len() > 5 {
+        return Err(PokerError::from_string(format!(
+            "Need a flop, turn or river, not {} board cards",
+            board.len()
+        )));
+    }
+
+    let cards_used = deal_cards_used(board, hole_cards)?;
+
+    let eval = |hc: &HoleCards| fast_hand_eval(board.iter().chain(hc.as_slice().iter()), hash_func);
+
+    let rank = eval(hole_cards);
+    let mut better_combos = Vec::new();
+    let mut tied_combos = Vec::new();
+    let mut num_worse = 0;
+    let mut nut_rank = rank;
+    let mut nut_combos = vec![*hole_cards];
+
+    for hc in ALL_HOLE_CARDS.iter() {
+        if cards_used[hc.get_hi_card().index as usize]
+            || cards_used[hc.get_lo_card().index as usize]
+        {
+            continue;
+        }
+        let hc_rank = eval(hc);
+
+        if hc_rank > nut_rank {
+            nut_rank = hc_rank;
+            nut_combos.clear();
+        }
+        if hc_rank == nut_rank {
+            nut_combos.push(*hc);
+        }
+
+        if range.is_some_and(|r| !r.data[hc.to_range_index()]) {
+            continue;
+        }
+        match hc_rank.cmp(&rank) {
+            std::cmp::Ordering::Greater => better_combos.push(*hc),
+            std::cmp::Ordering::Equal => tied_combos.push(*hc),
+            std::cmp::Ordering::Less => num_worse += 1,
+        }
+    }
+
+    //Strongest first
+    let better_combos = better_combos
+        .into_iter()
+        .sorted_by_key(|hc| std::cmp::Reverse(eval(hc)))
+        .collect_vec();
+
+    Ok(RelativeHandStrength {
+        rank,
+        better_combos,
+        tied_combos,
+        num_worse,
+        nut_rank,
+        nut_combos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval_test_deal, pre_calc::perfect_hash::load_boomperfect_hash, Board};
+
+    use super::*;
+
+    fn calc(hole_cards: &str, board: &str, range: Option<&str>) -> RelativeHandStrength {
+        let range: Option<BoolRange> = range.map(|r| r.parse().unwrap());
+        eval_test_deal(hole_cards, board, |hole_cards, board, hash_func| {
+            calc_relative_strength(hole_cards, board, range.as_ref(), hash_func)
+        })
+    }
+
+    #[test]
+    fn test_relative_strength() {
+        //Bottom set, beaten only by the 4 higher sets
+        let rs = calc("2s2d", "Kh 7c 2c Jd 3s", None);
+        //52 - 5 - 2 = 45 cards left
+        assert_eq!(rs.num_combos(), 45 * 44 / 2);
+        assert_eq!(rs.better_combos.len(), 3 + 3 + 3 + 3);
+        assert!(rs.tied_combos.is_empty());
+        assert_eq!(rs.percentile(), 1.0 - 12.0 / 990.0);
+        assert!(!rs.is_nuts());
+        //Set of kings
+        assert_eq!(rs.nut_combos.len(), 3);
+        assert!(rs.better_combos[..3]
+            .iter()
+            .all(|hc| rs.nut_combos.contains(hc)));
+
+        //Within a range it's a different story
+        let rs = calc("2s2d", "Kh 7c 2c Jd 3s", Some("KK,JJ,77,AK"));
+        assert_eq!(rs.better_combos.len(), 9);
+        assert_eq!(rs.num_worse, 4 * 3);
+        assert!(rs.percentile() < 0.6);
+
+        //Nut flush, tied by nobody
+        let rs = calc("AsKs", "Qs 7s 2s", None);
+        assert!(rs.is_nuts());
+        assert_eq!(rs.nut_combos, vec!["AsKs".parse().unwrap()]);
+        assert!(rs.better_combos.is_empty());
+        assert_eq!(rs.percentile(), 1.0);
+
+        //Board plays, everyone ties
+        let rs = calc("2c3d", "As Ks Qs Js Ts", Some("AA,KK"));
+        assert_eq!(rs.tied_combos.len(), 3 + 3);
+        assert_eq!(rs.percentile(), 0.5);
+        assert!(rs.is_nuts());
+
+        let hash_func = load_boomperfect_hash();
+        assert!(calc_relative_strength(
+            &"AsKs".parse().unwrap(),
+            Board::try_from("Qs 7s").unwrap().as_slice_card(),
+            None,
+            &hash_func
+        )
+        .is_err());
+        assert!(calc_relative_strength(
+            &"AsKs".parse().unwrap(),
+            Board::try_from("As 7s 2d").unwrap().as_slice_card(),
+            None,
+            &hash_func
+        )
+        .is_err());
+    }
+}
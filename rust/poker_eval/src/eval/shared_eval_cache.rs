@@ -0,0 +1,166 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Mutex,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    board_eval_cache_redb::{get_put_canonical_board, ProduceEvalResult},
+    board_hc_eval_cache_redb::{get_put_canonical_with_hc, EvalWithHcKey, ProduceEvalWithHcResult},
+    Board, HoleCards, InMemoryBackend, LruBackend, PokerError, SharedEvalCacheBackend,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::BatchedReDb;
+
+//Same as EvalCache but shareable between threads, wrap it in an Arc
+pub struct SharedEvalCache<P: ProduceEvalResult> {
+    backend: Box<dyn SharedEvalCacheBackend<u32, P::Result>>,
+    cache_hits: AtomicU32,
+    cache_misses: AtomicU32,
+}
+
+impl<P> SharedEvalCache<P>
+where
+    P: ProduceEvalResult,
+    P::Result: Clone + Send + 'static,
+{
+    pub fn new(backend: Box<dyn SharedEvalCacheBackend<u32, P::Result>>) -> Self {
+        Self {
+            backend,
+            cache_hits: AtomicU32::new(0),
+            cache_misses: AtomicU32::new(0),
+        }
+    }
+
+    pub fn new_in_memory() -> Self {
+        Self::new(Box::new(Mutex::new(InMemoryBackend::new())))
+    }
+
+    pub fn new_lru(capacity: usize) -> Self {
+        Self::new(Box::new(Mutex::new(LruBackend::new(capacity))))
+    }
+
+    pub fn get_put(&self, board: &Board) -> Result<P::Result, PokerError> {
+        let (result, is_hit) = get_put_canonical_board::<P>(&mut self.backend.as_ref(), board)?;
+        self.count(is_hit);
+        Ok(result)
+    }
+
+    fn count(&self, is_hit: bool) {
+        if is_hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn flush(&self) -> Result<(), PokerError> {
+        self.backend.flush()
+    }
+
+    pub fn cache_hits(&self) -> u32 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_misses(&self) -> u32 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<P> SharedEvalCache<P>
+where
+    P: ProduceEvalResult,
+    P::Result: Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+    //Misses are written batch_size at a time
+    pub fn new_redb(batch_size: usize) -> Result<Self, PokerError> {
+        Ok(Self::new(Box::new(BatchedReDb::<u32>::new(
+            P::get_cache_name(),
+            batch_size,
+        )?)))
+    }
+}
+
+//Same as EvalCacheWithHc but shareable between threads, wrap it in an Arc
+pub struct SharedEvalCacheWithHc<P: ProduceEvalWithHcResult> {
+    backend: Box<dyn SharedEvalCacheBackend<EvalWithHcKey, P::Result>>,
+    cache_hits: AtomicU32,
+    cache_misses: AtomicU32,
+}
+
+impl<P> SharedEvalCacheWithHc<P>
+where
+    P: ProduceEvalWithHcResult,
+    P::Result: Clone + Send + 'static,
+{
+    pub fn new(backend: Box<dyn SharedEvalCacheBackend<EvalWithHcKey, P::Result>>) -> Self {
+        Self {
+            backend,
+            cache_hits: AtomicU32::new(0),
+            cache_misses: AtomicU32::new(0),
+        }
+    }
+
+    pub fn new_in_memory() -> Self {
+        Self::new(Box::new(Mutex::new(InMemoryBackend::new())))
+    }
+
+    pub fn new_lru(capacity: usize) -> Self {
+        Self::new(Box::new(Mutex::new(LruBackend::new(capacity))))
+    }
+
+    pub fn get_put(
+        &self,
+        cards: &Board,
+        hole_cards: &HoleCards,
+        num_players: u8,
+    ) -> Result<P::Result, PokerError> {
+        let (result, is_hit) = get_put_canonical_with_hc::<P>(
+            &mut self.backend.as_ref(),
+            cards,
+            hole_cards,
+            num_players,
+        )?;
+        self.count(is_hit);
+        Ok(result)
+    }
+
+    fn count(&self, is_hit: bool) {
+        if is_hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn flush(&self) -> Result<(), PokerError> {
+        self.backend.flush()
+    }
+
+    pub fn cache_hits(&self) -> u32 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_misses(&self) -> u32 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<P> SharedEvalCacheWithHc<P>
+where
+    P: ProduceEvalWithHcResult,
+    P::Result: Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+    //Misses are written batch_size at a time
+    pub fn new_redb(batch_size: usize) -> Result<Self, PokerError> {
+        Ok(Self::new(Box::new(BatchedReDb::<EvalWithHcKey>::new(
+            P::get_cache_name(),
+            batch_size,
+        )?)))
+    }
+}
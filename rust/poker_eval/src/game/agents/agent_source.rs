@@ -16,6 +16,9 @@ pub struct AgentSource {
 
     //depending on the game, maybe this is 0, 3, 4, 5 cards
     pub board: Vec<Card>,
+
+    //The seed of the deck the hole cards & board were dealt from
+    pub seed: Option<u64>,
     //store results
     //pub final_stacks: Vec<ChipType>,
 }
@@ -78,6 +81,10 @@ impl GameRunnerSource for AgentSource {
         //agent.set_final_player_state(player_state, comment)
         Ok(())
     }
+
+    fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,152 @@
-use crate::{Card, Deck, HoleCards, InitialPlayerState, Position};
+use std::sync::{Arc, Mutex};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    likes_hand_rules::LikesHandRules,
+    likes_hands::{likes_hand, LikesHandResponse},
+    monte_carlo_equity::calc_equity_with_method,
+    narrow_range::{narrow_range_by_line, RangeNarrowingCache, StreetAction},
+    pre_calc::perfect_hash::BOOM_PERFECT_HASH,
+    ActionEnum, Board, BoardTexture, BoolRange, Card, Deck, FastEvaluator, GameState,
+    HandEvaluator, HoleCards, InitialPlayerState, PartialRankContainer, PokerError, Position,
+    Round, WeightedRange, DEFAULT_SEED,
+};
 
 use super::Agent;
 
+const LINE_EQUITY_SIMULATIONS: usize = 1_000;
+
+//How the agents judge their hand, the same for every agent
+pub struct AgentHandEval {
+    evaluator: Box<dyn HandEvaluator>,
+    likes_hand_rules: Option<Arc<LikesHandRules>>,
+    range_narrowing_cache: Option<Arc<Mutex<RangeNarrowingCache>>>,
+}
+
+impl Default for AgentHandEval {
+    fn default() -> Self {
+        AgentHandEval {
+            evaluator: Box::<FastEvaluator>::default(),
+            likes_hand_rules: None,
+            range_narrowing_cache: None,
+        }
+    }
+}
+
+impl AgentHandEval {
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn HandEvaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    //Use a rule file instead of the hard coded likes_hand
+    pub fn set_likes_hand_rules(&mut self, likes_hand_rules: Arc<LikesHandRules>) {
+        self.likes_hand_rules = Some(likes_hand_rules);
+    }
+
+    pub fn likes_hand(
+        &self,
+        prc: &PartialRankContainer,
+        ft: &BoardTexture,
+        board: &Board,
+        hc: &HoleCards,
+        num_in_pot: u8,
+    ) -> Result<LikesHandResponse, PokerError> {
+        let mut eval_cards = board.as_slice_card().to_vec();
+        eval_cards.extend(hc.get_iter());
+        let rank_family = self.evaluator.evaluate(&eval_cards).family;
+
+        match self.likes_hand_rules.as_ref() {
+            Some(rules) => rules.likes_hand(prc, ft, rank_family, board, hc, num_in_pot),
+            None => likes_hand(prc, ft, rank_family, board, hc, num_in_pot),
+        }
+    }
+
+    //Narrow the other players' ranges by how they bet, the cache can be shared by all the agents
+    pub fn set_range_narrowing_cache(
+        &mut self,
+        range_narrowing_cache: Arc<Mutex<RangeNarrowingCache>>,
+    ) {
+        self.range_narrowing_cache = Some(range_narrowing_cache);
+    }
+
+    /*
+    Equity vs. each other player still in the hand holding the hands that would have bet
+    like the other players did on each street so far.
+
+    None without a range narrowing cache, or if no hand fits the line
+    */
+    pub fn equity_vs_line(
+        &self,
+        game_state: &GameState,
+        hero_index: usize,
+        hc: &HoleCards,
+    ) -> Result<Option<f64>, PokerError> {
+        let range_narrowing_cache = match self.range_narrowing_cache.as_ref() {
+            Some(range_narrowing_cache) => range_narrowing_cache,
+            None => return Ok(None),
+        };
+
+        let mut range_narrowing_cache = range_narrowing_cache
+            .lock()
+            .map_err(|_| PokerError::from_str("Range narrowing cache lock poisoned"))?;
+        let num_players = game_state.num_non_folded_players();
+        let narrowed_ranges = narrow_range_by_line(
+            &BoolRange::new().complement(),
+            game_state.board.as_slice_card(),
+            &opponent_line(game_state, hero_index),
+            num_players,
+            &BOOM_PERFECT_HASH,
+            &mut range_narrowing_cache,
+        )?;
+        //Other agents can narrow while this one simulates
+        drop(range_narrowing_cache);
+
+        let opponent_range = narrowed_ranges
+            .last()
+            .unwrap()
+            .without_cards(&[hc.get_hi_card(), hc.get_lo_card()]);
+        if opponent_range.get_num_combos() == 0 {
+            return Ok(None);
+        }
+
+        let mut hero_range = WeightedRange::default();
+        hero_range.data[hc.to_range_index()] = 1.0;
+        let mut ranges = vec![hero_range];
+        ranges.extend((1..num_players).map(|_| WeightedRange::from(&opponent_range)));
+
+        let result = calc_equity_with_method(
+            &game_state.board,
+            &ranges,
+            LINE_EQUITY_SIMULATIONS,
+            StdRng::seed_from_u64(DEFAULT_SEED),
+        )?;
+        Ok(Some(result.equities[0]))
+    }
+}
+
+//The largest bet or raise by the other players on each street from the flop to the current one
+fn opponent_line(game_state: &GameState, hero_index: usize) -> Vec<StreetAction> {
+    [Round::Flop, Round::Turn, Round::River]
+        .into_iter()
+        .filter(|round| *round <= game_state.current_round)
+        .map(|round| {
+            game_state
+                .actions
+                .iter()
+                .filter(|a| a.round == round && a.player_index != hero_index)
+                .map(|a| match a.action {
+                    ActionEnum::Bet(amount) => StreetAction::from_bet(amount, a.pot),
+                    ActionEnum::Raise(_, raise_to) => StreetAction::from_bet(raise_to, a.pot),
+                    _ => StreetAction::NoBet,
+                })
+                .max()
+                .unwrap_or(StreetAction::NoBet)
+        })
+        .collect_vec()
+}
+
 pub fn set_agent_hole_cards(deck: &mut Deck, agents: &mut Vec<Box<dyn Agent>>) {
     for agent_index in 0..agents.len() {
         let agent = &mut agents[agent_index];
@@ -40,3 +185,66 @@ pub fn build_initial_players_from_agents(agents: &[Box<dyn Agent>]) -> Vec<Initi
 
     players
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PlayerAction, PlayerState};
+
+    #[test]
+    fn test_equity_vs_line() {
+        let player_states = (0..2)
+            .map(|pos| PlayerState {
+                stack: 400,
+                position: pos.try_into().unwrap(),
+                player_name: format!("Player {}", pos),
+                initial_stack: 500,
+                ..Default::default()
+            })
+            .collect_vec();
+
+        //Villian bets more than the pot on the flop
+        let game_state = GameState {
+            player_states,
+            current_to_act: 1.try_into().unwrap(),
+            current_round: Round::Flop,
+            prev_round_pot: 60,
+            round_pot: 100,
+            current_to_call: 100,
+            min_raise: 100,
+            board: "Ah Kd 7c".parse().unwrap(),
+            sb: 2,
+            bb: 5,
+            actions: vec![PlayerAction {
+                player_index: 0,
+                action: ActionEnum::Bet(100),
+                round: Round::Flop,
+                pot: 60,
+                ..Default::default()
+            }],
+            num_left_to_act: 1,
+            total_active_players: 2,
+            total_players_all_in: 0,
+        };
+        assert_eq!(opponent_line(&game_state, 1), vec![StreetAction::LargeBet]);
+        assert_eq!(opponent_line(&game_state, 0), vec![StreetAction::NoBet]);
+
+        let mut hand_eval = AgentHandEval::default();
+        let set: HoleCards = "7d 7s".parse().unwrap();
+        assert_eq!(hand_eval.equity_vs_line(&game_state, 1, &set).unwrap(), None);
+
+        let range_narrowing_cache = Arc::new(Mutex::new(RangeNarrowingCache::new()));
+        hand_eval.set_range_narrowing_cache(range_narrowing_cache.clone());
+
+        let set_eq = hand_eval.equity_vs_line(&game_state, 1, &set).unwrap().unwrap();
+        let air: HoleCards = "3c 2d".parse().unwrap();
+        let air_eq = hand_eval.equity_vs_line(&game_state, 1, &air).unwrap().unwrap();
+        assert!(set_eq > 0.6, "{}", set_eq);
+        assert!(air_eq < 0.1, "{}", air_eq);
+
+        //Both hands were checked against the same continuing range
+        let range_narrowing_cache = range_narrowing_cache.lock().unwrap();
+        assert_eq!(range_narrowing_cache.len(), 1);
+        assert_eq!(range_narrowing_cache.cache_hits, 1);
+    }
+}
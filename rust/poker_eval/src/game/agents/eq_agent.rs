@@ -1,19 +1,14 @@
-use std::{cell::RefCell, cmp::min, rc::Rc};
-
-use boomphf::Mphf;
+use std::{cmp::min, sync::Arc};
 
 use crate::{
-    board_eval_cache_redb::{EvalCacheReDb, ProduceFlopTexture},
-    board_hc_eval_cache_redb::{
-        EvalCacheWithHcReDb, ProduceMonteCarloEval, ProducePartialRankCards,
-    },
-    likes_hands::likes_hand,
+    board_eval_cache_redb::ProduceFlopTexture,
+    board_hc_eval_cache_redb::{ProduceMonteCarloEval, ProducePartialRankCards},
     monte_carlo_equity::get_equivalent_hole_board,
-    pre_calc::{fast_eval::fast_hand_eval, perfect_hash::load_boomperfect_hash},
     ActionEnum, BoolRange, CommentedAction, GameState, HoleCards, PlayerState, Round,
+    SharedEvalCache, SharedEvalCacheWithHc,
 };
 
-use super::Agent;
+use super::{Agent, AgentHandEval};
 
 //Need some config struct, for like eq to raise, call
 //For now just constants
@@ -69,10 +64,10 @@ impl EqAgentConfig {
 pub struct EqAgent {
     pub hole_cards: Option<HoleCards>,
     pub name: String,
-    flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-    partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
-    monte_carlo_db: Rc<RefCell<EvalCacheWithHcReDb<ProduceMonteCarloEval>>>,
-    hash_func: Mphf<u32>,
+    flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+    partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
+    monte_carlo_db: Arc<SharedEvalCacheWithHc<ProduceMonteCarloEval>>,
+    pub hand_eval: AgentHandEval,
     agent_config: EqAgentConfig,
 }
 
@@ -80,9 +75,9 @@ impl EqAgent {
     pub fn new(
         name: &str,
         agent_config: EqAgentConfig,
-        flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-        partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
-        monte_carlo_db: Rc<RefCell<EvalCacheWithHcReDb<ProduceMonteCarloEval>>>,
+        flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+        partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
+        monte_carlo_db: Arc<SharedEvalCacheWithHc<ProduceMonteCarloEval>>,
     ) -> Self {
         
 
@@ -93,7 +88,7 @@ impl EqAgent {
             partial_rank_db,
             flop_texture_db,
             monte_carlo_db,
-            hash_func: load_boomperfect_hash(),
+            hand_eval: AgentHandEval::default(),
             agent_config,
         }
     }
@@ -107,25 +102,19 @@ impl EqAgent {
         let players_at_round_start = game_state.num_players_at_round_start();
 
         let hole_cards = self.hole_cards.as_ref().unwrap();
-        let mut pr_db = self.partial_rank_db.borrow_mut();
-        let prc = pr_db.get_put(&game_state.board, hole_cards, 0).unwrap();
-        let mut ft_db = self.flop_texture_db.borrow_mut();
-        let ft = ft_db.get_put(&game_state.board).unwrap();
-
-        let rank = fast_hand_eval(
-            game_state.board.get_iter().chain(hole_cards.get_iter()),
-            &self.hash_func,
-        );
-
-        let likes_hand_response = likes_hand(
-            &prc,
-            &ft,
-            &rank,
-            &game_state.board,
-            &hole_cards,
-            players_at_round_start,
-        )
-        .unwrap();
+        let prc = self.partial_rank_db.get_put(&game_state.board, hole_cards, 0).unwrap();
+        let ft = self.flop_texture_db.get_put(&game_state.board).unwrap();
+
+        let likes_hand_response = self
+            .hand_eval
+            .likes_hand(
+                &prc,
+                &ft,
+                &game_state.board,
+                hole_cards,
+                players_at_round_start,
+            )
+            .unwrap();
 
         let (eq_hole_cards, mut eq_board) =
             get_equivalent_hole_board(&hole_cards, game_state.board.as_slice_card());
@@ -136,10 +125,16 @@ impl EqAgent {
 
         let eq = self
             .monte_carlo_db
-            .borrow_mut()
             .get_put(&eq_board, &eq_hole_cards, players_at_round_start)
             .unwrap();
 
+        //Better than vs. random hands if we know how the others bet
+        let eq = self
+            .hand_eval
+            .equity_vs_line(game_state, player_state.player_index(), hole_cards)
+            .unwrap()
+            .unwrap_or(eq);
+
         let call_amt = min(
             game_state.current_to_call - player_state.cur_round_putting_in_pot.unwrap_or(0),
             player_state.stack,
@@ -1,3 +1,5 @@
+//Feeds the agents to a GameRunner, which needs the file system
+#[cfg(not(target_arch = "wasm32"))]
 mod agent_source;
 mod agent_trait;
 #[allow(dead_code)]
@@ -10,6 +12,7 @@ mod tag;
 
 mod eq_agent;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub use agent_source::*;
 pub use agent_trait::*;
 pub use agent_util::*;
@@ -1,33 +1,31 @@
-use std::{cell::RefCell, rc::Rc};
-
-use boomphf::Mphf;
+use std::sync::Arc;
 
 use crate::{
-    board_eval_cache_redb::{EvalCacheReDb, ProduceFlopTexture},
-    board_hc_eval_cache_redb::{EvalCacheWithHcReDb, ProducePartialRankCards},
-    likes_hands::{likes_hand, LikesHandLevel},
-    pre_calc::{fast_eval::fast_hand_eval, perfect_hash::load_boomperfect_hash},
+    board_eval_cache_redb::ProduceFlopTexture,
+    board_hc_eval_cache_redb::ProducePartialRankCards,
+    likes_hands::LikesHandLevel,
     ActionEnum, BoolRange, CommentedAction, GameState, HoleCards, PlayerState, Round,
+    SharedEvalCache, SharedEvalCacheWithHc,
 };
 
-use super::Agent;
+use super::{Agent, AgentHandEval};
 
 //#[derive(Default)]
 pub struct PassiveCallingStation {
     pub calling_range: Option<BoolRange>,
     pub hole_cards: Option<HoleCards>,
     pub name: String,
-    flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-    partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
-    hash_func: Mphf<u32>,
+    flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+    partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
+    pub hand_eval: AgentHandEval,
 }
 
 impl PassiveCallingStation {
     pub fn new(
         calling_range_str: Option<&str>,
         name: &str,
-        flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-        partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
+        flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+        partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
     ) -> Self {
         let calling_range = match calling_range_str {
             Some(s) => Some(s.parse().unwrap()),
@@ -40,7 +38,7 @@ impl PassiveCallingStation {
             name: name.to_string(),
             partial_rank_db,
             flop_texture_db,
-            hash_func: load_boomperfect_hash(),
+            hand_eval: AgentHandEval::default(),
         }
     }
 
@@ -58,17 +56,13 @@ impl PassiveCallingStation {
 
         //We'll call with literally anything interesting
         let hc = self.hole_cards.as_ref().unwrap();
-        let mut pr_db = self.partial_rank_db.borrow_mut();
-        let prc = pr_db.get_put(&game_state.board, hc, 0).unwrap();
-        let mut ft_db = self.flop_texture_db.borrow_mut();
-        let ft = ft_db.get_put(&game_state.board).unwrap();
-
-        let rank = fast_hand_eval(
-            game_state.board.get_iter().chain(hc.get_iter()),
-            &self.hash_func,
-        );
+        let prc = self.partial_rank_db.get_put(&game_state.board, hc, 0).unwrap();
+        let ft = self.flop_texture_db.get_put(&game_state.board).unwrap();
 
-        let likes_hand_response = likes_hand(&prc, &ft, &rank, &game_state.board, &hc, 4).unwrap();
+        let likes_hand_response = self
+            .hand_eval
+            .likes_hand(&prc, &ft, &game_state.board, hc, 4)
+            .unwrap();
 
         let half_pot = game_state.pot() / 2;
 
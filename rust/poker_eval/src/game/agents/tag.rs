@@ -1,16 +1,14 @@
-use std::{cell::RefCell, cmp::min, rc::Rc};
-
-use boomphf::Mphf;
+use std::{cmp::min, sync::Arc};
 
 use crate::{
-    board_eval_cache_redb::{EvalCacheReDb, ProduceFlopTexture},
-    board_hc_eval_cache_redb::{EvalCacheWithHcReDb, ProducePartialRankCards},
-    likes_hands::{likes_hand, LikesHandLevel, LikesHandResponse},
-    pre_calc::{fast_eval::fast_hand_eval, perfect_hash::load_boomperfect_hash},
+    board_eval_cache_redb::ProduceFlopTexture,
+    board_hc_eval_cache_redb::ProducePartialRankCards,
+    likes_hands::{LikesHandLevel, LikesHandResponse},
     ActionEnum, BoolRange, CommentedAction, GameState, HoleCards, PlayerState, Round,
+    SharedEvalCache, SharedEvalCacheWithHc,
 };
 
-use super::Agent;
+use super::{Agent, AgentHandEval};
 
 //#[derive(Default)]
 pub struct Tag {
@@ -18,9 +16,9 @@ pub struct Tag {
     pub pfr_range: BoolRange,
     pub hole_cards: Option<HoleCards>,
     pub name: String,
-    flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-    partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
-    hash_func: Mphf<u32>,
+    flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+    partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
+    pub hand_eval: AgentHandEval,
 }
 
 impl Tag {
@@ -28,8 +26,8 @@ impl Tag {
         three_bet_range_str: &str,
         pfr_range_str: &str,
         name: &str,
-        flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-        partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
+        flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+        partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
     ) -> Self {
         Tag {
             three_bet_range: three_bet_range_str.parse().unwrap(),
@@ -38,7 +36,7 @@ impl Tag {
             name: name.to_string(),
             flop_texture_db,
             partial_rank_db,
-            hash_func: load_boomperfect_hash(),
+            hand_eval: AgentHandEval::default(),
         }
     }
 
@@ -129,19 +127,20 @@ impl Tag {
         let non_folded_players = game_state.num_non_folded_players();
 
         let hc = self.hole_cards.as_ref().unwrap();
-        let mut pdb = self.partial_rank_db.borrow_mut();
-        let prc = pdb.get_put(&game_state.board, hc, 0).unwrap();
-
-        let mut ft_db = self.flop_texture_db.borrow_mut();
-        let ft = ft_db.get_put(&game_state.board).unwrap();
+        let prc = self.partial_rank_db.get_put(&game_state.board, hc, 0).unwrap();
 
-        let rank = fast_hand_eval(
-            game_state.board.get_iter().chain(hc.get_iter()),
-            &self.hash_func,
-        );
+        let ft = self.flop_texture_db.get_put(&game_state.board).unwrap();
 
-        let likes_hand_response =
-            likes_hand(&prc, &ft, &rank, &game_state.board, &hc, non_folded_players).unwrap();
+        let likes_hand_response = self
+            .hand_eval
+            .likes_hand(
+                &prc,
+                &ft,
+                &game_state.board,
+                hc,
+                non_folded_players,
+            )
+            .unwrap();
 
         let current_pot = game_state.pot();
 
@@ -186,6 +185,26 @@ impl Tag {
                 };
             }
 
+            let call_pot_eq =
+                helpers.call_amount as f64 / (helpers.call_amount as f64 + current_pot as f64);
+            if let Some(eq) = self
+                .hand_eval
+                .equity_vs_line(game_state, player_state.player_index(), hc)
+                .unwrap()
+            {
+                if eq >= call_pot_eq {
+                    return CommentedAction {
+                        action: ActionEnum::Call(helpers.call_amount),
+                        comment: Some(format!(
+                            "Calling because equity vs. the betting line {:.2}% is at least the pot equity {:.2}%;Likes hand: {}",
+                            eq * 100.0,
+                            call_pot_eq * 100.0,
+                            likes_hand_response.likes_hand
+                        )),
+                    };
+                }
+            }
+
             return CommentedAction {
                 action: ActionEnum::Fold,
                 comment: Some(format!(
@@ -275,34 +294,27 @@ impl Agent for Tag {
 
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
-
     use log::info;
 
     use super::*;
-    use crate::{board_hc_eval_cache_redb::EvalCacheWithHcReDb, init_test_logger, Board};
+    use crate::{init_test_logger, Board};
 
-    //#[test]
-    //Need to move over partial rank to a no file system solution
-    #[allow(dead_code)]
+    #[test]
     fn test_doesnt_bet_river() {
         init_test_logger();
 
-        let partial_rank_db: EvalCacheWithHcReDb<ProducePartialRankCards> =
-            EvalCacheWithHcReDb::new().unwrap();
-
-        let rcref_pdb = Rc::new(RefCell::new(partial_rank_db));
-
-        let flop_texture_db: EvalCacheReDb<ProduceFlopTexture> = EvalCacheReDb::new().unwrap();
+        let partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>> =
+            Arc::new(SharedEvalCacheWithHc::new_in_memory());
 
-        let rcref_ftdb = Rc::new(RefCell::new(flop_texture_db));
+        let flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>> =
+            Arc::new(SharedEvalCache::new_in_memory());
 
         let mut tag = Tag::new(
             "JJ+,AJs+,AQo+,KQs",
             "22+,A2+,K2+,Q2+,J2+,T2s+,T5o+,93s+,96o+,85s+,87o,75s+",
             "Hero",
-            rcref_ftdb.clone(),
-            rcref_pdb.clone(),
+            flop_texture_db.clone(),
+            partial_rank_db.clone(),
         );
 
         let player_state = PlayerState {
@@ -1,8 +1,6 @@
 //A game log is all the information needed to reconstruct a game.
 
-use std::cell::RefCell;
 use std::cmp::min;
-use std::rc::Rc;
 use std::str::FromStr;
 
 use boomphf::Mphf;
@@ -15,17 +13,16 @@ use crate::Card;
 use crate::FinalPlayerState;
 
 use crate::InitialPlayerState;
-use crate::OldRank;
-use crate::board_hc_eval_cache_redb::EvalCacheWithHcReDb;
 use crate::board_hc_eval_cache_redb::ProduceMonteCarloEval;
 use crate::game::game_log_parser::GameLogParser;
 use crate::monte_carlo_equity::get_equivalent_hole_board;
-use crate::pre_calc::fast_eval::fast_hand_eval;
+use crate::pre_calc::fast_eval::{fast_best_hand, fast_hand_eval};
+use crate::pre_calc::perfect_hash::BOOM_PERFECT_HASH;
 use crate::pre_calc::rank::Rank;
-use crate::rank_cards;
 use crate::ChipType;
 use crate::PlayerAction;
 use crate::PokerError;
+use crate::SharedEvalCacheWithHc;
 use crate::Round;
 
 
@@ -50,6 +47,10 @@ pub struct GameLog {
 
     //1 for best, etc.  can have repeated ranks for ties
     pub player_ranks_per_round: Vec<Vec<u8>>,
+
+    //If the cards were dealt from a seeded deck, dealing again with this seed gives the same hand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 impl GameLog {
@@ -348,14 +349,13 @@ impl GameLog {
         let mut eval_cards = self.board.to_vec();
         eval_cards.extend(self.players[player_index].cards.unwrap().as_slice());
 
-        let rank = rank_cards(eval_cards.iter());
-        rank.print_winning(&eval_cards)
+        fast_best_hand(&eval_cards, &BOOM_PERFECT_HASH).description()
     }
 
     /*
     Fills in the field for best hands for each player in each round
     */
-    pub fn calc_best_hands(&mut self) {
+    pub fn calc_best_hands(&mut self, hash_func: &Mphf<u32>) {
         let mut v: Vec<Vec<[Card; 5]>> = Vec::new();
         let mut player_rank_order: Vec<Vec<u8>> = Vec::new();
 
@@ -382,9 +382,8 @@ impl GameLog {
 
                     board_cards.extend(p.cards.as_ref().unwrap().as_slice());
 
-                    let rank = rank_cards(board_cards.iter());
-                    let winning_cards = rank.get_winning(&board_cards);
-                    (rank, p_idx, winning_cards)
+                    let best_hand = fast_best_hand(&board_cards, hash_func);
+                    (best_hand.rank, p_idx, best_hand.cards)
                 })
                 .collect_vec();
 
@@ -403,12 +402,12 @@ impl GameLog {
             let mut cur_round_rank_order = vec![0; self.players.len()];
             let mut rank_order = 0;
 
-            let mut last_rank_value = OldRank::StraightFlush(1 << 31);
+            let mut last_rank_value = None;
 
             for (rank, p_idx, _) in player_hand_ranks.iter() {
-                if rank != &last_rank_value {
+                if Some(*rank) != last_rank_value {
                     rank_order += 1;
-                    last_rank_value = *rank;
+                    last_rank_value = Some(*rank);
                 }
                 
                 cur_round_rank_order[*p_idx] = rank_order;
@@ -426,19 +425,16 @@ impl GameLog {
     pub fn get_csv_line(
         &self,
         hero_index: usize,
-        monte_carlo_db: Rc<RefCell<EvalCacheWithHcReDb<ProduceMonteCarloEval>>>,
+        monte_carlo_db: &SharedEvalCacheWithHc<ProduceMonteCarloEval>,
         hash_func: &Mphf<u32>
     ) -> Result<CsvLineForPokerHand, PokerError> {
-        let mut ret = CsvLineForPokerHand::default();
-
-        let mut mc_db = monte_carlo_db.borrow_mut();
-        //Position 0 sb, 1 bb, 2 UTG
-
-        ret.position = hero_index as u8;
-
-        //Number of players in hand
-
-        ret.players_starting_preflop = self.players.len() as u8;
+        let mut ret = CsvLineForPokerHand {
+            //Position 0 sb, 1 bb, 2 UTG
+            position: hero_index as u8,
+            //Number of players in hand
+            players_starting_preflop: self.players.len() as u8,
+            ..Default::default()
+        };
         
 
         let mut when_players_folded: Vec<Option<Round>> = vec![None; self.players.len()];
@@ -492,7 +488,7 @@ impl GameLog {
                     Round::Preflop => {
                         ret.players_before_hero_pre_flop =
                             self.players.len() as u8 - action.players_left_to_act - 1;
-                        ret.hero_eq_start_pre_flop = mc_db
+                        ret.hero_eq_start_pre_flop = monte_carlo_db
                             .get_put(&eq_board, &eq_hole_cards, action.non_folded_players)
                             .unwrap();
                         ret.amt_to_call_start_preflop =
@@ -504,7 +500,7 @@ impl GameLog {
                     Round::Flop => {
                         ret.players_before_hero_flop =
                             ret.players_starting_flop as u8 - action.players_left_to_act - 1;
-                        ret.hero_eq_start_flop = mc_db
+                        ret.hero_eq_start_flop = monte_carlo_db
                             .get_put(&eq_board, &eq_hole_cards, action.non_folded_players)
                             .unwrap();
                         ret.amt_to_call_start_flop =
@@ -516,7 +512,7 @@ impl GameLog {
                     Round::Turn => {
                         ret.players_before_hero_turn =
                             ret.players_starting_turn as u8 - action.players_left_to_act - 1;
-                        ret.hero_eq_start_turn = mc_db
+                        ret.hero_eq_start_turn = monte_carlo_db
                             .get_put(&eq_board, &eq_hole_cards, action.non_folded_players)
                             .unwrap();
                         ret.amt_to_call_start_turn =
@@ -528,7 +524,7 @@ impl GameLog {
                     Round::River => {
                         ret.players_before_hero_river =
                             ret.players_starting_river as u8 - action.players_left_to_act - 1;
-                        ret.hero_eq_start_river = mc_db
+                        ret.hero_eq_start_river = monte_carlo_db
                             .get_put(&eq_board, &eq_hole_cards, action.non_folded_players)
                             .unwrap();
                         ret.amt_to_call_start_river =
@@ -1251,9 +1247,7 @@ Agent 4               - 495 # Started with 500 change -5
         let _game_log: GameLog = hh.parse().unwrap();
     }
 
-    //Needs db
-    //#[test]
-    #[allow(dead_code)]
+    #[test]
     fn test_csv_line() {
         
         init_test_logger();
@@ -1295,9 +1289,8 @@ Agent 4               - 495 # Started with 500 change -5
         ";
         let parsed_game_log: GameLog = hh.parse().unwrap();
 
-        let monte_carlo_equity_db: EvalCacheWithHcReDb<ProduceMonteCarloEval> =
-        EvalCacheWithHcReDb::new().unwrap();
-        let rcref_mcedb = Rc::new(RefCell::new(monte_carlo_equity_db));
+        let monte_carlo_equity_db: SharedEvalCacheWithHc<ProduceMonteCarloEval> =
+        SharedEvalCacheWithHc::new_in_memory();
 
         let hash_func = load_boomperfect_hash();
 
@@ -1318,7 +1311,7 @@ Agent 4               - 495 # Started with 500 change -5
             .unwrap();
 
         //Player B
-        let game_line = log2.get_csv_line(1, rcref_mcedb.clone(), &hash_func).unwrap();
+        let game_line = log2.get_csv_line(1, &monte_carlo_equity_db, &hash_func).unwrap();
 
         assert_eq!(game_line.first_action_amount_preflop, 2.0);
         assert_eq!(game_line.first_action_amount_flop, 1.0);
@@ -1341,7 +1334,7 @@ Agent 4               - 495 # Started with 500 change -5
         assert_eq!(game_line.final_stack, 209.0 / 10.0);
 
         //Player D
-        let game_line = log2.get_csv_line(3, rcref_mcedb, &hash_func).unwrap();
+        let game_line = log2.get_csv_line(3, &monte_carlo_equity_db, &hash_func).unwrap();
         assert_eq!(game_line.first_action_amount_preflop, 1.0);
         assert_eq!(game_line.first_action_amount_flop, 2.0);
         assert_eq!(game_line.first_action_turn, ActionString::Fold);
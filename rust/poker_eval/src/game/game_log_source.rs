@@ -136,4 +136,8 @@ impl GameRunnerSource for GameLogSource {
 
         Ok(())
     }
+
+    fn get_seed(&self) -> Option<u64> {
+        self.game_log.seed
+    }
 }
@@ -4,10 +4,8 @@
 
 use std::cmp::min;
 
-use crate::pre_calc::fast_eval::fast_hand_eval;
-use crate::pre_calc::perfect_hash::load_boomperfect_hash;
-use crate::pre_calc::rank::Rank;
 use crate::{set_used_card, Board, Card, GameLog, InitialPlayerState, PlayerAction};
+use crate::{FastEvaluator, HandEvaluator, HandValue};
 use crate::{
     ActionEnum, CardUsedType, ChipType, FinalPlayerState, GameState, PlayerState, PokerError,
     Position, Round,
@@ -15,7 +13,6 @@ use crate::{
 
 use crate::game::game_runner_source::GameRunnerSource;
 use crate::game::game_runner_source::GameRunnerSourceEnum;
-use boomphf::Mphf;
 
 use log::trace;
 
@@ -28,7 +25,8 @@ pub struct GameRunner {
     // Source of actions, cards
     pub game_runner_source: GameRunnerSourceEnum,
 
-    hash_func: Mphf<u32>,
+    // Decides the winners at showdown
+    evaluator: Box<dyn HandEvaluator>,
 }
 
 impl GameRunner {
@@ -74,7 +72,7 @@ impl GameRunner {
             game_state,
             game_runner_source,
             used_cards: CardUsedType::default(),
-            hash_func: load_boomperfect_hash(),
+            evaluator: Box::<FastEvaluator>::default(),
         };
 
         r.handle_blinds()?;
@@ -83,6 +81,10 @@ impl GameRunner {
         Ok(r)
     }
 
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn HandEvaluator>) {
+        self.evaluator = evaluator;
+    }
+
     fn handle_blinds(&mut self) -> Result<(), PokerError> {
         let sb = self.game_state.sb;
         let bb = self.game_state.bb;
@@ -349,7 +351,7 @@ impl GameRunner {
             self.game_state.total_active_players > 1 || self.game_state.total_players_all_in > 0
         );
 
-        let mut hand_rankings: Vec<(Rank, usize)> = Vec::new();
+        let mut hand_rankings: Vec<(HandValue, usize)> = Vec::new();
         //let mut hand_ranking_strings: Vec<Option<String>> = vec![None; self.game_state.player_states.len()];
 
         //let mut eval_cards = self.game_state.board.as_slice_card().to_vec();
@@ -371,13 +373,9 @@ impl GameRunner {
 
             let hole_cards = self.game_runner_source.get_hole_cards(player_index)?;
 
-            let rank = fast_hand_eval(
-                self.game_state
-                    .board
-                    .get_iter()
-                    .chain(hole_cards.get_iter()),
-                &self.hash_func,
-            );
+            let mut eval_cards = self.game_state.board.as_slice_card().to_vec();
+            eval_cards.extend(hole_cards.get_iter());
+            let rank = self.evaluator.evaluate(&eval_cards);
 
             hand_rankings.push((rank, player_index));
         }
@@ -971,6 +969,7 @@ impl GameRunner {
             //Don't calculate yet as it's expensive
             best_player_hands: vec![],
             player_ranks_per_round: vec![],
+            seed: self.game_runner_source.get_seed(),
         };
 
         Ok(game_log)
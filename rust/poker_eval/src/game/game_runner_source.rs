@@ -39,4 +39,7 @@ pub trait GameRunnerSource {
         player_state: &PlayerState,
         comment: Option<String>,
     ) -> Result<(), PokerError>;
+
+    //The seed used to deal the cards, if any, so the game can be regenerated
+    fn get_seed(&self) -> Option<u64>;
 }
@@ -32,5 +32,4 @@ pub mod game_log_source;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod game_runner_source;
 
-#[cfg(not(target_arch = "wasm32"))]
 pub mod agents;
@@ -1,9 +1,9 @@
 use core::fmt;
 use std::fmt::Display;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug, PartialOrd, Ord, Serialize)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Round {
     Preflop = 0,
@@ -67,4 +67,4 @@ impl TryFrom<u8> for Round {
             _ => Err(format!("Invalid round {}", value)),
         }
     }
-}
\ No newline at end of file
+}
@@ -7,7 +7,7 @@ use std::{
 
 use log::info;
 use poker_eval::{
-    board_hc_eval_cache_redb::{EvalCacheWithHcReDb, ProduceMonteCarloEval},
+    board_hc_eval_cache_redb::{EvalCacheWithHc, ProduceMonteCarloEval},
     init_logger,
     monte_carlo_equity::get_equivalent_hole_board,
     pre_calc::get_data_file_path,
@@ -28,8 +28,8 @@ fn main_impl() -> Result<(), PokerError> {
 
     let mut check_index = 0;
 
-    let mut monte_carlo_equity_db: EvalCacheWithHcReDb<ProduceMonteCarloEval> =
-        EvalCacheWithHcReDb::new().unwrap();
+    let mut monte_carlo_equity_db: EvalCacheWithHc<ProduceMonteCarloEval> =
+        EvalCacheWithHc::new_redb().unwrap();
 
     //let rcref_mcedb = Rc::new(RefCell::new(monte_carlo_equity_db));
 
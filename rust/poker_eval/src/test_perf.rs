@@ -15,7 +15,7 @@ fn main2() {
             "33+,A2s+,K3s+,Q6s+,J8s+,T9s,A2o+,K6o+,Q8o+,JTo".parse().unwrap(),
         ];
 
-    //let rank_db: EvalCacheReDb<ProduceRank> = EvalCacheReDb::new().unwrap();
+    //let rank_db: EvalCache<ProduceRank> = EvalCache::new_redb().unwrap();
 
     //let shared = Rc::new(RefCell::new(rank_db));
 
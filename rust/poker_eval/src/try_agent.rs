@@ -1,9 +1,8 @@
 use std::{
-    cell::RefCell,
     collections::{BinaryHeap, HashMap},
     fs,
     path::PathBuf,
-    rc::Rc,
+    sync::{Arc, Mutex},
 };
 
 use log::debug;
@@ -13,32 +12,36 @@ use poker_eval::{
         build_initial_players_from_agents, set_agent_hole_cards, Agent, AgentSource, EqAgent,
         EqAgentConfig, Tag,
     },
-    board_eval_cache_redb::{EvalCacheReDb, ProduceFlopTexture},
-    board_hc_eval_cache_redb::{
-        EvalCacheWithHcReDb, ProduceMonteCarloEval, ProducePartialRankCards,
-    },
+    board_eval_cache_redb::ProduceFlopTexture,
+    board_hc_eval_cache_redb::{ProduceMonteCarloEval, ProducePartialRankCards},
     game_runner_source::GameRunnerSourceEnum,
-    init_logger, Card, Deck, GameLog, GameRunner, InitialPlayerState, pre_calc::perfect_hash::load_boomperfect_hash,
+    likes_hand_rules::LikesHandRules,
+    narrow_range::RangeNarrowingCache,
+    init_logger, Card, SharedEvalCache, SharedEvalCacheWithHc, Deck, DEFAULT_WRITE_BATCH_SIZE, GameLog, DEFAULT_SEED, GameRunner, InitialPlayerState, pre_calc::perfect_hash::load_boomperfect_hash,
 };
 use rand::seq::SliceRandom;
 
 fn build_agents(
-    flop_texture_db: Rc<RefCell<EvalCacheReDb<ProduceFlopTexture>>>,
-    partial_rank_db: Rc<RefCell<EvalCacheWithHcReDb<ProducePartialRankCards>>>,
-    monte_carlo_equity_db: Rc<RefCell<EvalCacheWithHcReDb<ProduceMonteCarloEval>>>,
+    flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>>,
+    partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>>,
+    monte_carlo_equity_db: Arc<SharedEvalCacheWithHc<ProduceMonteCarloEval>>,
+    likes_hand_rules: Option<Arc<LikesHandRules>>,
+    range_narrowing_cache: Arc<Mutex<RangeNarrowingCache>>,
     num_total_players: usize,
 ) -> Vec<Box<dyn Agent>> {
     //let calling_75 = "22+,A2+,K2+,Q2+,J2+,T2s+,T5o+,93s+,96o+,85s+,87o,75s+";
 
     let mut agents: Vec<Box<dyn Agent>> = Vec::new();
 
-    agents.push(Box::new(EqAgent::new(        
+    let mut eq_agent = EqAgent::new(        
         "EqAggroA",
         EqAgentConfig::get_aggressive(),
         flop_texture_db.clone(),
         partial_rank_db.clone(),
         monte_carlo_equity_db.clone(),
-    )));
+    );
+    eq_agent.hand_eval.set_range_narrowing_cache(range_narrowing_cache.clone());
+    agents.push(Box::new(eq_agent));
     
 
     // agents.push(Box::new(PassiveCallingStation::new(
@@ -56,23 +59,30 @@ fn build_agents(
         monte_carlo_equity_db.clone(),
     )));
 
-    let tag = Tag::new(
+    let mut tag = Tag::new(
         "JJ+,AJs+,AQo+,KQs",
         "22+,A2+,K2+,Q2+,J2+,T2s+,T5o+,93s+,96o+,85s+,87o,75s+",
         "Hero",
         flop_texture_db.clone(),
         partial_rank_db.clone(),
     );
+    if let Some(rules) = likes_hand_rules.as_ref() {
+        tag.hand_eval.set_likes_hand_rules(rules.clone());
+    }
+    tag.hand_eval.set_range_narrowing_cache(range_narrowing_cache.clone());
 
     agents.push(Box::new(tag));
 
-    let tag = Tag::new(
+    let mut tag = Tag::new(
         "JJ+,AJs+,AQo+,KQs",
         "22+,A2+,K2+,Q2+,J2+,T2s+,T5o+,93s+,96o+,85s+,87o,75s+",
         "HeroDeux",
         flop_texture_db.clone(),
         partial_rank_db.clone(),
     );
+    if let Some(rules) = likes_hand_rules.as_ref() {
+        tag.hand_eval.set_likes_hand_rules(rules.clone());
+    }
 
     agents.push(Box::new(tag));
 
@@ -97,23 +107,33 @@ fn main() {
     */
     init_logger();
 
-    let partial_rank_db: EvalCacheWithHcReDb<ProducePartialRankCards> =
-        EvalCacheWithHcReDb::new().unwrap();
-
-    let rcref_pdb = Rc::new(RefCell::new(partial_rank_db));
+    //Batched, otherwise each miss is its own write transaction
+    let partial_rank_db: Arc<SharedEvalCacheWithHc<ProducePartialRankCards>> =
+        Arc::new(SharedEvalCacheWithHc::new_redb(DEFAULT_WRITE_BATCH_SIZE).unwrap());
 
-    let flop_texture_db: EvalCacheReDb<ProduceFlopTexture> = EvalCacheReDb::new().unwrap();
+    let flop_texture_db: Arc<SharedEvalCache<ProduceFlopTexture>> =
+        Arc::new(SharedEvalCache::new_redb(DEFAULT_WRITE_BATCH_SIZE).unwrap());
 
-    let rcref_ftdb = Rc::new(RefCell::new(flop_texture_db));
+    let monte_carlo_equity_db: Arc<SharedEvalCacheWithHc<ProduceMonteCarloEval>> =
+        Arc::new(SharedEvalCacheWithHc::new_redb(DEFAULT_WRITE_BATCH_SIZE).unwrap());
 
-    let monte_carlo_equity_db: EvalCacheWithHcReDb<ProduceMonteCarloEval> =
-        EvalCacheWithHcReDb::new().unwrap();
+    let hash_func = load_boomperfect_hash();
 
-    let rcref_mcedb = Rc::new(RefCell::new(monte_carlo_equity_db));
+    //Shared by the agents across all the hands
+    let range_narrowing_cache = Arc::new(Mutex::new(RangeNarrowingCache::new()));
 
-    let hash_func = load_boomperfect_hash();
+    //Each hand is dealt from a deck seeded with base_seed + iteration, which is saved in the game log
+    //cargo run --release --bin try_agent -- <base_seed>
+    let base_seed: u64 = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("Seed should be a number"))
+        .unwrap_or(DEFAULT_SEED);
 
-    let mut agent_deck = Deck::new();
+    //Optionally the Tag agents use a likes hand rule file
+    //cargo run --release --bin try_agent -- <base_seed> <likes_hand_rules.json>
+    let likes_hand_rules: Option<Arc<LikesHandRules>> = std::env::args().nth(2).map(|path| {
+        Arc::new(LikesHandRules::load(&PathBuf::from(path)).expect("Invalid likes hand rules"))
+    });
 
     //we want to track the worst loses
     let mut heap: BinaryHeap<(i64, i32, GameLog)> = BinaryHeap::new();
@@ -142,12 +162,15 @@ fn main() {
     let mut json_filenames = Vec::new();
 
     for it_num in 0..num_total_iterations {
-        agent_deck.reset();
+        let seed = base_seed.wrapping_add(it_num as u64);
+        let mut agent_deck = Deck::new_with_seed(seed);
 
         let mut agents = build_agents(
-            rcref_ftdb.clone(),
-            rcref_pdb.clone(),
-            rcref_mcedb.clone(),
+            flop_texture_db.clone(),
+            partial_rank_db.clone(),
+            monte_carlo_equity_db.clone(),
+            likes_hand_rules.clone(),
+            range_narrowing_cache.clone(),
             num_players,
         );
         agents.shuffle(&mut agent_deck.rng);
@@ -164,6 +187,7 @@ fn main() {
             sb: 2,
             bb: 5,
             board,
+            seed: Some(seed),
         };
 
         let mut game_runner = GameRunner::new(GameRunnerSourceEnum::from(agent_source)).unwrap();
@@ -199,7 +223,7 @@ fn main() {
 
         let mut game_log = game_runner.to_game_log().unwrap();
 
-        game_log.calc_best_hands();
+        game_log.calc_best_hands(&hash_func);
         let json_str = serde_json::to_string_pretty(&game_log).unwrap();
         let json_filename = format!("{}.json", it_num);
         let file_path = json_hh_path.join(&json_filename);
@@ -210,7 +234,7 @@ fn main() {
         //     break;
         // }
 
-        let game_csv_line  = game_log.get_csv_line(hero_index, rcref_mcedb.clone(), &hash_func).unwrap();
+        let game_csv_line  = game_log.get_csv_line(hero_index, &monte_carlo_equity_db, &hash_func).unwrap();
         wtr.serialize(game_csv_line).unwrap();
         // for (c, it, _log) in heap.iter() {
         //     debug!(
@@ -0,0 +1,80 @@
+use log::info;
+use poker_eval::{
+    cross_validate, init_logger, CrossValidationMode, EvaluatorBackend, PokerError, DEFAULT_SEED,
+};
+
+/*
+Compares the hand evaluators against the fast (perfect hash) one on 5, 6 and 7 card hands
+
+cargo run --release --bin verify_evaluators -- random 1000000
+cargo run --release --bin verify_evaluators -- exhaustive 5 kev
+
+The exhaustive 7 card run is 133 million hands per evaluator
+*/
+
+fn main() {
+    main_impl().unwrap();
+}
+
+fn main_impl() -> Result<(), PokerError> {
+    init_logger();
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        return Err(PokerError::from_str(
+            "Usage: verify_evaluators random [num hands] [evaluator] | exhaustive [num cards] [evaluator]",
+        ));
+    }
+
+    let parse_num = |i: usize, default: usize| -> Result<usize, PokerError> {
+        match args.get(i) {
+            Some(s) => s
+                .parse()
+                .map_err(|_| PokerError::from_string(format!("Invalid number {}", s))),
+            None => Ok(default),
+        }
+    };
+
+    let (mode, card_counts) = match args[1].as_str() {
+        "random" => (
+            CrossValidationMode::Random {
+                num_hands: parse_num(2, 100_000)?,
+                seed: DEFAULT_SEED,
+            },
+            vec![5, 6, 7],
+        ),
+        "exhaustive" => (CrossValidationMode::Exhaustive, vec![parse_num(2, 5)?]),
+        s => {
+            return Err(PokerError::from_string(format!(
+                "Unknown mode {}, expected random or exhaustive",
+                s
+            )))
+        }
+    };
+
+    let others = match args.get(3) {
+        Some(s) => vec![s.parse::<EvaluatorBackend>()?],
+        None => vec![EvaluatorBackend::Kev, EvaluatorBackend::OldRank],
+    };
+
+    let reference = EvaluatorBackend::Fast.evaluator();
+    let mut num_mismatches = 0;
+
+    for other in others {
+        let other = other.evaluator();
+        for num_cards in card_counts.iter() {
+            let report = cross_validate(reference.as_ref(), other.as_ref(), *num_cards, mode)?;
+            info!("{}", report);
+            num_mismatches += report.num_mismatches;
+        }
+    }
+
+    if num_mismatches > 0 {
+        return Err(PokerError::from_string(format!(
+            "{} mismatches between evaluators",
+            num_mismatches
+        )));
+    }
+
+    Ok(())
+}
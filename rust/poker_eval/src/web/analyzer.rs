@@ -1,24 +1,32 @@
 use std::cmp::{max, min};
 
 use crate::likes_hands::LikesHandLevel;
-use crate::narrow_range::{narrow_range_by_equity, narrow_range_by_pref};
+use crate::narrow_range::{
+    narrow_range_by_equity, narrow_range_by_line, narrow_range_by_pref, RangeNarrowingCache,
+    StreetAction,
+};
 use crate::pre_calc::perfect_hash::load_boomperfect_hash;
+use crate::pre_calc::NUMBER_OF_HOLE_CARDS;
 use crate::web::player_results::PlayerFlopResults;
 use crate::web::{
-    eval_current, eval_current_draws, get_all_player_hole_cards, FlopSimulationResults,
-    PlayerPreFlopState, PreflopPlayerInfo,
+    eval_current, eval_current_draws, get_all_player_hole_cards, EvalDeal, FlopSimulationResults,
+    PlayerPreFlopState, PreflopPlayerInfo, RunoutMatrix,
+};
+use crate::{
+    calc_outs, set_used_card, Board, BoolRange, Deck, HoleCards, HoleCardsSampler, PokerError,
+    WeightedRange, ALL_CARDS,
 };
-use crate::{set_used_card, Board, BoolRange, Deck, HoleCards, PokerError, ALL_CARDS};
 use boomphf::Mphf;
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
 
-use rand::seq::SliceRandom;
 #[cfg(not(test))]
 use rand::thread_rng;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::{Card, CardUsedType};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{derive_seed, split_iterations};
+use crate::{now_ms, Card, CardUsedType, DeckVariant, StoppingRule, DEFAULT_BATCH_SIZE};
 use wasm_bindgen::prelude::wasm_bindgen;
 
 #[wasm_bindgen]
@@ -29,6 +37,14 @@ pub struct flop_analyzer {
     player_info: Vec<PreflopPlayerInfo>,
 
     hash_func: Mphf<u32>,
+
+    //If set, simulate_flop gives the same results for the same inputs
+    seed: Option<u64>,
+
+    deck_variant: DeckVariant,
+
+    //Kept between calls, the same runout is usually narrowed for several players
+    range_narrowing_cache: RangeNarrowingCache,
 }
 
 //hero is 0
@@ -57,9 +73,31 @@ impl flop_analyzer {
             board_cards: Vec::with_capacity(7),
             player_info: Vec::with_capacity(MAX_PLAYERS),
             hash_func,
+            seed: None,
+            deck_variant: DeckVariant::Standard,
+            range_narrowing_cache: RangeNarrowingCache::new(),
         }
     }
 
+    pub fn set_seed(&mut self, seed: u64) {
+        info!("set_seed: {}", seed);
+        self.seed = Some(seed);
+    }
+
+    pub fn clear_seed(&mut self) {
+        self.seed = None;
+    }
+
+    //Short deck (6+) removes the 2s to 5s
+    pub fn set_short_deck(&mut self, short_deck: bool) {
+        info!("set_short_deck: {}", short_deck);
+        self.deck_variant = if short_deck {
+            DeckVariant::ShortDeck
+        } else {
+            DeckVariant::Standard
+        };
+    }
+
     pub fn set_board_cards(&mut self, cards: &[u8]) -> Result<(), PokerError> {
         self.board_cards.clear();
         info!("set_board_cards: len {}", cards.len());
@@ -142,9 +180,13 @@ impl flop_analyzer {
         if range_str.is_empty() {
             return Err(PokerError::from_str("set_player_range: empty string"));
         }
-        let range: BoolRange = range_str.parse()?;
+        //Also accepts weights, like AKs:0.5,QQ+
+        let range: WeightedRange = range_str.parse()?;
 
-        info!("% is {}", range.data.count_ones() as f64 / 2652.0);
+        info!(
+            "% is {}",
+            range.get_num_combos() / NUMBER_OF_HOLE_CARDS as f64
+        );
 
         self.player_info[player_idx].range = range;
         self.player_info[player_idx].range_string = range_str.to_string();
@@ -179,6 +221,12 @@ impl flop_analyzer {
         let mut cards_used = CardUsedType::default();
 
         for c in self.board_cards.iter() {
+            if !self.deck_variant.contains(*c) {
+                return Err(PokerError::from_string(format!(
+                    "Board card {} is not in the deck",
+                    c
+                )));
+            }
             set_used_card((*c).into(), &mut cards_used)?;
         }
 
@@ -191,6 +239,13 @@ impl flop_analyzer {
                 "Player missing hole cards"
             )))?;
 
+            if !self.deck_variant.contains(hc.get_lo_card()) {
+                return Err(PokerError::from_string(format!(
+                    "Hole cards {} are not in the deck",
+                    hc
+                )));
+            }
+
             set_used_card(hc.get_hi_card().into(), &mut cards_used)?;
             set_used_card(hc.get_lo_card().into(), &mut cards_used)?;
         }
@@ -226,14 +281,21 @@ impl flop_analyzer {
         equity_only: bool,
     ) -> Result<FlopSimulationResults, PokerError> {
         //let n_players = self.player_info.len();
-        #[cfg(test)]
-        let rng = StdRng::seed_from_u64(42);
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => Self::unseeded_rng()?,
+        };
 
-        #[cfg(not(test))]
-        let rng = StdRng::from_rng(thread_rng())
-            .ok()
-            .ok_or(PokerError::from_str("Failed to create rng"))?;
+        self.simulate_flop_with_rng(num_iterations, all_flop_results, equity_only, rng)
+    }
 
+    fn simulate_flop_with_rng(
+        &self,
+        num_iterations: u32,
+        all_flop_results: FlopSimulationResults,
+        equity_only: bool,
+        rng: StdRng,
+    ) -> Result<FlopSimulationResults, PokerError> {
         let active_players = self
             .player_info
             .iter()
@@ -248,17 +310,24 @@ impl flop_analyzer {
             )));
         }
 
-        //Store the active player index because we want to change the order in which we choose the ranges
-        //Ignores players that already have hole cards set
-        let mut possible_hole_cards: Vec<(usize, Vec<HoleCards>)> = active_players
-            .iter()
-            .enumerate()
-            .filter(|(_idx, p)| p.1.state == PlayerPreFlopState::UseRange)
-            //These are irrespective of used cards, we try later on which hole cards are still valid
-            .map(|(active_player_index, r)| {
-                (active_player_index, r.1.range.get_all_enabled_holecards())
-            })
-            .collect_vec();
+        //Players with hole cards are dead cards for the ranges, as is the board and cards not in the deck
+        let mut dead_cards = self.board_cards.clone();
+        dead_cards.extend_from_slice(self.deck_variant.removed_cards());
+        let mut player_ranges: Vec<Option<&WeightedRange>> = Vec::new();
+        for (_, p) in active_players.iter() {
+            match p.state {
+                PlayerPreFlopState::UseHoleCards => {
+                    let hc = p
+                        .hole_cards
+                        .ok_or(PokerError::from_str("Player missing hole cards"))?;
+                    dead_cards.push(hc.get_hi_card());
+                    dead_cards.push(hc.get_lo_card());
+                }
+                PlayerPreFlopState::UseRange => player_ranges.push(Some(&p.range)),
+                PlayerPreFlopState::Disabled => {}
+            }
+        }
+        let sampler = HoleCardsSampler::new(&player_ranges, &dead_cards)?;
 
         let mut flop_results = all_flop_results.flop_results;
         let mut villian_results = all_flop_results.all_villians;
@@ -280,10 +349,8 @@ impl flop_analyzer {
 
         let base_cards_used = self.init_cards_used()?;
 
-        let mut deck = Deck {
-            rng,
-            used_cards: base_cards_used.clone(),
-        };
+        let mut deck = Deck::new_with_variant(rng, self.deck_variant);
+        deck.used_cards = base_cards_used.clone();
 
         let mut num_errors = 0;
         let allowed_errors = max(5, min(num_iterations / 500, 100));
@@ -291,23 +358,11 @@ impl flop_analyzer {
         for it_num in 0..num_iterations {
             //debug!("simulate_flop: iteration {}", it_num);
 
-            //We don't want any selection bias; like choosing first I think makes it
-            //more likely they get pocket pairs for example
-            possible_hole_cards.shuffle(&mut deck.rng);
-
             deck.used_cards = base_cards_used.clone();
 
-            //with flop, players with hole cards
-            let mut eval_cards = Vec::with_capacity(15);
-
-            //let mut cards_used = base_cards_used.clone();
-            let num_added = self.add_flop(&mut deck, &mut eval_cards)?;
-
-            assert_eq!(3, eval_cards.len());
-
-            //First we choose hole cards for players that are using a range
-            let player_cards =
-                get_all_player_hole_cards(&active_players, &mut deck, &possible_hole_cards);
+            //First we choose hole cards for players that are using a range, before any flop cards
+            //so the flop doesn't influence which hole cards are chosen
+            let player_cards = get_all_player_hole_cards(&active_players, &mut deck, &sampler);
 
             if let Err(e) = player_cards {
                 warn!("Failed to get player cards: {} in iteration {}", e, it_num);
@@ -320,6 +375,14 @@ impl flop_analyzer {
                 }
                 continue;
             }
+
+            //with flop, players with hole cards
+            let mut eval_cards = Vec::with_capacity(15);
+
+            let num_added = self.add_flop(&mut deck, &mut eval_cards)?;
+
+            assert_eq!(3, eval_cards.len());
+
             let player_cards = player_cards.unwrap();
 
             assert_eq!(player_cards.len(), active_players.len());
@@ -341,13 +404,16 @@ impl flop_analyzer {
             }
 
             eval_current(
-                &active_players,
-                &player_cards,
-                &mut eval_cards,
+                EvalDeal {
+                    active_players: &active_players,
+                    player_cards: &player_cards,
+                    eval_cards: &mut eval_cards,
+                },
                 &mut flop_results,
                 &mut villian_results,
                 0,
                 &self.hash_func,
+                self.deck_variant,
             )?;
 
             assert_eq!(3, eval_cards.len());
@@ -388,13 +454,16 @@ impl flop_analyzer {
             }
 
             eval_current(
-                &active_players,
-                &player_cards,
-                &mut eval_cards,
+                EvalDeal {
+                    active_players: &active_players,
+                    player_cards: &player_cards,
+                    eval_cards: &mut eval_cards,
+                },
                 &mut flop_results,
                 &mut villian_results,
                 1,
                 &self.hash_func,
+                self.deck_variant,
             )?;
 
             //River
@@ -421,13 +490,16 @@ impl flop_analyzer {
             assert_eq!(5, eval_cards.len());
 
             eval_current(
-                &active_players,
-                &player_cards,
-                &mut eval_cards,
+                EvalDeal {
+                    active_players: &active_players,
+                    player_cards: &player_cards,
+                    eval_cards: &mut eval_cards,
+                },
                 &mut flop_results,
                 &mut villian_results,
                 2,
                 &self.hash_func,
+                self.deck_variant,
             )?;
         }
 
@@ -437,6 +509,76 @@ impl flop_analyzer {
         })
     }
 
+    /*
+    Simulates in batches until every player's river equity has a standard error of at most target_std_error,
+    or time_budget_ms has passed, or max_iterations have been done.
+
+    Either target_std_error or time_budget_ms can be 0 to not use them
+    */
+    pub fn simulate_flop_until_converged(
+        &self,
+        mut all_flop_results: FlopSimulationResults,
+        equity_only: bool,
+        target_std_error: f64,
+        time_budget_ms: f64,
+        max_iterations: u32,
+    ) -> Result<FlopSimulationResults, PokerError> {
+        let stopping_rule = StoppingRule {
+            target_std_error: (target_std_error > 0.0).then_some(target_std_error),
+            time_budget_ms: (time_budget_ms > 0.0).then_some(time_budget_ms),
+            max_iterations: max_iterations as usize,
+            batch_size: DEFAULT_BATCH_SIZE,
+        };
+
+        //Each batch gets its own rng, seeded from this one
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => Self::unseeded_rng()?,
+        };
+
+        let start_ms = now_ms();
+        let mut num_iterations = 0;
+
+        loop {
+            let batch_size = stopping_rule.next_batch_size(num_iterations);
+            all_flop_results = self.simulate_flop_with_rng(
+                batch_size as u32,
+                all_flop_results,
+                equity_only,
+                StdRng::seed_from_u64(rng.gen()),
+            )?;
+            num_iterations += batch_size;
+
+            if stopping_rule.is_done(
+                num_iterations,
+                all_flop_results.max_river_std_error(),
+                start_ms,
+            ) {
+                break;
+            }
+        }
+
+        info!(
+            "simulate_flop_until_converged: {} iterations, max std error {:.4}",
+            num_iterations,
+            all_flop_results.max_river_std_error()
+        );
+
+        Ok(all_flop_results)
+    }
+
+    #[cfg(test)]
+    fn unseeded_rng() -> Result<StdRng, PokerError> {
+        Ok(StdRng::seed_from_u64(crate::DEFAULT_SEED))
+    }
+
+    #[cfg(not(test))]
+    fn unseeded_rng() -> Result<StdRng, PokerError> {
+        StdRng::from_rng(thread_rng())
+            .ok()
+            .ok_or(PokerError::from_str("Failed to create rng"))
+    }
+
     fn add_flop(&self, deck: &mut Deck, eval_cards: &mut Vec<Card>) -> Result<usize, PokerError> {
         assert!(eval_cards.is_empty());
 
@@ -548,4 +690,201 @@ impl flop_analyzer {
 
         Ok(narrowed_range.to_string())
     }
+
+    /*
+    Narrows over the flop / turn / river, line has one street action per street (see StreetAction)
+
+    Returns the narrowed range after each street, seperated by ;
+    */
+    pub fn narrow_range_by_line(
+        &mut self,
+        str_range_to_narrow: &str,
+        line: &[u8],
+        cards: &[u8],
+        num_players: u8,
+    ) -> Result<String, PokerError> {
+        let range_to_narrow: BoolRange = str_range_to_narrow.parse()?;
+        let line: Vec<StreetAction> = line
+            .iter()
+            .map(|a| StreetAction::try_from(*a))
+            .collect::<Result<_, _>>()?;
+        info!(
+            "Starting narrow range {} by line {} vs {} opponents",
+            range_to_narrow.data.count_ones(),
+            line.iter().join(", "),
+            num_players
+        );
+
+        let board_cards = cards.iter().map(|c| ALL_CARDS[*c as usize]).collect_vec();
+
+        let narrowed_ranges = narrow_range_by_line(
+            &range_to_narrow,
+            &board_cards,
+            &line,
+            num_players,
+            &self.hash_func,
+            &mut self.range_narrowing_cache,
+        )?;
+
+        info!(
+            "narrowed range {} hands; cache hits {} misses {}",
+            narrowed_ranges.last().unwrap().data.count_ones(),
+            self.range_narrowing_cache.cache_hits,
+            self.range_narrowing_cache.cache_misses
+        );
+
+        Ok(narrowed_ranges.iter().map(|r| r.to_string()).join(";"))
+    }
+
+    /*
+    Sets the outs on the current street's draws (flop or turn) for a player with hole cards,
+    against every hand the other active players could have
+    */
+    pub fn calc_outs(
+        &self,
+        mut all_flop_results: FlopSimulationResults,
+        active_player_index: usize,
+    ) -> Result<FlopSimulationResults, PokerError> {
+        if self.board_cards.len() != 3 && self.board_cards.len() != 4 {
+            return Err(PokerError::from_str("calc_outs needs a flop or turn"));
+        }
+
+        let player_index = all_flop_results
+            .flop_results
+            .get(active_player_index)
+            .ok_or(PokerError::from_str("Invalid active player index"))?
+            .player_index;
+        let hole_cards = match self.player_info[player_index].state {
+            PlayerPreFlopState::UseHoleCards => self.player_info[player_index].hole_cards,
+            _ => None,
+        }
+        .ok_or(PokerError::from_str(
+            "calc_outs needs a player with hole cards",
+        ))?;
+
+        //Keeps the weights, a combo in several ranges counts with its highest weight
+        let mut villian_range = WeightedRange::new();
+        for (p_idx, p) in self.player_info.iter().enumerate() {
+            if p_idx == player_index {
+                continue;
+            }
+            match p.state {
+                PlayerPreFlopState::Disabled => {}
+                PlayerPreFlopState::UseHoleCards => {
+                    let hc = p
+                        .hole_cards
+                        .ok_or(PokerError::from_str("Player missing hole cards"))?;
+                    villian_range.data[hc.to_range_index()] = 1.0;
+                }
+                PlayerPreFlopState::UseRange => {
+                    villian_range = villian_range.union(&p.range);
+                }
+            }
+        }
+
+        let outs = calc_outs(
+            &hole_cards,
+            &self.board_cards,
+            &villian_range,
+            &self.hash_func,
+        )?;
+        info!(
+            "calc_outs: {} outs for {} on {}",
+            outs.num_outs(),
+            hole_cards,
+            Board::new_from_cards(&self.board_cards)
+        );
+
+        all_flop_results.flop_results[active_player_index].street_draws
+            [self.board_cards.len() - 3]
+            .outs = outs.cards;
+
+        Ok(all_flop_results)
+    }
+
+    /*
+    On a flop, simulates each possible turn card with num_iterations each, or each river card on a turn,
+    to see which cards help or hurt each player
+    */
+    pub fn simulate_runouts(&mut self, num_iterations: u32) -> Result<RunoutMatrix, PokerError> {
+        if self.board_cards.len() != 3 && self.board_cards.len() != 4 {
+            return Err(PokerError::from_str(
+                "simulate_runouts needs a flop or turn",
+            ));
+        }
+
+        let base_results = self.simulate_flop(num_iterations, self.build_results(), true)?;
+        let street_index = self.board_cards.len() - 2;
+        let mut matrix = RunoutMatrix::new(street_index, &base_results);
+
+        let cards_used = self.init_cards_used()?;
+        for card in ALL_CARDS.iter() {
+            if cards_used[card.index as usize] || !self.deck_variant.contains(*card) {
+                continue;
+            }
+
+            self.board_cards.push(*card);
+            let card_results = self.simulate_flop(num_iterations, self.build_results(), true);
+            self.board_cards.pop();
+
+            match card_results {
+                Ok(card_results) => matrix.set_runout(*card, &base_results, &card_results),
+                //ex: the card blocks every hand in a range
+                Err(e) => debug!("simulate_runouts: skipping {}: {}", card, e),
+            }
+        }
+
+        Ok(matrix)
+    }
+}
+
+impl flop_analyzer {
+    /*
+    Native only, splits the iterations across num_threads threads each with an rng seeded from seed,
+    then adds up the results.  The results are the same for the same seed and number of threads.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn simulate_flop_parallel(
+        &self,
+        num_iterations: u32,
+        mut all_flop_results: FlopSimulationResults,
+        equity_only: bool,
+        seed: u64,
+        num_threads: usize,
+    ) -> Result<FlopSimulationResults, PokerError> {
+        let thread_results: Vec<Result<FlopSimulationResults, PokerError>> =
+            std::thread::scope(|s| {
+                let handles = split_iterations(num_iterations as usize, num_threads)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, thread_iterations)| *thread_iterations > 0)
+                    .map(|(thread_index, thread_iterations)| {
+                        let rng = StdRng::seed_from_u64(derive_seed(seed, thread_index as u64));
+                        s.spawn(move || {
+                            self.simulate_flop_with_rng(
+                                thread_iterations as u32,
+                                self.build_results(),
+                                equity_only,
+                                rng,
+                            )
+                        })
+                    })
+                    .collect_vec();
+
+                handles
+                    .into_iter()
+                    .map(|h| {
+                        h.join().unwrap_or_else(|_| {
+                            Err(PokerError::from_str("simulate_flop thread panicked"))
+                        })
+                    })
+                    .collect_vec()
+            });
+
+        for thread_result in thread_results {
+            all_flop_results.merge(&thread_result?)?;
+        }
+
+        Ok(all_flop_results)
+    }
 }
@@ -1,7 +1,7 @@
 use crate::web::{PlayerFlopResults, PlayerPreFlopState, PreflopPlayerInfo};
 use crate::{HoleCards, PokerError};
 
-use crate::{partial_rank_cards, Card, FlushDrawType, PartialRankContainer, StraightDrawType};
+use crate::{partial_rank_cards, Card, FlushDrawType, Out, PartialRankContainer, StraightDrawType};
 use wasm_bindgen::prelude::wasm_bindgen;
 type ResultType = u32;
 use serde::Serialize;
@@ -16,12 +16,24 @@ pub struct Draws {
     pub flush_draw: ResultType,
     pub backdoor_flush_draw: ResultType,
 
+    //Subsets of the draws above
+    pub nut_flush_draw: ResultType,
+    pub combo_draw: ResultType,
+    pub pair_and_draw: ResultType,
+    //Straight draws (of any type) when the board has 3 of a suit
+    pub str8_draw_flush_possible: ResultType,
+
+    pub backdoor_str8_draw: ResultType,
+
     pub one_overcard: ResultType,
     pub two_overcards: ResultType,
 
     pub lo_paired: ResultType,
     pub hi_paired: ResultType,
     pub pp_paired: ResultType,
+
+    //Filled by flop_analyzer::calc_outs for a player with known hole cards
+    pub(crate) outs: Vec<Out>,
 }
 
 #[wasm_bindgen]
@@ -32,6 +44,11 @@ impl Draws {
             str8_draw: 0,
             flush_draw: 0,
             backdoor_flush_draw: 0,
+            nut_flush_draw: 0,
+            combo_draw: 0,
+            pair_and_draw: 0,
+            str8_draw_flush_possible: 0,
+            backdoor_str8_draw: 0,
             one_overcard: 0,
             two_overcards: 0,
 
@@ -40,6 +57,32 @@ impl Draws {
             pp_paired: 0,
 
             num_iterations: 0,
+
+            outs: Vec::new(),
+        }
+    }
+}
+
+impl Draws {
+    pub fn merge(&mut self, other: &Draws) {
+        self.num_iterations += other.num_iterations;
+        self.gut_shot += other.gut_shot;
+        self.str8_draw += other.str8_draw;
+        self.flush_draw += other.flush_draw;
+        self.backdoor_flush_draw += other.backdoor_flush_draw;
+        self.nut_flush_draw += other.nut_flush_draw;
+        self.combo_draw += other.combo_draw;
+        self.pair_and_draw += other.pair_and_draw;
+        self.str8_draw_flush_possible += other.str8_draw_flush_possible;
+        self.backdoor_str8_draw += other.backdoor_str8_draw;
+        self.one_overcard += other.one_overcard;
+        self.two_overcards += other.two_overcards;
+        self.lo_paired += other.lo_paired;
+        self.hi_paired += other.hi_paired;
+        self.pp_paired += other.pp_paired;
+        //The outs don't depend on the iterations, so either side's list will do
+        if self.outs.is_empty() {
+            self.outs = other.outs.clone();
         }
     }
 }
@@ -53,6 +96,11 @@ fn update_draw(results: &mut Draws, prc: &PartialRankContainer) {
             StraightDrawType::OpenEnded => results.str8_draw += 1,
             StraightDrawType::DoubleGutShot => results.str8_draw += 1,
         }
+        if sd.flush_possible {
+            results.str8_draw_flush_possible += 1;
+        }
+    } else if prc.backdoor_straight_draw {
+        results.backdoor_str8_draw += 1;
     }
 
     if let Some(fd) = prc.flush_draw.as_ref() {
@@ -62,6 +110,18 @@ fn update_draw(results: &mut Draws, prc: &PartialRankContainer) {
         }
     }
 
+    if prc.has_nut_flush_draw() {
+        results.nut_flush_draw += 1;
+    }
+
+    if prc.has_combo_draw() {
+        results.combo_draw += 1;
+    }
+
+    if prc.has_pair_and_draw() {
+        results.pair_and_draw += 1;
+    }
+
     if let Some(_pp) = prc.pocket_pair.as_ref() {
         results.pp_paired += 1;
     }
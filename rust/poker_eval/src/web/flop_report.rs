@@ -0,0 +1,414 @@
+use std::{collections::BTreeMap, fmt::Display, io::Write};
+
+use itertools::Itertools;
+use log::info;
+use serde::Serialize;
+
+use crate::{
+    calc_board_texture, derive_seed, is_suit_symmetric,
+    pre_calc::{rank::Rank, RANK_FAMILY_OFFEST},
+    web::{flop_analyzer, PlayerPreFlopState},
+    BoardTexture, CanonicalFlop, PokerError, WeightedRange, NUM_RANK_FAMILIES,
+};
+
+/*
+How range A does against range B on every distinct flop, using flop_analyzer::simulate_flop,
+then averaged by the kind of flop, weighted by how many of the 22,100 flops each one stands for.
+
+Ex: to go through all flops
+
+build_flop_report("22+,A2s+,KTs+,ATo+", "22+,A2+,K9+,QT+,JT", &CANONICAL_FLOPS, 10_000, DEFAULT_SEED)
+*/
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlopSuits {
+    Rainbow,
+    TwoTone,
+    Monotone,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlopPairing {
+    Unpaired,
+    Paired,
+    Trips,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FlopTextureClass {
+    pub suits: FlopSuits,
+    pub pairing: FlopPairing,
+    //2 hole cards can make a straight
+    pub connected: bool,
+    //How many cards are J or higher
+    pub num_high_cards: u8,
+}
+
+impl From<&BoardTexture> for FlopTextureClass {
+    fn from(texture: &BoardTexture) -> Self {
+        let suits = match texture.same_suited_max_count {
+            0 | 1 => FlopSuits::Rainbow,
+            2 => FlopSuits::TwoTone,
+            _ => FlopSuits::Monotone,
+        };
+        let pairing = if texture.has_trips {
+            FlopPairing::Trips
+        } else if texture.has_pair {
+            FlopPairing::Paired
+        } else {
+            FlopPairing::Unpaired
+        };
+
+        FlopTextureClass {
+            suits,
+            pairing,
+            connected: !texture.others_with_str8.is_empty(),
+            num_high_cards: texture.high_value_count,
+        }
+    }
+}
+
+impl Display for FlopTextureClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} {} {} high",
+            self.suits,
+            self.pairing,
+            if self.connected {
+                "Connected"
+            } else {
+                "Disconnected"
+            },
+            self.num_high_cards
+        )
+    }
+}
+
+//All for range A, on the flop unless it says otherwise
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FlopReportStats {
+    //Equity at showdown
+    pub equity: f64,
+    //Equity if there were no more cards
+    pub flop_equity: f64,
+    //How often range A has each hand family, high card first
+    pub made_family: [f64; NUM_RANK_FAMILIES],
+    pub flush_draw: f64,
+    pub backdoor_flush_draw: f64,
+    pub str8_draw: f64,
+    pub gut_shot: f64,
+}
+
+impl FlopReportStats {
+    fn add_weighted(&mut self, other: &FlopReportStats, weight: f64) {
+        self.equity += other.equity * weight;
+        self.flop_equity += other.flop_equity * weight;
+        for (f, other_f) in self.made_family.iter_mut().zip(other.made_family.iter()) {
+            *f += other_f * weight;
+        }
+        self.flush_draw += other.flush_draw * weight;
+        self.backdoor_flush_draw += other.backdoor_flush_draw * weight;
+        self.str8_draw += other.str8_draw * weight;
+        self.gut_shot += other.gut_shot * weight;
+    }
+
+    fn csv_header() -> Vec<String> {
+        let mut header = vec!["equity".to_string(), "flop_equity".to_string()];
+        for family_index in 0..NUM_RANK_FAMILIES {
+            let rank = Rank::from((family_index as u16) << RANK_FAMILY_OFFEST);
+            header.push(format!("% {}", rank.get_rank_enum()));
+        }
+        header.extend(
+            ["flush_draw", "backdoor_flush_draw", "str8_draw", "gut_shot"]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+        header
+    }
+
+    fn csv_values(&self) -> Vec<String> {
+        [self.equity, self.flop_equity]
+            .iter()
+            .chain(self.made_family.iter())
+            .chain(
+                [
+                    self.flush_draw,
+                    self.backdoor_flush_draw,
+                    self.str8_draw,
+                    self.gut_shot,
+                ]
+                .iter(),
+            )
+            .map(|v| format!("{:.4}", v))
+            .collect_vec()
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FlopReportEntry {
+    pub flop: String,
+    //How many flops are the same as this one up to suits
+    pub multiplicity: u32,
+    pub texture: FlopTextureClass,
+    pub stats: FlopReportStats,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TextureReportRow {
+    pub texture: FlopTextureClass,
+    pub num_flops: usize,
+    //Sum of the multiplicities, out of 22,100 when every flop is in the report
+    pub weight: u32,
+    pub stats: FlopReportStats,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FlopReport {
+    pub range_a: String,
+    pub range_b: String,
+    pub num_iterations_per_flop: u32,
+    pub flops: Vec<FlopReportEntry>,
+}
+
+pub fn build_flop_report(
+    range_a: &str,
+    range_b: &str,
+    flops: &[CanonicalFlop],
+    num_iterations_per_flop: u32,
+    seed: u64,
+) -> Result<FlopReport, PokerError> {
+    //Otherwise a flop would not be the same as its isomorphic flops
+    for range_str in [range_a, range_b] {
+        let range: WeightedRange = range_str.parse()?;
+        if !is_suit_symmetric(&range) {
+            return Err(PokerError::from_string(format!(
+                "Range {} depends on suits, use suited/offsuit combos like AKs or AKo",
+                range_str
+            )));
+        }
+    }
+
+    let mut analyzer = flop_analyzer::new();
+    analyzer.reset();
+    analyzer.set_player_state(0, PlayerPreFlopState::UseRange as u8);
+    analyzer.set_player_range(0, range_a)?;
+    analyzer.set_player_state(1, PlayerPreFlopState::UseRange as u8);
+    analyzer.set_player_range(1, range_b)?;
+
+    let mut entries = Vec::with_capacity(flops.len());
+
+    for (flop_index, flop) in flops.iter().enumerate() {
+        analyzer.set_board_cards(&flop.cards.iter().map(|c| c.index).collect_vec())?;
+        analyzer.set_seed(derive_seed(seed, flop_index as u64));
+
+        let results =
+            analyzer.simulate_flop(num_iterations_per_flop, analyzer.build_results(), false)?;
+
+        let draws = &results.flop_results[0].street_draws[0];
+        let num_draw_iterations = draws.num_iterations.max(1) as f64;
+        let mut made_family = [0.0; NUM_RANK_FAMILIES];
+        for (family_index, f) in made_family.iter_mut().enumerate() {
+            *f = results.get_perc_made_family(Some(0), 0, family_index);
+        }
+
+        entries.push(FlopReportEntry {
+            flop: flop.cards.iter().join(""),
+            multiplicity: flop.multiplicity,
+            texture: (&calc_board_texture(&flop.cards)).into(),
+            stats: FlopReportStats {
+                equity: results.get_equity(Some(0), 2),
+                flop_equity: results.get_equity(Some(0), 0),
+                made_family,
+                flush_draw: draws.flush_draw as f64 / num_draw_iterations,
+                backdoor_flush_draw: draws.backdoor_flush_draw as f64 / num_draw_iterations,
+                str8_draw: draws.str8_draw as f64 / num_draw_iterations,
+                gut_shot: draws.gut_shot as f64 / num_draw_iterations,
+            },
+        });
+
+        if flop_index % 100 == 99 {
+            info!(
+                "build_flop_report: {} of {} flops",
+                flop_index + 1,
+                flops.len()
+            );
+        }
+    }
+
+    Ok(FlopReport {
+        range_a: range_a.to_string(),
+        range_b: range_b.to_string(),
+        num_iterations_per_flop,
+        flops: entries,
+    })
+}
+
+impl FlopReport {
+    //Averages of the flops of each texture class, weighted by their multiplicity
+    pub fn by_texture(&self) -> Vec<TextureReportRow> {
+        let mut rows: BTreeMap<FlopTextureClass, TextureReportRow> = BTreeMap::new();
+
+        for entry in self.flops.iter() {
+            let row = rows.entry(entry.texture).or_insert(TextureReportRow {
+                texture: entry.texture,
+                num_flops: 0,
+                weight: 0,
+                stats: FlopReportStats::default(),
+            });
+            row.num_flops += 1;
+            row.weight += entry.multiplicity;
+            row.stats
+                .add_weighted(&entry.stats, entry.multiplicity as f64);
+        }
+
+        rows.into_values()
+            .map(|mut row| {
+                let mut stats = FlopReportStats::default();
+                stats.add_weighted(&row.stats, 1.0 / row.weight as f64);
+                row.stats = stats;
+                row
+            })
+            .collect_vec()
+    }
+
+    //One line per flop
+    pub fn write_flops_csv<W: Write>(&self, writer: W) -> Result<(), PokerError> {
+        let mut header = vec!["flop", "multiplicity", "texture"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect_vec();
+        header.extend(FlopReportStats::csv_header());
+
+        write_csv(
+            writer,
+            header,
+            self.flops.iter().map(|entry| {
+                let mut values = vec![
+                    entry.flop.clone(),
+                    entry.multiplicity.to_string(),
+                    entry.texture.to_string(),
+                ];
+                values.extend(entry.stats.csv_values());
+                values
+            }),
+        )
+    }
+
+    //One line per texture class
+    pub fn write_texture_csv<W: Write>(&self, writer: W) -> Result<(), PokerError> {
+        let mut header = vec!["texture", "num_flops", "weight"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect_vec();
+        header.extend(FlopReportStats::csv_header());
+
+        write_csv(
+            writer,
+            header,
+            self.by_texture().iter().map(|row| {
+                let mut values = vec![
+                    row.texture.to_string(),
+                    row.num_flops.to_string(),
+                    row.weight.to_string(),
+                ];
+                values.extend(row.stats.csv_values());
+                values
+            }),
+        )
+    }
+
+    //Both the flops and the texture classes
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), PokerError> {
+        #[derive(Serialize)]
+        struct JsonReport<'a> {
+            report: &'a FlopReport,
+            by_texture: Vec<TextureReportRow>,
+        }
+
+        serde_json::to_writer_pretty(
+            writer,
+            &JsonReport {
+                report: self,
+                by_texture: self.by_texture(),
+            },
+        )
+        .map_err(|e| PokerError::from_string(format!("Unable to write json: {}", e)))
+    }
+}
+
+fn write_csv<W: Write>(
+    writer: W,
+    header: Vec<String>,
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result<(), PokerError> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(&header)
+        .map_err(|e| PokerError::from_string(format!("Unable to write csv: {}", e)))?;
+    for row in rows {
+        wtr.write_record(&row)
+            .map_err(|e| PokerError::from_string(format!("Unable to write csv: {}", e)))?;
+    }
+    wtr.flush()
+        .map_err(|e| PokerError::from_string(format!("Unable to write csv: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pre_calc::rank::RankEnum, CANONICAL_FLOPS, DEFAULT_SEED};
+
+    use super::*;
+
+    #[test]
+    fn test_flop_report() {
+        //A spread of flops to keep it fast
+        let flops = CANONICAL_FLOPS.iter().step_by(97).cloned().collect_vec();
+        let report = build_flop_report("AA,KK", "72o,T9s", &flops, 200, DEFAULT_SEED).unwrap();
+
+        assert_eq!(report.flops.len(), flops.len());
+        for entry in report.flops.iter() {
+            //Overpairs vs a weak range
+            assert!(entry.stats.equity > 0.5, "{:?}", entry);
+            assert!((entry.stats.made_family.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            //Pocket pairs are never high card
+            assert_eq!(entry.stats.made_family[RankEnum::HighCard as usize], 0.0);
+        }
+
+        let by_texture = report.by_texture();
+        assert_eq!(
+            by_texture.iter().map(|r| r.num_flops).sum::<usize>(),
+            flops.len()
+        );
+        assert_eq!(
+            by_texture.iter().map(|r| r.weight).sum::<u32>(),
+            flops.iter().map(|f| f.multiplicity).sum::<u32>()
+        );
+        assert!(by_texture
+            .iter()
+            .all(|r| r.stats.equity > 0.5 && r.stats.equity <= 1.0));
+
+        let mut csv = Vec::new();
+        report.write_flops_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), flops.len() + 1);
+        assert!(csv.starts_with("flop,multiplicity,texture,equity,flop_equity,% High Card"));
+
+        let mut csv = Vec::new();
+        report.write_texture_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap().lines().count(),
+            by_texture.len() + 1
+        );
+
+        let mut json = Vec::new();
+        report.write_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json["report"]["flops"].as_array().unwrap().len(),
+            flops.len()
+        );
+
+        //A suit specific range can't stand in for the other suits
+        assert!(build_flop_report("AhKh", "72o", &flops, 200, DEFAULT_SEED).is_err());
+    }
+}
@@ -16,4 +16,12 @@ pub use ranks::*;
 mod preflop_player_info;
 pub use preflop_player_info::*;
 
+mod runout_matrix;
+pub use runout_matrix::*;
+
 mod test_analyser;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod flop_report;
+#[cfg(not(target_arch = "wasm32"))]
+pub use flop_report::*;
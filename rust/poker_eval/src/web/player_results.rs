@@ -20,6 +20,21 @@ pub struct PlayerFlopResults {
     pub(crate) street_draws: [Draws; 2],
 }
 
+impl PlayerFlopResults {
+    pub fn merge(&mut self, other: &PlayerFlopResults) {
+        for (r, other_r) in self
+            .street_rank_results
+            .iter_mut()
+            .zip(other.street_rank_results.iter())
+        {
+            r.merge(other_r);
+        }
+        for (d, other_d) in self.street_draws.iter_mut().zip(other.street_draws.iter()) {
+            d.merge(other_d);
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl PlayerFlopResults {
     pub fn new() -> Self {
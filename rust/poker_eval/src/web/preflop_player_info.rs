@@ -2,7 +2,7 @@ use std::{cmp, mem};
 
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::{BoolRange, Deck, HoleCards, PokerError, ALL_HOLE_CARDS};
+use crate::{Deck, HoleCards, HoleCardsSampler, PokerError, WeightedRange, ALL_HOLE_CARDS};
 
 #[derive(Eq, PartialEq, Debug)]
 #[repr(u8)]
@@ -30,47 +30,39 @@ pub struct PreflopPlayerInfo {
     pub(crate) range_string: String,
     //results: Results,
     pub(crate) hole_cards: Option<HoleCards>,
-    pub(crate) range: BoolRange,
+    pub(crate) range: WeightedRange,
     pub(crate) state: PlayerPreFlopState,
 }
 
+/*
+sampler deals the hole cards for the players using a range, in the same order as active_players
+*/
 pub fn get_all_player_hole_cards(
     //usize is the original player index
     // we may have removed some players
     active_players: &[(usize, &PreflopPlayerInfo)],
     deck: &mut Deck,
-    //usize is the index in active players
-    all_possible_hole_cards: &Vec<(usize, Vec<HoleCards>)>,
+    sampler: &HoleCardsSampler,
 ) -> Result<Vec<HoleCards>, PokerError> {
-    let mut player_cards: Vec<HoleCards> = vec![ALL_HOLE_CARDS[0]; active_players.len()];
+    let mut range_hole_cards: Vec<HoleCards> = vec![ALL_HOLE_CARDS[0]; sampler.num_players()];
+    sampler.sample(deck, &mut range_hole_cards)?;
 
-    let mut hole_cards_set = 0;
+    let mut range_hole_cards = range_hole_cards.into_iter();
+    let mut player_cards: Vec<HoleCards> = Vec::with_capacity(active_players.len());
 
-    //Fill in hole cards for players that have them
-    for (active_player_index, (_player_index, p)) in active_players.iter().enumerate() {
-        if p.state != PlayerPreFlopState::UseHoleCards {
-            continue;
-        }
-
-        hole_cards_set += 1;
-        let pc = p.hole_cards.ok_or(PokerError::from_string(format!(
-            "Player missing hole cards"
-        )))?;
-        player_cards[active_player_index] = pc;
-    }
-
-    assert_eq!(
-        active_players.len(),
-        all_possible_hole_cards.len() + hole_cards_set
-    );
-
-    for (active_player_index, possible_hole_cards) in all_possible_hole_cards.iter() {
-        let p = active_players[*active_player_index].1;
-        assert!(p.state == PlayerPreFlopState::UseRange);
-
-        let hc = deck.choose_available_in_range(possible_hole_cards)?;
-
-        player_cards[*active_player_index] = hc;
+    for (_player_index, p) in active_players.iter() {
+        let hc = match p.state {
+            PlayerPreFlopState::UseHoleCards => p
+                .hole_cards
+                .ok_or(PokerError::from_str("Player missing hole cards"))?,
+            PlayerPreFlopState::UseRange => range_hole_cards.next().ok_or(PokerError::from_str(
+                "Sampler has fewer players than the active range players",
+            ))?,
+            PlayerPreFlopState::Disabled => {
+                return Err(PokerError::from_str("Disabled player is not active"))
+            }
+        };
+        player_cards.push(hc);
     }
 
     Ok(player_cards)
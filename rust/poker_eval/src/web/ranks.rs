@@ -1,9 +1,12 @@
 use boomphf::Mphf;
 
-use crate::pre_calc::fast_eval::fast_hand_eval;
+use crate::pre_calc::fast_eval::fast_hand_eval_for_variant;
 use crate::pre_calc::rank::Rank;
 use crate::web::{PlayerFlopResults, PlayerPreFlopState, PreflopPlayerInfo, ResultType};
-use crate::{Card, HoleCards, PokerError, NUM_RANK_FAMILIES, SIMPLE_RANGE_INDEX_LEN};
+use crate::{
+    std_error_of_mean, Card, DeckVariant, HoleCards, PokerError, NUM_RANK_FAMILIES,
+    SIMPLE_RANGE_INDEX_LEN,
+};
 
 pub struct RankResults {
     pub(crate) num_iterations: ResultType,
@@ -11,6 +14,8 @@ pub struct RankResults {
     //win = 1, tie = 1 / num players in tie, loss = 0
     pub(crate) win_eq: f64,
     pub(crate) tie_eq: f64,
+    //Sum of the squared equity of each iteration, for the standard error
+    pub(crate) eq_sum_sq: f64,
 
     //Also track equity by the simplified hole card range index
     pub(crate) eq_by_range_index: Vec<f64>,
@@ -20,6 +25,9 @@ pub struct RankResults {
     //float because a tie counts for a fraction of a win
     pub(crate) win_rank_family_count: [f64; NUM_RANK_FAMILIES],
     pub(crate) lose_rank_family_count: [ResultType; NUM_RANK_FAMILIES],
+
+    //How often the player had each hand rank, win or lose
+    pub(crate) rank_family_count: [ResultType; NUM_RANK_FAMILIES],
 }
 
 impl Default for RankResults {
@@ -29,26 +37,93 @@ impl Default for RankResults {
             num_it_by_range_index: vec![0; SIMPLE_RANGE_INDEX_LEN],
             win_rank_family_count: [0.0; NUM_RANK_FAMILIES],
             lose_rank_family_count: [0; NUM_RANK_FAMILIES],
+            rank_family_count: [0; NUM_RANK_FAMILIES],
             num_iterations: 0,
             win_eq: 0.0,
             tie_eq: 0.0,
+            eq_sum_sq: 0.0,
         }
     }
 }
 
+impl RankResults {
+    pub fn equity_std_error(&self) -> f64 {
+        std_error_of_mean(
+            self.win_eq + self.tie_eq,
+            self.eq_sum_sq,
+            self.num_iterations as usize,
+        )
+    }
+
+    pub fn merge(&mut self, other: &RankResults) {
+        self.num_iterations += other.num_iterations;
+        self.win_eq += other.win_eq;
+        self.tie_eq += other.tie_eq;
+        self.eq_sum_sq += other.eq_sum_sq;
+
+        for (eq, other_eq) in self
+            .eq_by_range_index
+            .iter_mut()
+            .zip(other.eq_by_range_index.iter())
+        {
+            *eq += other_eq;
+        }
+        for (n, other_n) in self
+            .num_it_by_range_index
+            .iter_mut()
+            .zip(other.num_it_by_range_index.iter())
+        {
+            *n += other_n;
+        }
+        for (c, other_c) in self
+            .win_rank_family_count
+            .iter_mut()
+            .zip(other.win_rank_family_count.iter())
+        {
+            *c += other_c;
+        }
+        for (c, other_c) in self
+            .lose_rank_family_count
+            .iter_mut()
+            .zip(other.lose_rank_family_count.iter())
+        {
+            *c += other_c;
+        }
+        for (c, other_c) in self
+            .rank_family_count
+            .iter_mut()
+            .zip(other.rank_family_count.iter())
+        {
+            *c += other_c;
+        }
+    }
+}
+
+//The players in the hand, the hole cards they were dealt this iteration and the board so far
+pub struct EvalDeal<'a> {
+    pub active_players: &'a [(usize, &'a PreflopPlayerInfo)],
+    pub player_cards: &'a [HoleCards],
+    pub eval_cards: &'a mut Vec<Card>,
+}
+
 /*
 Assumes all players have either hole cards or their ranges chosen
 */
 pub fn eval_current(
-    active_players: &[(usize, &PreflopPlayerInfo)],
-    player_cards: &[HoleCards],
-    eval_cards: &mut Vec<Card>,
+    deal: EvalDeal,
     flop_results: &mut Vec<PlayerFlopResults>,
     //treat first active player as the hero, all others as villians
     villian_results: &mut PlayerFlopResults,
     street_index: usize,
     hash_func: &Mphf<u32>,
+    deck_variant: DeckVariant,
 ) -> Result<(), PokerError> {
+    let EvalDeal {
+        active_players,
+        player_cards,
+        eval_cards,
+    } = deal;
+
     if eval_cards.len() < 3 {
         return Err(PokerError::from_string(format!(
             "eval_current: eval_cards needs at least 3 cards, but had {} cards",
@@ -75,7 +150,7 @@ pub fn eval_current(
 
         player_cards[active_index].add_to_eval(eval_cards);
 
-        let rank = fast_hand_eval(eval_cards.iter(), hash_func);
+        let rank = fast_hand_eval_for_variant(eval_cards.iter(), hash_func, deck_variant);
 
         flop_results[active_index].street_rank_results[street_index].num_it_by_range_index
             [player_cards[active_index].to_simple_range_index()] += 1;
@@ -91,11 +166,15 @@ pub fn eval_current(
     }
 
     //Best villian hand
-    let best_villian_rank = hand_evals[1..]
-        .iter()
-        .fold(Rank::lowest_rank(), |acc, &x| acc.max(x));
+    let best_villian_rank = hand_evals[1..].iter().fold(Rank::lowest_rank(), |acc, &x| {
+        if x.strength(deck_variant) > acc.strength(deck_variant) {
+            x
+        } else {
+            acc
+        }
+    });
 
-    let (max_value, num_with_max) = winning_rank(&hand_evals);
+    let (max_value, num_with_max) = winning_rank(&hand_evals, deck_variant);
 
     //cases
     //hero wins, hero ties with villian, hero loses
@@ -126,6 +205,8 @@ pub fn eval_current(
     if villian_rnk_amt == 1.0 {
         villian_results.street_rank_results[street_index].win_eq += 1.0;
     }
+    villian_results.street_rank_results[street_index].eq_sum_sq +=
+        villian_rnk_amt * villian_rnk_amt;
 
     for (active_player_index, rank) in hand_evals.iter().enumerate() {
         if *rank == max_value {
@@ -135,6 +216,7 @@ pub fn eval_current(
             } else {
                 results.tie_eq += 1.0 / num_with_max as f64;
             }
+            results.eq_sum_sq += 1.0 / (num_with_max * num_with_max) as f64;
 
             //Update equity by range index
             let range_index = player_cards[active_player_index].to_simple_range_index();
@@ -167,6 +249,7 @@ pub fn eval_current(
 
 pub(crate) fn update_results_from_rank(results: &mut RankResults, rank: &Rank, amt: f64) {
     results.num_iterations += 1;
+    results.rank_family_count[rank.get_rank_enum() as u8 as usize] += 1;
     if amt == 0.0 {
         results.lose_rank_family_count[rank.get_rank_enum() as u8 as usize] += 1;
     } else {
@@ -175,12 +258,12 @@ pub(crate) fn update_results_from_rank(results: &mut RankResults, rank: &Rank, a
 }
 
 //returns winners and how many players were considered (non None rank)
-pub(crate) fn winning_rank(arr: &[Rank]) -> (Rank, usize) {
+pub(crate) fn winning_rank(arr: &[Rank], deck_variant: DeckVariant) -> (Rank, usize) {
     let mut max_value = Rank::lowest_rank();
     let mut num_with_max = 0;
 
     for &value in arr.iter() {
-        if value > max_value {
+        if value.strength(deck_variant) > max_value.strength(deck_variant) {
             max_value = value;
             num_with_max = 1;
         } else if value == max_value {
@@ -0,0 +1,204 @@
+use serde::Serialize;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{web::FlopSimulationResults, Card, PokerError, NUM_RANK_FAMILIES};
+
+pub const NUM_RUNOUT_CARDS: usize = 52;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RunoutPlayerResult {
+    //Equity on the river once the card is out
+    pub equity: f64,
+    //vs the equity before the card, < 0 means the card is bad for the player
+    pub equity_change: f64,
+    //How often the player has each family on the runout card's street
+    pub made_family: [f64; NUM_RANK_FAMILIES],
+    //vs the families on the current street
+    pub family_change: [f64; NUM_RANK_FAMILIES],
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RunoutCard {
+    pub card: Card,
+    //By active player index
+    pub players: Vec<RunoutPlayerResult>,
+}
+
+/*
+What each turn card does on a flop, or each river card on a turn.
+
+Indexed by card index, cards on the board, in hole cards or not in the deck have no runout
+*/
+#[wasm_bindgen]
+pub struct RunoutMatrix {
+    //1 if the runout cards are turns, 2 if rivers
+    pub(crate) street_index: usize,
+    //By active player index, river equity without fixing the next card
+    pub(crate) base_equity: Vec<f64>,
+    pub(crate) runouts: Vec<Option<RunoutCard>>,
+}
+
+impl RunoutMatrix {
+    pub(crate) fn new(street_index: usize, base_results: &FlopSimulationResults) -> Self {
+        let base_equity = (0..base_results.get_num_players())
+            .map(|p_idx| base_results.get_equity(Some(p_idx), 2))
+            .collect();
+        RunoutMatrix {
+            street_index,
+            base_equity,
+            runouts: vec![None; NUM_RUNOUT_CARDS],
+        }
+    }
+
+    pub(crate) fn set_runout(
+        &mut self,
+        card: Card,
+        base_results: &FlopSimulationResults,
+        card_results: &FlopSimulationResults,
+    ) {
+        let players = (0..card_results.get_num_players())
+            .map(|p_idx| {
+                let mut made_family = [0.0; NUM_RANK_FAMILIES];
+                let mut family_change = [0.0; NUM_RANK_FAMILIES];
+                for family_index in 0..NUM_RANK_FAMILIES {
+                    made_family[family_index] = card_results.get_perc_made_family(
+                        Some(p_idx),
+                        self.street_index,
+                        family_index,
+                    );
+                    family_change[family_index] = made_family[family_index]
+                        - base_results.get_perc_made_family(
+                            Some(p_idx),
+                            self.street_index - 1,
+                            family_index,
+                        );
+                }
+                let equity = card_results.get_equity(Some(p_idx), 2);
+                RunoutPlayerResult {
+                    equity,
+                    equity_change: equity - self.base_equity[p_idx],
+                    made_family,
+                    family_change,
+                }
+            })
+            .collect();
+
+        self.runouts[card.index as usize] = Some(RunoutCard { card, players });
+    }
+
+    pub fn get_runout_card(&self, card_index: usize) -> Option<&RunoutCard> {
+        self.runouts.get(card_index).and_then(|r| r.as_ref())
+    }
+
+    //Every runout card has a result for each player in base_equity
+    fn check_active_player_index(&self, active_player_index: usize) -> Result<(), PokerError> {
+        if active_player_index >= self.base_equity.len() {
+            return Err(PokerError::from_string(format!(
+                "Invalid active player index {}",
+                active_player_index
+            )));
+        }
+        Ok(())
+    }
+
+    fn get_player_result(
+        &self,
+        active_player_index: usize,
+        card_index: usize,
+    ) -> Result<&RunoutPlayerResult, PokerError> {
+        self.get_runout_card(card_index)
+            .ok_or(PokerError::from_string(format!(
+                "No runout for card index {}",
+                card_index
+            )))?
+            .players
+            .get(active_player_index)
+            .ok_or(PokerError::from_string(format!(
+                "Invalid active player index {}",
+                active_player_index
+            )))
+    }
+}
+
+#[wasm_bindgen]
+impl RunoutMatrix {
+    pub fn get_street_index(&self) -> usize {
+        self.street_index
+    }
+
+    pub fn get_num_players(&self) -> usize {
+        self.base_equity.len()
+    }
+
+    pub fn get_base_equity(&self, active_player_index: usize) -> Result<f64, PokerError> {
+        self.check_active_player_index(active_player_index)?;
+        Ok(self.base_equity[active_player_index])
+    }
+
+    pub fn is_possible(&self, card_index: usize) -> bool {
+        self.get_runout_card(card_index).is_some()
+    }
+
+    pub fn get_equity(
+        &self,
+        active_player_index: usize,
+        card_index: usize,
+    ) -> Result<f64, PokerError> {
+        Ok(self
+            .get_player_result(active_player_index, card_index)?
+            .equity)
+    }
+
+    pub fn get_equity_change(
+        &self,
+        active_player_index: usize,
+        card_index: usize,
+    ) -> Result<f64, PokerError> {
+        Ok(self
+            .get_player_result(active_player_index, card_index)?
+            .equity_change)
+    }
+
+    pub fn get_perc_made_family(
+        &self,
+        active_player_index: usize,
+        card_index: usize,
+        family_index: usize,
+    ) -> Result<f64, PokerError> {
+        Ok(self
+            .get_player_result(active_player_index, card_index)?
+            .made_family[family_index])
+    }
+
+    pub fn get_family_change(
+        &self,
+        active_player_index: usize,
+        card_index: usize,
+        family_index: usize,
+    ) -> Result<f64, PokerError> {
+        Ok(self
+            .get_player_result(active_player_index, card_index)?
+            .family_change[family_index])
+    }
+
+    //Card indexes where the player's equity drops by at least min_equity_drop
+    pub fn get_scare_cards(
+        &self,
+        active_player_index: usize,
+        min_equity_drop: f64,
+    ) -> Result<Vec<u8>, PokerError> {
+        self.check_active_player_index(active_player_index)?;
+        Ok(self
+            .runouts
+            .iter()
+            .flatten()
+            .filter(|r| r.players[active_player_index].equity_change <= -min_equity_drop)
+            .map(|r| r.card.index)
+            .collect())
+    }
+
+    //The 52 entry table, null for cards that can't come
+    pub fn get_table(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.runouts)?)
+    }
+}
@@ -1,7 +1,10 @@
 use log::info;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
-use crate::{web::PlayerFlopResults, PokerError, NUM_RANK_FAMILIES};
+use crate::{
+    web::{PlayerFlopResults, RankResults},
+    PokerError, NUM_RANK_FAMILIES, Z_SCORE_95,
+};
 
 #[wasm_bindgen]
 pub struct FlopSimulationResults {
@@ -11,6 +14,46 @@ pub struct FlopSimulationResults {
     pub(crate) flop_results: Vec<PlayerFlopResults>,
 }
 
+impl FlopSimulationResults {
+    //Adds the results of another simulation of the same players, i.e. from another thread
+    pub fn merge(&mut self, other: &FlopSimulationResults) -> Result<(), PokerError> {
+        if self.flop_results.len() != other.flop_results.len() {
+            return Err(PokerError::from_string(format!(
+                "merge: flop_results.len() {} != other flop_results.len() {}",
+                self.flop_results.len(),
+                other.flop_results.len()
+            )));
+        }
+        for (results, other_results) in self.flop_results.iter_mut().zip(other.flop_results.iter())
+        {
+            results.merge(other_results);
+        }
+        self.all_villians.merge(&other.all_villians);
+        Ok(())
+    }
+
+    //null active_player_index means all villians
+    fn get_rank_results(
+        &self,
+        active_player_index: Option<usize>,
+        street_index: usize,
+    ) -> &RankResults {
+        if let Some(p_idx) = active_player_index {
+            &self.flop_results[p_idx].street_rank_results[street_index]
+        } else {
+            &self.all_villians.street_rank_results[street_index]
+        }
+    }
+
+    //Largest standard error of any player's equity on the river
+    pub fn max_river_std_error(&self) -> f64 {
+        self.flop_results
+            .iter()
+            .map(|r| r.street_rank_results[2].equity_std_error())
+            .fold(0.0, f64::max)
+    }
+}
+
 #[wasm_bindgen]
 impl FlopSimulationResults {
     //note these player_indexes are the index of active players
@@ -49,17 +92,42 @@ impl FlopSimulationResults {
         total
     }
 
+    //How often the player had a hand of this family, win or lose
+    pub fn get_perc_made_family(
+        &self,
+        active_player_index: Option<usize>,
+        street_index: usize,
+        family_index: usize,
+    ) -> f64 {
+        let r = self.get_rank_results(active_player_index, street_index);
+        r.rank_family_count[family_index] as f64 / r.num_iterations as f64
+    }
+
     //null active_player_index means all villians
     pub fn get_equity(&self, active_player_index: Option<usize>, street_index: usize) -> f64 {
-        let r = if let Some(p_idx) = active_player_index {
-            &self.flop_results[p_idx].street_rank_results[street_index]
-        } else {
-            &self.all_villians.street_rank_results[street_index]
-        };
+        let r = self.get_rank_results(active_player_index, street_index);
 
         (r.win_eq + r.tie_eq) / r.num_iterations as f64
     }
 
+    pub fn get_equity_std_error(
+        &self,
+        active_player_index: Option<usize>,
+        street_index: usize,
+    ) -> f64 {
+        self.get_rank_results(active_player_index, street_index)
+            .equity_std_error()
+    }
+
+    //Half the width of the 95% confidence interval, so the equity is get_equity +- this
+    pub fn get_equity_error_bar(
+        &self,
+        active_player_index: Option<usize>,
+        street_index: usize,
+    ) -> f64 {
+        Z_SCORE_95 * self.get_equity_std_error(active_player_index, street_index)
+    }
+
     pub fn get_range_equity(
         &self,
         active_player_index: Option<usize>,
@@ -91,7 +159,7 @@ impl FlopSimulationResults {
             .ok_or(PokerError::from_str("Unable to convert to js value"))?)
     }
 
-    //This guy has no arrays, so we can just convert it to json
+    //This guy has no big arrays, so we can just convert it to json, including the outs if calc_outs was called
     pub fn get_street_draw(
         &self,
         active_player_index: Option<usize>,
@@ -1,5 +1,8 @@
 extern crate wasm_bindgen;
-use poker_eval::{pre_calc::NUMBER_OF_HOLE_CARDS, BoolRange, CardValue, PokerError};
+use poker_eval::{
+    pre_calc::NUMBER_OF_HOLE_CARDS, Card, CardValue, PokerError, WeightedRange,
+    SIMPLE_RANGE_INDEX_COMBOS,
+};
 //use postflop_solver::*;
 use log::debug;
 use std::cmp::Ordering;
@@ -7,7 +10,7 @@ use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 pub struct RangeManager {
-    range: BoolRange,
+    range: WeightedRange,
 }
 
 #[wasm_bindgen]
@@ -21,26 +24,37 @@ impl RangeManager {
         debug!("RangeManager::new()");
 
         Self {
-            range: BoolRange::default(),
+            range: WeightedRange::default(),
         }
     }
 
     pub fn clear(&mut self) {
-        self.range.data.fill(false)
+        self.range.data.fill(0.0)
     }
 
     //row/col are 1 based
     pub fn update(&mut self, row: u8, col: u8, is_enabled: bool) -> Result<(), PokerError> {
+        self.update_weight(row, col, if is_enabled { 1.0 } else { 0.0 })
+    }
+
+    //row/col are 1 based, weight is between 0 and 1
+    pub fn update_weight(&mut self, row: u8, col: u8, weight: f32) -> Result<(), PokerError> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(PokerError::from_string(format!(
+                "update_weight: weight {} must be between 0 and 1",
+                weight
+            )));
+        }
         let rank1: CardValue = (13 - row).try_into()?;
         let rank2: CardValue = (13 - col).try_into()?;
         debug!(
             "update: row: {}, col: {}, rank1: {}, rank2: {} ==> {}",
-            row, col, rank1, rank2, is_enabled
+            row, col, rank1, rank2, weight
         );
         match row.cmp(&col) {
-            Ordering::Equal => self.range.set_enabled_pair(rank1, is_enabled),
-            Ordering::Less => self.range.set_enabled_suited(rank1, rank2, is_enabled),
-            Ordering::Greater => self.range.set_enabled_offsuit(rank1, rank2, is_enabled),
+            Ordering::Equal => self.range.set_weight_pair(rank1, weight),
+            Ordering::Less => self.range.set_weight_suited(rank1, rank2, weight),
+            Ordering::Greater => self.range.set_weight_offsuit(rank1, rank2, weight),
         }
         debug!("update: range: {}", self.range.to_string());
         Ok(())
@@ -48,7 +62,8 @@ impl RangeManager {
 
     pub fn from_string(&mut self, s: &str) -> Result<(), PokerError> {
         //info!("from_string: {}", s);
-        let result: BoolRange = s.parse()?;
+        //Weights are optional, AKs:0.5,QQ+
+        let result: WeightedRange = s.parse()?;
         self.range = result;
         Ok(())
     }
@@ -87,10 +102,13 @@ impl RangeManager {
         let rank1: CardValue = (12 - row).try_into()?;
         let rank2: CardValue = (12 - col).try_into()?;
 
+        //Comments are on which combos are in the range at all, ignoring the weights
+        let bool_range = self.range.to_bool_range();
+
         let st = match row.cmp(&col) {
-            Ordering::Equal => self.range.get_weight_pair_comment(rank1),
-            Ordering::Less => self.range.get_weight_suited_comment(rank1, rank2),
-            Ordering::Greater => self.range.get_weight_offsuit_comment(rank1, rank2),
+            Ordering::Equal => bool_range.get_weight_pair_comment(rank1),
+            Ordering::Less => bool_range.get_weight_suited_comment(rank1, rank2),
+            Ordering::Greater => bool_range.get_weight_offsuit_comment(rank1, rank2),
         };
         Ok(st)
     }
@@ -98,8 +116,60 @@ impl RangeManager {
     pub fn raw_data(&self) -> Box<[u8]> {
         let mut data = vec![0u8; NUMBER_OF_HOLE_CARDS];
         for i in 0..NUMBER_OF_HOLE_CARDS {
-            data[i] = self.range.data[i] as u8;
+            data[i] = (self.range.data[i] > 0.0) as u8;
         }
         data.into()
     }
+
+    //The weight of each hole card combo, indexed like raw_data
+    pub fn raw_weights(&self) -> Box<[f32]> {
+        self.range.data.clone().into()
+    }
+
+    //Range algebra, the other range is in the same string format as from_string
+    pub fn union(&mut self, other: &str) -> Result<(), PokerError> {
+        let other: WeightedRange = other.parse()?;
+        self.range = self.range.union(&other);
+        Ok(())
+    }
+
+    pub fn intersect(&mut self, other: &str) -> Result<(), PokerError> {
+        let other: WeightedRange = other.parse()?;
+        self.range = self.range.intersection(&other);
+        Ok(())
+    }
+
+    pub fn subtract(&mut self, other: &str) -> Result<(), PokerError> {
+        let other: WeightedRange = other.parse()?;
+        self.range = self.range.difference(&other);
+        Ok(())
+    }
+
+    pub fn complement(&mut self) {
+        self.range = self.range.complement();
+    }
+
+    //Card removal, cards are the card indexes (0-51) on the board / in hero's hand
+    pub fn remove_cards(&mut self, cards: &[u8]) -> Result<(), PokerError> {
+        let cards: Vec<Card> = cards
+            .iter()
+            .map(|c| Card::try_from(*c))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.range.remove_cards(&cards);
+        Ok(())
+    }
+
+    //Weighted number of combos in the range
+    pub fn num_combos(&self) -> f64 {
+        self.range.get_num_combos()
+    }
+
+    //Weighted number of combos for each of the 169 hand classes, in simple range index order
+    pub fn get_class_combo_counts(&self) -> Box<[f32]> {
+        SIMPLE_RANGE_INDEX_COMBOS
+            .iter()
+            .map(|indices| indices.iter().map(|i| self.range.data[*i]).sum())
+            .collect::<Vec<f32>>()
+            .into()
+    }
 }
@@ -15,6 +15,12 @@ interface NarrowStoreData {
 
   // See LikesHandLevel
   likesHandMinimum: number;
+
+  //narrow over flop, turn and river using actionLine instead of likesHandMinimum
+  useActionLine: boolean;
+
+  // See StreetAction, one per street
+  actionLine: Array<number>;
 }
 
 export const useNarrowStore = defineStore('narrow', () => {
@@ -37,7 +43,9 @@ export const useNarrowStore = defineStore('narrow', () => {
         }
       ],
       useEquity: true,
-      likesHandMinimum: 2 // see LikesHandLevel
+      likesHandMinimum: 2, // see LikesHandLevel
+      useActionLine: false,
+      actionLine: [1, 0, 0] // see StreetAction
     },
     {
       mergeDefaults: true
@@ -61,8 +69,22 @@ export const useNarrowStore = defineStore('narrow', () => {
     return 'Any';
   }
 
+  function getStreetActionString(streetAction: number): string {
+    switch (streetAction) {
+      case 0:
+        return 'No bet';
+      case 1:
+        return 'Small bet (<= 1/2 pot)';
+      case 2:
+        return 'Large bet';
+    }
+
+    return 'No bet';
+  }
+
   return {
     getLikesHandMinimumString,
+    getStreetActionString,
     state
   };
 });
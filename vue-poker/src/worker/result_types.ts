@@ -7,6 +7,8 @@ export interface PercOrBetter {
 
 export interface StreetResults {
   equity: number;
+  //Half width of the 95% confidence interval
+  equity_error_bar: number;
   //Includes ties
   win_rank_family_count: Array<PercOrBetter>;
   lose_rank_family_count: Array<PercOrBetter>;
@@ -110,6 +110,22 @@ const createHandler = (mod: Mod) => {
         num_active_players
       );
       return result;
+    },
+
+    //Returns the narrowed range after each street in the line
+    narrowRangeByLine(
+      range_to_narrow: string,
+      action_line: Uint8Array,
+      cards: Uint8Array,
+      num_active_players: number
+    ): Array<string> {
+      const result = this.flop_analyzer.narrow_range_by_line(
+        range_to_narrow,
+        action_line,
+        cards,
+        num_active_players
+      );
+      return result.split(';');
     }
   };
 };
@@ -125,6 +141,7 @@ function buildResultsInterface(
   for (let i = 0; i < 3; i++) {
     const sr: StreetResults = {
       equity: r.get_equity(active_player_index, i),
+      equity_error_bar: r.get_equity_error_bar(active_player_index, i),
       win_rank_family_count: rankIndexes.map((ri) => {
         return {
           perc: r.get_perc_family(active_player_index, i, ri, true),